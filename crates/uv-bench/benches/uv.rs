@@ -3,8 +3,13 @@ use std::str::FromStr;
 
 use criterion::{Criterion, criterion_group, criterion_main, measurement::WallTime};
 use uv_cache::Cache;
+use uv_cache_info::CacheInfo;
 use uv_client::{BaseClientBuilder, RegistryClientBuilder};
-use uv_distribution_types::Requirement;
+use uv_distribution_types::{
+    ConfigSettings, ExtraBuildRequires, ExtraBuildVariables, Name, PackageConfigSettings,
+    Requirement,
+};
+use uv_installer::{InstallationStrategy, SatisfiesOptions, SitePackages};
 use uv_python::PythonEnvironment;
 use uv_resolver::Manifest;
 
@@ -43,11 +48,29 @@ fn resolve_warm_airflow(c: &mut Criterion<WallTime>) {
 //     c.bench_function("resolve_warm_airflow_universal", |b| b.iter(|| run(true)));
 // }
 
+fn satisfies_missing(c: &mut Criterion<WallTime>) {
+    let run = setup_satisfies();
+    c.bench_function("satisfies_missing", |b| b.iter(|| run(false)));
+}
+
+fn satisfies_missing_prefiltered(c: &mut Criterion<WallTime>) {
+    let run = setup_satisfies();
+    c.bench_function("satisfies_missing_prefiltered", |b| b.iter(|| run(true)));
+}
+
+fn cache_info_glob(c: &mut Criterion<WallTime>) {
+    let run = setup_cache_info();
+    c.bench_function("cache_info_glob", |b| b.iter(&run));
+}
+
 criterion_group!(
     uv,
     resolve_warm_jupyter,
     resolve_warm_jupyter_universal,
-    resolve_warm_airflow
+    resolve_warm_airflow,
+    satisfies_missing,
+    satisfies_missing_prefiltered,
+    cache_info_glob
 );
 criterion_main!(uv);
 
@@ -78,6 +101,66 @@ fn setup(manifest: Manifest) -> impl Fn(bool) {
     }
 }
 
+/// Compute the cache info for a source tree with thousands of files matched by a glob.
+fn setup_cache_info() -> impl Fn() {
+    let dir = tempfile::tempdir().unwrap();
+    for package in 0..40 {
+        let package = dir.path().join(format!("package_{package}"));
+        fs_err::create_dir_all(&package).unwrap();
+        for module in 0..100 {
+            fs_err::write(package.join(format!("module_{module}.py")), "").unwrap();
+        }
+    }
+    fs_err::write(
+        dir.path().join("pyproject.toml"),
+        "[tool.uv]\ncache-keys = [\"**/*.py\"]\n",
+    )
+    .unwrap();
+
+    move || {
+        CacheInfo::from_directory(black_box(dir.path())).unwrap();
+    }
+}
+
+/// Check every package installed in the environment, followed by a missing package that the full
+/// traversal only reaches after checking the installed ones.
+fn setup_satisfies() -> impl Fn(bool) {
+    let cache = Cache::from_path("../../.cache").init().unwrap();
+    let environment = PythonEnvironment::from_root("../../.venv", &cache).unwrap();
+    let site_packages = SitePackages::from_environment(&environment).unwrap();
+    let interpreter = environment.interpreter();
+    let markers = interpreter.resolver_marker_environment();
+    let tags = interpreter.tags().unwrap().clone();
+
+    let requirements = site_packages
+        .iter()
+        .map(|dist| dist.name().to_string())
+        .chain(std::iter::once("uv-bench-missing".to_string()))
+        .map(|name| Requirement::from(uv_pep508::Requirement::from_str(&name).unwrap()))
+        .collect::<Vec<_>>();
+
+    move |prefilter| {
+        site_packages
+            .satisfies_requirements_with_options(
+                black_box(requirements.iter()),
+                std::iter::empty(),
+                std::iter::empty(),
+                InstallationStrategy::Permissive,
+                &ConfigSettings::default(),
+                &PackageConfigSettings::default(),
+                &ExtraBuildRequires::default(),
+                &ExtraBuildVariables::default(),
+                &SatisfiesOptions {
+                    markers: Some(&markers),
+                    tags: Some(&tags),
+                    prefilter,
+                    ..SatisfiesOptions::default()
+                },
+            )
+            .unwrap();
+    }
+}
+
 mod resolver {
     use std::sync::LazyLock;
 
@@ -1,20 +1,30 @@
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use regex::Regex;
+use seahash::SeaHasher;
 use serde::Deserialize;
+use sha2::Digest;
 use tracing::{debug, warn};
 
+use uv_cache_key::{cache_digest, hash_digest};
 use uv_fs::Simplified;
+use uv_warnings::warn_user;
 
 use crate::git_info::{Commit, Tags};
-use crate::glob::cluster_globs;
-use crate::timestamp::Timestamp;
+use crate::glob::{GlobOptions, glob_files, is_glob};
+use crate::timestamp::{Timestamp, TimestampGranularity};
 
 #[derive(Debug, thiserror::Error)]
 pub enum CacheInfoError {
     #[error("Failed to parse glob patterns for `cache-keys`: {0}")]
     Glob(#[from] globwalk::GlobError),
+    #[error("Failed to parse glob patterns for `cache-keys`: {0}")]
+    Ignore(#[from] ignore::Error),
+    #[error("Failed to parse regular expression for `cache-keys`: {0}")]
+    Regex(#[from] regex::Error),
     #[error(transparent)]
     Io(#[from] std::io::Error),
 }
@@ -31,6 +41,14 @@ pub struct CacheInfo {
     timestamp: Option<Timestamp>,
     /// The commit at which the distribution was built.
     commit: Option<Commit>,
+    /// The commits for any paths requested via `{ git = true, path = "..." }`, keyed by path
+    /// (relative to the project directory).
+    ///
+    /// Each path is either the root of a separate repository (e.g., a submodule or vendored
+    /// tree), in which case its current commit is recorded, or a path within the enclosing
+    /// repository, in which case the ID of its tree at the current commit is recorded.
+    #[serde(default)]
+    commits: BTreeMap<PathBuf, Commit>,
     /// The Git tags present at the time of the build.
     tags: Option<Tags>,
     /// Environment variables to include in the cache key.
@@ -39,6 +57,126 @@ pub struct CacheInfo {
     /// The timestamp or inode of any directories that should be considered in the cache key.
     #[serde(default)]
     directories: BTreeMap<Cow<'static, str>, Option<DirectoryTimestamp>>,
+    /// The marker environment to include in the cache key, if requested via `cache-keys`.
+    #[serde(default)]
+    markers: Option<CacheMarkers>,
+    /// A digest of the build-relevant tables in the `pyproject.toml`, if requested via
+    /// `{ pyproject = true }`.
+    #[serde(default)]
+    pyproject: Option<String>,
+    /// A digest of the `[build-system].requires` list in the `pyproject.toml`, if requested via
+    /// `{ build-requires = true }`.
+    #[serde(default)]
+    build_requires: Option<String>,
+    /// Whether the distribution was built with build isolation, if known.
+    ///
+    /// Builds with and without isolation may produce different artifacts, since a non-isolated
+    /// build uses whatever build dependencies are installed in the environment.
+    #[serde(default)]
+    build_isolation: Option<bool>,
+    /// A digest of the hostname of the machine on which the cache info was computed, if requested.
+    ///
+    /// Timestamps are only comparable on the machine that recorded them; a cache copied from a
+    /// machine with a skewed clock could otherwise be considered fresh.
+    #[serde(default)]
+    host: Option<String>,
+    /// Arbitrary values to include in the cache key, as provided via
+    /// [`CacheInfoBuilder::literal`].
+    #[serde(default)]
+    literals: Vec<String>,
+    /// The digests of the contents of any files requested via `{ file = "...", hash = ... }`,
+    /// keyed by the file (or glob) as written in the cache key.
+    ///
+    /// Each digest is stored alongside the algorithm that produced it, such that digests
+    /// computed with different algorithms are never considered equal.
+    #[serde(default)]
+    hashes: BTreeMap<Cow<'static, str>, ContentHash>,
+    /// The digests of the values extracted from any files requested via
+    /// `{ file = "...", pattern = "..." }`, keyed by the file and then by the pattern.
+    ///
+    /// Files in which the pattern doesn't match are omitted.
+    #[serde(default)]
+    fields: BTreeMap<Cow<'static, str>, BTreeMap<String, String>>,
+    /// A digest of the cache keys from which the cache info was computed, if known.
+    ///
+    /// Cache info computed from the same keys can be compared key-by-key, such that a value that's
+    /// missing from one (e.g., the hash of a file that was since deleted) is a mismatch.
+    #[serde(default)]
+    keys: Option<String>,
+}
+
+/// The result of comparing two [`CacheInfo`]s via [`CacheInfo::compatible`].
+///
+/// Variants are ordered by precedence: a single mismatched key outweighs any number of keys that
+/// couldn't be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compatibility {
+    /// Every key is present in both, with equal values.
+    Match,
+    /// The keys present in both are equal, but the two were computed from different cache keys, or
+    /// some keys are only present in one for reasons unrelated to the project (e.g., a commit
+    /// recorded on a machine with Git, but not on one without), so they can't be fully compared.
+    Indeterminate,
+    /// At least one key is present in both, with different values.
+    Mismatch,
+}
+
+impl Compatibility {
+    /// Compare two optional values, returning `missing` if only one is present.
+    fn of<T: PartialEq>(left: Option<&T>, right: Option<&T>, missing: Self) -> Self {
+        match (left, right) {
+            (Some(left), Some(right)) if left == right => Self::Match,
+            (Some(_), Some(_)) => Self::Mismatch,
+            (None, None) => Self::Match,
+            (Some(_), None) | (None, Some(_)) => missing,
+        }
+    }
+
+    /// Compare two maps entry-by-entry, returning `missing` for entries only present in one.
+    fn of_map<K: Ord, V: PartialEq>(
+        left: &BTreeMap<K, V>,
+        right: &BTreeMap<K, V>,
+        missing: Self,
+    ) -> Self {
+        left.keys()
+            .chain(right.keys())
+            .map(|key| Self::of(left.get(key), right.get(key), missing))
+            .max()
+            .unwrap_or(Self::Match)
+    }
+}
+
+/// A digest of the contents of the files matched by a cache key.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ContentHash {
+    algorithm: ContentHashAlgorithm,
+    digest: String,
+}
+
+/// The subset of a marker environment that can be included in the cache key (via
+/// `{ markers = true }`), for build backends that produce different wheels per platform.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CacheMarkers {
+    sys_platform: String,
+    platform_machine: String,
+    python_full_version: String,
+}
+
+impl CacheMarkers {
+    /// Create a new [`CacheMarkers`] from the relevant marker values.
+    pub fn new(
+        sys_platform: impl Into<String>,
+        platform_machine: impl Into<String>,
+        python_full_version: impl Into<String>,
+    ) -> Self {
+        Self {
+            sys_platform: sys_platform.into(),
+            platform_machine: platform_machine.into(),
+            python_full_version: python_full_version.into(),
+        }
+    }
 }
 
 impl CacheInfo {
@@ -50,6 +188,148 @@ impl CacheInfo {
         }
     }
 
+    /// Returns `true` if the [`CacheInfo`] matches `other`, comparing any timestamps at the given
+    /// granularity.
+    ///
+    /// With [`TimestampGranularity::Exact`], this is equivalent to `==`.
+    pub fn matches(&self, other: &Self, granularity: TimestampGranularity) -> bool {
+        if !self.is_comparable(other) {
+            return false;
+        }
+        if granularity == TimestampGranularity::Exact {
+            return self == other;
+        }
+        self.clone().truncate(granularity) == other.clone().truncate(granularity)
+    }
+
+    /// Returns `true` if the timestamps in the [`CacheInfo`] are comparable to those in `other`,
+    /// i.e., if both were computed on the same host, or neither recorded a host.
+    ///
+    /// Cache info that isn't comparable never [matches](Self::matches).
+    pub fn is_comparable(&self, other: &Self) -> bool {
+        self.host == other.host
+    }
+
+    /// Compare the [`CacheInfo`] to `other`, distinguishing cache info that differs from cache
+    /// info that was computed with different keys (e.g., on machines with different tools
+    /// available), and so can't be meaningfully compared.
+    ///
+    /// Cache info computed from different cache keys is at best indeterminate, though a mismatch
+    /// in a key present in both still takes precedence. If both were computed from the same cache
+    /// keys, a file-based key that's only present in one (e.g., a file that was since deleted) is a
+    /// mismatch; a Git key that's only present in one (e.g., as computed on a machine without Git)
+    /// is indeterminate, leaving it to the caller to decide whether to trust the result. Cache info
+    /// computed on different hosts is always indeterminate.
+    pub fn compatible(&self, other: &Self) -> Compatibility {
+        if !self.is_comparable(other) {
+            return Compatibility::Indeterminate;
+        }
+        let (keys, missing) = match (&self.keys, &other.keys) {
+            (Some(left), Some(right)) if left == right => {
+                (Compatibility::Match, Compatibility::Mismatch)
+            }
+            (None, None) => (Compatibility::Match, Compatibility::Indeterminate),
+            _ => (Compatibility::Indeterminate, Compatibility::Indeterminate),
+        };
+        [
+            keys,
+            Compatibility::of(self.timestamp.as_ref(), other.timestamp.as_ref(), missing),
+            Compatibility::of(
+                self.commit.as_ref(),
+                other.commit.as_ref(),
+                Compatibility::Indeterminate,
+            ),
+            Compatibility::of_map(&self.commits, &other.commits, Compatibility::Indeterminate),
+            Compatibility::of(
+                self.tags.as_ref(),
+                other.tags.as_ref(),
+                Compatibility::Indeterminate,
+            ),
+            Compatibility::of_map(&self.env, &other.env, missing),
+            Compatibility::of_map(&self.directories, &other.directories, missing),
+            Compatibility::of(
+                self.markers.as_ref(),
+                other.markers.as_ref(),
+                Compatibility::Indeterminate,
+            ),
+            Compatibility::of(self.pyproject.as_ref(), other.pyproject.as_ref(), missing),
+            Compatibility::of(
+                self.build_requires.as_ref(),
+                other.build_requires.as_ref(),
+                missing,
+            ),
+            Compatibility::of(
+                self.build_isolation.as_ref(),
+                other.build_isolation.as_ref(),
+                Compatibility::Indeterminate,
+            ),
+            Compatibility::of(
+                (!self.literals.is_empty()).then_some(&self.literals),
+                (!other.literals.is_empty()).then_some(&other.literals),
+                Compatibility::Indeterminate,
+            ),
+            Compatibility::of_map(&self.hashes, &other.hashes, missing),
+            Compatibility::of_map(&self.fields, &other.fields, missing),
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(Compatibility::Match)
+    }
+
+    /// Record the identity of the host (via a digest of its hostname), such that cache info
+    /// computed on another machine (e.g., copied as part of a shared cache) is never considered
+    /// fresh, regardless of its timestamps.
+    #[must_use]
+    pub fn with_host(mut self, hostname: &str) -> Self {
+        self.host = Some(cache_digest(&hostname));
+        self
+    }
+
+    /// Returns whether the distribution was built with build isolation, if recorded.
+    pub fn build_isolation(&self) -> Option<bool> {
+        self.build_isolation
+    }
+
+    /// Record whether the distribution is built with build isolation, such that switching between
+    /// isolated and non-isolated builds (e.g., via `--no-build-isolation`) invalidates the cache.
+    #[must_use]
+    pub fn with_build_isolation(mut self, build_isolation: bool) -> Self {
+        self.build_isolation = Some(build_isolation);
+        self
+    }
+
+    /// Discard the timestamps in the [`CacheInfo`], such that it's keyed only on the presence of
+    /// any timestamped files and directories, along with its non-timestamp keys (e.g., the Git
+    /// commit).
+    ///
+    /// Equivalent to comparing with [`TimestampGranularity::Ignore`].
+    #[must_use]
+    pub fn without_timestamps(self) -> Self {
+        self.truncate(TimestampGranularity::Ignore)
+    }
+
+    /// Truncate any timestamps in the [`CacheInfo`] to the given granularity.
+    fn truncate(mut self, granularity: TimestampGranularity) -> Self {
+        self.timestamp = self
+            .timestamp
+            .map(|timestamp| timestamp.truncate(granularity));
+        for directory in self.directories.values_mut().flatten() {
+            match directory {
+                DirectoryTimestamp::Timestamp(timestamp) => {
+                    *timestamp = timestamp.truncate(granularity);
+                }
+                // The inode of a directory changes whenever it's recreated, so it's discarded
+                // along with the timestamps.
+                DirectoryTimestamp::Inode(_) if granularity == TimestampGranularity::Ignore => {
+                    *directory =
+                        DirectoryTimestamp::Timestamp(Timestamp::from(std::time::UNIX_EPOCH));
+                }
+                DirectoryTimestamp::Inode(_) => {}
+            }
+        }
+        self
+    }
+
     /// Compute the cache info for a given path, which may be a file or a directory.
     pub fn from_path(path: &Path) -> Result<Self, CacheInfoError> {
         let metadata = fs_err::metadata(path)?;
@@ -60,53 +340,202 @@ impl CacheInfo {
         }
     }
 
+    /// Compute the cache info for a given path, including the given marker environment if the
+    /// project requests it via `{ markers = true }`.
+    pub fn from_path_with_markers(
+        path: &Path,
+        markers: &CacheMarkers,
+    ) -> Result<Self, CacheInfoError> {
+        let metadata = fs_err::metadata(path)?;
+        if metadata.is_file() {
+            Ok(Self::from_file(path)?)
+        } else {
+            Self::from_directory_with_markers(path, markers)
+        }
+    }
+
     /// Compute the cache info for a given directory.
+    ///
+    /// The cache keys are read from the nearest `pyproject.toml` or `uv.toml`, starting with the
+    /// directory itself and continuing through its parents, and any relative paths are resolved
+    /// against the directory containing that file. If no such file defines cache keys, the
+    /// default keys are resolved against the given directory.
+    ///
+    /// If `SOURCE_DATE_EPOCH` is set, any timestamps older than it are clamped to it, since
+    /// reproducible-build tooling may have rewritten them.
     pub fn from_directory(directory: &Path) -> Result<Self, CacheInfoError> {
+        Self::from_directory_with_floor(directory, Timestamp::from_source_date_epoch(), None)
+    }
+
+    /// Compute the cache info for a given directory, including the given marker environment if
+    /// the project requests it via `{ markers = true }`.
+    pub fn from_directory_with_markers(
+        directory: &Path,
+        markers: &CacheMarkers,
+    ) -> Result<Self, CacheInfoError> {
+        Self::from_directory_with_floor(
+            directory,
+            Timestamp::from_source_date_epoch(),
+            Some(markers),
+        )
+    }
+
+    /// Compute the cache info for a given directory from the default cache keys (the
+    /// `pyproject.toml`, `setup.py`, and `setup.cfg` files, and the presence of a `src`
+    /// directory), without reading any `pyproject.toml` or `uv.toml` for custom `cache-keys`.
+    ///
+    /// This is equivalent to [`CacheInfo::from_directory`] for a project that doesn't define any
+    /// cache keys, but avoids the I/O and parsing, e.g., for callers that already know that none
+    /// are defined.
+    pub fn from_directory_defaults_only(directory: &Path) -> Result<Self, CacheInfoError> {
+        Self::from_cache_keys(
+            directory,
+            default_cache_keys(),
+            Vec::new(),
+            Timestamp::from_source_date_epoch(),
+            None,
+            Timestamp::now(),
+        )
+    }
+
+    /// Return a [`CacheInfoBuilder`] to compose the cache info for the given directory from
+    /// individual cache keys, rather than those declared in its `pyproject.toml`.
+    pub fn builder(directory: impl Into<PathBuf>) -> CacheInfoBuilder {
+        CacheInfoBuilder::new(directory)
+    }
+
+    /// Compute the cache info for a given directory, clamping any timestamps to the given floor.
+    fn from_directory_with_floor(
+        directory: &Path,
+        floor: Option<Timestamp>,
+        markers: Option<&CacheMarkers>,
+    ) -> Result<Self, CacheInfoError> {
+        // Find the configuration that supplies the cache keys (e.g., if the directory is nested
+        // within the project, as in some `src` layouts), and resolve any paths relative to it.
+        //
+        // The search stops at the first project (i.e., a directory with a `setup.py` or
+        // `setup.cfg`), or at the root of the repository, to avoid picking up the configuration of
+        // an unrelated enclosing project.
+        let mut config = None;
+        for ancestor in directory.ancestors() {
+            if ancestor.join("uv.toml").is_file() || ancestor.join("pyproject.toml").is_file() {
+                config = Some(ancestor);
+                break;
+            }
+            if ancestor.join("setup.py").is_file()
+                || ancestor.join("setup.cfg").is_file()
+                || ancestor.join(".git").exists()
+            {
+                break;
+            }
+        }
+        if let Some((config, cache_keys)) =
+            config.and_then(|config| Some((config, read_cache_keys(config)?)))
+        {
+            return Self::from_cache_keys(
+                config,
+                cache_keys,
+                Vec::new(),
+                floor,
+                markers,
+                Timestamp::now(),
+            );
+        }
+
+        // If no cache keys were defined, use the defaults. An explicit list of cache keys (even an
+        // empty one) replaces the defaults entirely, rather than extending them.
+        Self::from_cache_keys(
+            directory,
+            default_cache_keys(),
+            Vec::new(),
+            floor,
+            markers,
+            Timestamp::now(),
+        )
+    }
+
+    /// Compute the cache info for a given directory from the given cache keys and literal values,
+    /// clamping any timestamps to the given floor.
+    ///
+    /// Any file timestamps in the future, relative to `now`, are clamped to `now`.
+    fn from_cache_keys(
+        directory: &Path,
+        cache_keys: Vec<CacheKey>,
+        literals: Vec<String>,
+        floor: Option<Timestamp>,
+        markers: Option<&CacheMarkers>,
+        now: Timestamp,
+    ) -> Result<Self, CacheInfoError> {
         let mut commit = None;
+        let mut commits = BTreeMap::new();
         let mut tags = None;
         let mut last_changed: Option<(PathBuf, Timestamp)> = None;
         let mut directories = BTreeMap::new();
         let mut env = BTreeMap::new();
-
-        // Read the cache keys.
-        let cache_keys =
-            if let Ok(contents) = fs_err::read_to_string(directory.join("pyproject.toml")) {
-                if let Ok(pyproject_toml) = toml::from_str::<PyProjectToml>(&contents) {
-                    pyproject_toml
-                        .tool
-                        .and_then(|tool| tool.uv)
-                        .and_then(|tool_uv| tool_uv.cache_keys)
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-
-        // If no cache keys were defined, use the defaults.
-        let cache_keys = cache_keys.unwrap_or_else(|| {
-            vec![
-                CacheKey::Path(Cow::Borrowed("pyproject.toml")),
-                CacheKey::Path(Cow::Borrowed("setup.py")),
-                CacheKey::Path(Cow::Borrowed("setup.cfg")),
-                CacheKey::Directory {
-                    dir: Cow::Borrowed("src"),
-                },
-            ]
-        });
+        let mut cache_markers = None;
+        let mut pyproject = None;
+        let mut build_requires = None;
+        let mut host = None;
+        let mut hashes = BTreeMap::new();
+        let mut fields: BTreeMap<Cow<'static, str>, BTreeMap<String, String>> = BTreeMap::new();
+        let keys = hash_digest(&cache_keys);
 
         // Incorporate timestamps from any direct filepaths.
-        let mut globs = vec![];
+        //
+        // Globs are grouped by their [`GlobOptions`], such that each group can be expanded in a
+        // single pass. (A file listed explicitly, rather than via a glob, is never ignored or
+        // excluded.)
+        let mut globs: BTreeMap<GlobOptions, Vec<Cow<'static, str>>> = BTreeMap::new();
         for cache_key in cache_keys {
+            // Incorporate the contents of any files that are keyed by hash, rather than timestamp.
+            if let CacheKey::File {
+                file,
+                respect_gitignore,
+                include_bytecode,
+                anchored,
+                hash: Some(hash),
+            } = &cache_key
+            {
+                if let Some(algorithm) = hash.algorithm() {
+                    let options = GlobOptions {
+                        respect_gitignore: *respect_gitignore,
+                        include_bytecode: *include_bytecode,
+                        anchored: *anchored,
+                    };
+                    if let Some(hash) = content_hash(directory, file, options, algorithm)? {
+                        hashes.insert(file.clone(), hash);
+                    }
+                    continue;
+                }
+            }
+
             match cache_key {
-                CacheKey::Path(file) | CacheKey::File { file } => {
-                    if file
-                        .as_ref()
-                        .chars()
-                        .any(|c| matches!(c, '*' | '?' | '[' | '{'))
-                    {
+                CacheKey::FileField { file, pattern } => {
+                    if let Some(digest) = field_digest(directory, &file, &pattern)? {
+                        fields.entry(file).or_default().insert(pattern, digest);
+                    }
+                }
+                CacheKey::File {
+                    file,
+                    respect_gitignore,
+                    include_bytecode,
+                    anchored,
+                    ..
+                } if is_glob(&file) => {
+                    // Defer globs to a separate pass.
+                    globs
+                        .entry(GlobOptions {
+                            respect_gitignore,
+                            include_bytecode,
+                            anchored,
+                        })
+                        .or_default()
+                        .push(file);
+                }
+                CacheKey::Path(file) | CacheKey::File { file, .. } => {
+                    if is_glob(&file) {
                         // Defer globs to a separate pass.
-                        globs.push(file);
+                        globs.entry(GlobOptions::default()).or_default().push(file);
                         continue;
                     }
 
@@ -136,7 +565,7 @@ impl CacheInfo {
                         last_changed = Some((path, timestamp));
                     }
                 }
-                CacheKey::Directory { dir } => {
+                CacheKey::Directory { dir, entries } => {
                     // Treat the path as a directory.
                     let path = directory.join(dir.as_ref());
                     let metadata = match path.metadata() {
@@ -158,6 +587,18 @@ impl CacheInfo {
                         continue;
                     }
 
+                    // Adding, removing, or renaming an entry in the directory changes its own
+                    // timestamp, even if no file within it changed.
+                    if entries {
+                        let timestamp = Timestamp::from_metadata(&metadata);
+                        if last_changed
+                            .as_ref()
+                            .is_none_or(|(_, prev_timestamp)| *prev_timestamp < timestamp)
+                        {
+                            last_changed = Some((path.clone(), timestamp));
+                        }
+                    }
+
                     if let Ok(created) = metadata.created() {
                         // Prefer the creation time.
                         directories.insert(
@@ -183,21 +624,27 @@ impl CacheInfo {
                 }
                 CacheKey::Git {
                     git: GitPattern::Bool(true),
-                } => match Commit::from_repository(directory) {
-                    Ok(commit_info) => commit = Some(commit_info),
-                    Err(err) => {
-                        debug!("Failed to read the current commit: {err}");
+                    path,
+                } => {
+                    if let Some(path) = path {
+                        if let Some(commit_info) = read_path_commit(directory, &path) {
+                            commits.insert(PathBuf::from(path), commit_info);
+                        }
+                    } else if let Some(commit_info) = read_commit(directory) {
+                        commit = Some(commit_info);
                     }
-                },
+                }
                 CacheKey::Git {
                     git: GitPattern::Set(set),
+                    path,
                 } => {
                     if set.commit.unwrap_or(false) {
-                        match Commit::from_repository(directory) {
-                            Ok(commit_info) => commit = Some(commit_info),
-                            Err(err) => {
-                                debug!("Failed to read the current commit: {err}");
+                        if let Some(path) = path {
+                            if let Some(commit_info) = read_path_commit(directory, &path) {
+                                commits.insert(PathBuf::from(path), commit_info);
                             }
+                        } else if let Some(commit_info) = read_commit(directory) {
+                            commit = Some(commit_info);
                         }
                     }
                     if set.tags.unwrap_or(false) {
@@ -211,93 +658,134 @@ impl CacheInfo {
                 }
                 CacheKey::Git {
                     git: GitPattern::Bool(false),
+                    ..
                 } => {}
                 CacheKey::Environment { env: var } => {
                     let value = std::env::var(&var).ok();
                     env.insert(var, value);
                 }
-            }
-        }
-
-        // If we have any globs, first cluster them using LCP and then do a single pass on each group.
-        if !globs.is_empty() {
-            for (glob_base, glob_patterns) in cluster_globs(&globs) {
-                let walker = globwalk::GlobWalkerBuilder::from_patterns(
-                    directory.join(glob_base),
-                    &glob_patterns,
-                )
-                .file_type(globwalk::FileType::FILE | globwalk::FileType::SYMLINK)
-                .build()?;
-                for entry in walker {
-                    let entry = match entry {
-                        Ok(entry) => entry,
+                CacheKey::Markers { markers: true } => {
+                    if let Some(markers) = markers {
+                        cache_markers = Some(markers.clone());
+                    } else {
+                        debug!("Ignoring `markers` cache key without a marker environment");
+                    }
+                }
+                CacheKey::Markers { markers: false } => {}
+                cache_key @ (CacheKey::PyProject { pyproject: true }
+                | CacheKey::BuildRequires {
+                    build_requires: true,
+                }) => {
+                    let path = directory.join("pyproject.toml");
+                    let contents = match fs_err::read_to_string(&path) {
+                        Ok(contents) => contents,
+                        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                            continue;
+                        }
                         Err(err) => {
-                            warn!("Failed to read glob entry: {err}");
+                            warn!("Failed to read `pyproject.toml`: {err}");
                             continue;
                         }
                     };
-                    let metadata = if entry.path_is_symlink() {
-                        // resolve symlinks for leaf entries without following symlinks while globbing
-                        match fs_err::metadata(entry.path()) {
-                            Ok(metadata) => metadata,
-                            Err(err) => {
-                                warn!("Failed to resolve symlink for glob entry: {err}");
-                                continue;
-                            }
-                        }
+                    let parsed = if matches!(cache_key, CacheKey::PyProject { .. }) {
+                        pyproject_build_digest(&contents).map(|digest| pyproject = Some(digest))
                     } else {
-                        match entry.metadata() {
-                            Ok(metadata) => metadata,
-                            Err(err) => {
-                                warn!("Failed to read metadata for glob entry: {err}");
-                                continue;
-                            }
-                        }
+                        build_requires_digest(&contents).map(|digest| build_requires = digest)
                     };
-                    if !metadata.is_file() {
-                        if !entry.path_is_symlink() {
-                            // don't warn if it was a symlink - it may legitimately resolve to a directory
-                            warn!(
-                                "Expected file for cache key, but found directory: `{}`",
-                                entry.path().display()
-                            );
+                    if let Err(err) = parsed {
+                        // If the `pyproject.toml` can't be parsed, fall back to its timestamp.
+                        debug!(
+                            "Failed to parse `pyproject.toml`, falling back to its timestamp: {err}"
+                        );
+                        let timestamp = Timestamp::from_path(&path)?;
+                        if last_changed
+                            .as_ref()
+                            .is_none_or(|(_, prev_timestamp)| *prev_timestamp < timestamp)
+                        {
+                            last_changed = Some((path, timestamp));
                         }
-                        continue;
                     }
-                    let timestamp = Timestamp::from_metadata(&metadata);
-                    if last_changed.as_ref().is_none_or(|(_, prev_timestamp)| {
-                        *prev_timestamp < Timestamp::from_metadata(&metadata)
-                    }) {
-                        last_changed = Some((entry.into_path(), timestamp));
+                }
+                CacheKey::PyProject { pyproject: false } => {}
+                CacheKey::BuildRequires {
+                    build_requires: false,
+                } => {}
+                CacheKey::Host { host: true } => {
+                    if let Some(hostname) = hostname() {
+                        host = Some(cache_digest(&hostname));
+                    } else {
+                        debug!("Ignoring `host` cache key: failed to determine the hostname");
                     }
                 }
+                CacheKey::Host { host: false } => {}
+            }
+        }
+
+        // If we have any globs, first cluster them using LCP and then do a single pass on each group.
+        for (options, globs) in globs {
+            for (path, metadata) in glob_files(directory, &globs, options)? {
+                let timestamp = Timestamp::from_metadata(&metadata);
+                if last_changed
+                    .as_ref()
+                    .is_none_or(|(_, prev_timestamp)| *prev_timestamp < timestamp)
+                {
+                    last_changed = Some((path, timestamp));
+                }
             }
         }
 
         let timestamp = if let Some((path, timestamp)) = last_changed {
             debug!(
-                "Computed cache info: {timestamp:?}, {commit:?}, {tags:?}, {env:?}, {directories:?}. Most recently modified: {}",
+                "Computed cache info: {timestamp:?}, {commit:?}, {commits:?}, {tags:?}, {env:?}, {directories:?}. Most recently modified: {}",
                 path.user_display()
             );
-            Some(timestamp)
+            Some(clamp_future(&path, timestamp, now))
         } else {
             None
         };
 
+        // Clamp any timestamps to the floor.
+        let timestamp =
+            timestamp.map(|timestamp| floor.map_or(timestamp, |floor| timestamp.max(floor)));
+        if let Some(floor) = floor {
+            for directory in directories.values_mut().flatten() {
+                if let DirectoryTimestamp::Timestamp(timestamp) = directory {
+                    *timestamp = (*timestamp).max(floor);
+                }
+            }
+        }
+
         Ok(Self {
             timestamp,
             commit,
+            commits,
             tags,
             env,
             directories,
+            markers: cache_markers,
+            pyproject,
+            build_requires,
+            build_isolation: None,
+            host,
+            literals,
+            hashes,
+            fields,
+            keys: Some(keys),
         })
     }
 
     /// Compute the cache info for a given file, assumed to be a binary or source distribution
     /// represented as (e.g.) a `.whl` or `.tar.gz` archive.
+    ///
+    /// If the file's timestamp is in the future (e.g., due to clock skew), it's clamped to the
+    /// current time.
     pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
         let metadata = fs_err::metadata(path.as_ref())?;
-        let timestamp = Timestamp::from_metadata(&metadata);
+        let timestamp = clamp_future(
+            path.as_ref(),
+            Timestamp::from_metadata(&metadata),
+            Timestamp::now(),
+        );
         Ok(Self {
             timestamp: Some(timestamp),
             ..Self::default()
@@ -308,12 +796,496 @@ impl CacheInfo {
     pub fn is_empty(&self) -> bool {
         self.timestamp.is_none()
             && self.commit.is_none()
+            && self.commits.is_empty()
             && self.tags.is_none()
             && self.env.is_empty()
             && self.directories.is_empty()
+            && self.markers.is_none()
+            && self.pyproject.is_none()
+            && self.build_requires.is_none()
+            && self.build_isolation.is_none()
+            && self.host.is_none()
+            && self.literals.is_empty()
+            && self.hashes.is_empty()
+            && self.fields.is_empty()
+    }
+
+    /// Return a stable digest over every recorded key, e.g., for use as the key in a
+    /// content-addressed store.
+    ///
+    /// Identical cache info always produces the same digest, while a change to any field produces
+    /// a different one. Returns `None` if the cache info is empty.
+    pub fn digest(&self) -> Option<String> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(hash_digest(self))
+        }
+    }
+}
+
+/// A builder for a [`CacheInfo`], composed from individual cache keys in Rust rather than read
+/// from the `tool.uv.cache-keys` field of a `pyproject.toml`.
+///
+/// Each method mirrors a [`CacheKey`] variant, and relative paths are resolved against the
+/// builder's directory, as in [`CacheInfo::from_directory`].
+#[derive(Debug, Clone)]
+pub struct CacheInfoBuilder {
+    directory: PathBuf,
+    cache_keys: Vec<CacheKey>,
+    literals: Vec<String>,
+    markers: Option<CacheMarkers>,
+    build_isolation: Option<bool>,
+    host: Option<String>,
+    ignore_timestamps: bool,
+}
+
+impl CacheInfoBuilder {
+    /// Create a new [`CacheInfoBuilder`] for the given directory, without any cache keys.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            cache_keys: Vec::new(),
+            literals: Vec::new(),
+            markers: None,
+            build_isolation: None,
+            host: None,
+            ignore_timestamps: false,
+        }
+    }
+
+    /// Include the timestamp of a file, or of any files matching a glob (e.g., `**/*.toml`).
+    #[must_use]
+    pub fn file(mut self, file: impl Into<String>) -> Self {
+        self.cache_keys.push(CacheKey::File {
+            file: Cow::Owned(file.into()),
+            respect_gitignore: false,
+            include_bytecode: false,
+            anchored: false,
+            hash: None,
+        });
+        self
+    }
+
+    /// Include the contents of a file, or of any files matching a glob, as hashed with the given
+    /// algorithm.
+    #[must_use]
+    pub fn file_hash(mut self, file: impl Into<String>, algorithm: ContentHashAlgorithm) -> Self {
+        self.cache_keys.push(CacheKey::File {
+            file: Cow::Owned(file.into()),
+            respect_gitignore: false,
+            include_bytecode: false,
+            anchored: false,
+            hash: Some(HashPattern::Algorithm(algorithm)),
+        });
+        self
+    }
+
+    /// Include the value extracted from a file by the given regular expression (i.e., its first
+    /// capture group, or the entire match), rather than the file's timestamp.
+    #[must_use]
+    pub fn file_field(mut self, file: impl Into<String>, pattern: impl Into<String>) -> Self {
+        self.cache_keys.push(CacheKey::FileField {
+            file: Cow::Owned(file.into()),
+            pattern: pattern.into(),
+        });
+        self
+    }
+
+    /// Include the presence and entries of a directory.
+    #[must_use]
+    pub fn directory(mut self, dir: impl Into<String>) -> Self {
+        self.cache_keys.push(CacheKey::Directory {
+            dir: Cow::Owned(dir.into()),
+            entries: true,
+        });
+        self
+    }
+
+    /// Include the value of an environment variable.
+    #[must_use]
+    pub fn env(mut self, env: impl Into<String>) -> Self {
+        self.cache_keys
+            .push(CacheKey::Environment { env: env.into() });
+        self
+    }
+
+    /// Include the Git tree of the given path at the current commit or, if the path is the root of
+    /// its own repository, that repository's current commit.
+    #[must_use]
+    pub fn git(mut self, path: impl Into<String>) -> Self {
+        self.cache_keys.push(CacheKey::Git {
+            git: GitPattern::Bool(true),
+            path: Some(path.into()),
+        });
+        self
+    }
+
+    /// Include an arbitrary value, such that changing it invalidates the cache.
+    #[must_use]
+    pub fn literal(mut self, literal: impl Into<String>) -> Self {
+        self.literals.push(literal.into());
+        self
+    }
+
+    /// Include the given marker environment.
+    #[must_use]
+    pub fn markers(mut self, markers: CacheMarkers) -> Self {
+        self.cache_keys.push(CacheKey::Markers { markers: true });
+        self.markers = Some(markers);
+        self
+    }
+
+    /// Include whether the distribution is built with build isolation.
+    #[must_use]
+    pub fn build_isolation(mut self, build_isolation: bool) -> Self {
+        self.build_isolation = Some(build_isolation);
+        self
+    }
+
+    /// Include the identity of the host with the given hostname.
+    #[must_use]
+    pub fn host(mut self, hostname: impl Into<String>) -> Self {
+        self.host = Some(hostname.into());
+        self
+    }
+
+    /// Discard the timestamps of any files and directories, such that the cache info is keyed
+    /// only on their presence, along with the other keys (e.g., the Git commit).
+    ///
+    /// See [`CacheInfo::without_timestamps`].
+    #[must_use]
+    pub fn ignore_timestamps(mut self) -> Self {
+        self.ignore_timestamps = true;
+        self
+    }
+
+    /// Compute the [`CacheInfo`] from the cache keys.
+    ///
+    /// If `SOURCE_DATE_EPOCH` is set, any timestamps older than it are clamped to it.
+    pub fn build(self) -> Result<CacheInfo, CacheInfoError> {
+        let cache_info = CacheInfo::from_cache_keys(
+            &self.directory,
+            self.cache_keys,
+            self.literals,
+            Timestamp::from_source_date_epoch(),
+            self.markers.as_ref(),
+            Timestamp::now(),
+        )?;
+        let cache_info = match self.build_isolation {
+            Some(build_isolation) => cache_info.with_build_isolation(build_isolation),
+            None => cache_info,
+        };
+        let cache_info = match self.host {
+            Some(hostname) => cache_info.with_host(&hostname),
+            None => cache_info,
+        };
+        Ok(if self.ignore_timestamps {
+            cache_info.without_timestamps()
+        } else {
+            cache_info
+        })
+    }
+}
+
+/// The amount by which a file's timestamp may exceed the current time before it's considered to be
+/// in the future, to allow for minor drift between (e.g.) a network filesystem and the local clock.
+const FUTURE_TOLERANCE: Duration = Duration::from_secs(60);
+
+/// Clamp a file's timestamp to `now` if it's in the future (e.g., due to clock skew or a file
+/// restored from a backup), warning the user.
+///
+/// A timestamp in the future would exceed any timestamp recorded until the clock catches up, such
+/// that subsequent changes to the file would go undetected. Once clamped, the recorded timestamp
+/// instead changes on every run, which errs on the side of rebuilding.
+fn clamp_future(path: &Path, timestamp: Timestamp, now: Timestamp) -> Timestamp {
+    if timestamp.is_after(now, FUTURE_TOLERANCE) {
+        warn_user!(
+            "The modification time of `{}` is in the future; the system clock may have changed, or the file may have been restored from a backup",
+            path.user_display()
+        );
+        now
+    } else {
+        timestamp
+    }
+}
+
+/// The cache keys used if a project doesn't define any.
+fn default_cache_keys() -> Vec<CacheKey> {
+    vec![
+        CacheKey::Path(Cow::Borrowed("pyproject.toml")),
+        CacheKey::Path(Cow::Borrowed("setup.py")),
+        CacheKey::Path(Cow::Borrowed("setup.cfg")),
+        // Only track the presence of `src`, rather than its entries, since adding or
+        // removing a module rarely affects the build.
+        CacheKey::Directory {
+            dir: Cow::Borrowed("src"),
+            entries: false,
+        },
+    ]
+}
+
+/// Read the cache keys defined in the given directory's `uv.toml` or `pyproject.toml`, if any.
+///
+/// As elsewhere in uv, a `uv.toml` takes precedence over the `[tool.uv]` section of the
+/// `pyproject.toml`; the two are never merged.
+fn read_cache_keys(directory: &Path) -> Option<Vec<CacheKey>> {
+    if let Ok(contents) = fs_err::read_to_string(directory.join("uv.toml")) {
+        if let Some(cache_keys) = toml::from_str::<ToolUv>(&contents)
+            .ok()
+            .and_then(|uv_toml| uv_toml.cache_keys)
+        {
+            return Some(cache_keys);
+        }
+    }
+    let contents = fs_err::read_to_string(directory.join("pyproject.toml")).ok()?;
+    toml::from_str::<PyProjectToml>(&contents)
+        .ok()?
+        .tool
+        .and_then(|tool| tool.uv)
+        .and_then(|tool_uv| tool_uv.cache_keys)
+}
+
+/// Returns the hostname of the current machine, if it can be determined.
+fn hostname() -> Option<String> {
+    #[cfg(unix)]
+    let hostname = rustix::system::uname()
+        .nodename()
+        .to_str()
+        .ok()
+        .map(ToString::to_string);
+    #[cfg(not(unix))]
+    let hostname = std::env::var("COMPUTERNAME").ok();
+    hostname.filter(|hostname| !hostname.is_empty())
+}
+
+/// Read the commit to include in the cache key for a path given via
+/// `{ git = true, path = "..." }`.
+///
+/// If the path is the root of its own repository (e.g., a submodule or a vendored tree with its
+/// own history), returns that repository's current commit. Otherwise, returns the ID of the path's
+/// tree at the current commit of the enclosing repository, falling back to the current commit.
+fn read_path_commit(directory: &Path, path: &str) -> Option<Commit> {
+    let root = directory.join(path);
+    if root.join(".git").exists() {
+        return match Commit::from_repository(&root) {
+            Ok(commit) => Some(commit),
+            Err(err) => {
+                debug!("Failed to read the current commit for `{path}`: {err}");
+                None
+            }
+        };
+    }
+    match Commit::from_path(directory, path) {
+        Ok(commit) => Some(commit),
+        Err(err) => {
+            debug!("Failed to read the Git tree for `{path}`, falling back to `HEAD`: {err}");
+            read_commit(directory)
+        }
+    }
+}
+
+/// The tables in a `pyproject.toml` that may affect the build, as opposed to (e.g.) the
+/// configuration for formatters and linters.
+const PYPROJECT_BUILD_TABLES: &[&str] = &[
+    "build-system",
+    "project",
+    "tool.flit",
+    "tool.hatch",
+    "tool.maturin",
+    "tool.meson-python",
+    "tool.pdm",
+    "tool.poetry",
+    "tool.scikit-build",
+    "tool.setuptools",
+    "tool.setuptools_scm",
+    "tool.uv.build-backend",
+];
+
+/// Compute a digest of the build-relevant tables in a `pyproject.toml`.
+fn pyproject_build_digest(contents: &str) -> Result<String, toml::de::Error> {
+    let pyproject_toml = toml::from_str::<toml::Table>(contents)?;
+    let tables = PYPROJECT_BUILD_TABLES
+        .iter()
+        .filter_map(|table| {
+            let mut parts = table.split('.');
+            let mut value = pyproject_toml.get(parts.next()?)?;
+            for part in parts {
+                value = value.get(part)?;
+            }
+            Some((*table, value.to_string()))
+        })
+        .collect::<Vec<_>>();
+    Ok(cache_digest(&tables))
+}
+
+/// Compute a digest of the `[build-system].requires` list in a `pyproject.toml`, or `None` if the
+/// list is absent.
+///
+/// The order of the requirements is ignored.
+fn build_requires_digest(contents: &str) -> Result<Option<String>, toml::de::Error> {
+    let pyproject_toml = toml::from_str::<BuildSystemToml>(contents)?;
+    let Some(mut requires) = pyproject_toml
+        .build_system
+        .and_then(|build_system| build_system.requires)
+    else {
+        return Ok(None);
+    };
+    for requirement in &mut requires {
+        *requirement = requirement.trim().to_string();
+    }
+    requires.sort_unstable();
+    Ok(Some(cache_digest(&requires)))
+}
+
+/// Compute a digest of the value extracted from a file by a `{ file = "...", pattern = "..." }`
+/// cache key, i.e., the first capture group of the first match (or the entire match, if the
+/// pattern has no capture groups).
+///
+/// Returns `None` if the file doesn't exist, or if the pattern doesn't match.
+fn field_digest(
+    directory: &Path,
+    file: &str,
+    pattern: &str,
+) -> Result<Option<String>, CacheInfoError> {
+    let regex = Regex::new(pattern)?;
+    let path = directory.join(file);
+    let contents = match fs_err::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(None);
+        }
+        Err(err) => {
+            warn!("Failed to read file for cache key: {err}");
+            return Ok(None);
+        }
+    };
+    let Some(captures) = regex.captures(&contents) else {
+        debug!(
+            "Pattern `{pattern}` didn't match any value in `{}`; ignoring cache key",
+            path.user_display()
+        );
+        return Ok(None);
+    };
+    let value = captures
+        .get(1)
+        .or_else(|| captures.get(0))
+        .map_or("", |value| value.as_str());
+    Ok(Some(cache_digest(&value)))
+}
+
+/// Compute a digest of the contents of the files matched by a `{ file = "...", hash = ... }` cache
+/// key, or `None` if the key refers to a single file that doesn't exist.
+///
+/// Each file's path (relative to `directory`) is hashed alongside its contents, in sorted order,
+/// such that renaming a file changes the digest, but the order in which files are found doesn't.
+fn content_hash(
+    directory: &Path,
+    file: &str,
+    options: GlobOptions,
+    algorithm: ContentHashAlgorithm,
+) -> Result<Option<ContentHash>, CacheInfoError> {
+    let mut paths = if is_glob(file) {
+        glob_files(directory, &[file], options)?
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>()
+    } else {
+        let path = directory.join(file);
+        match path.metadata() {
+            Ok(metadata) if metadata.is_file() => vec![path],
+            Ok(_) => {
+                warn!(
+                    "Expected file for cache key, but found directory: `{}`",
+                    path.display()
+                );
+                return Ok(None);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                warn!("Failed to read metadata for file: {err}");
+                return Ok(None);
+            }
+        }
+    };
+    paths.sort_unstable();
+
+    let mut hasher = ContentHasher::from(algorithm);
+    for path in paths {
+        let contents = fs_err::read(&path)?;
+        let relative = path.strip_prefix(directory).unwrap_or(&path);
+        let relative = relative.to_string_lossy();
+        hasher.update(&(relative.len() as u64).to_le_bytes());
+        hasher.update(relative.as_bytes());
+        hasher.update(&(contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(Some(ContentHash {
+        algorithm,
+        digest: hasher.finalize(),
+    }))
+}
+
+/// An incremental hasher for a [`ContentHashAlgorithm`].
+enum ContentHasher {
+    Seahash(SeaHasher),
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl ContentHasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Seahash(hasher) => std::hash::Hasher::write(hasher, data),
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// Return the hex-encoded digest.
+    fn finalize(self) -> String {
+        match self {
+            Self::Seahash(hasher) => format!("{:016x}", std::hash::Hasher::finish(&hasher)),
+            Self::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+impl From<ContentHashAlgorithm> for ContentHasher {
+    fn from(algorithm: ContentHashAlgorithm) -> Self {
+        match algorithm {
+            ContentHashAlgorithm::Seahash => Self::Seahash(SeaHasher::new()),
+            ContentHashAlgorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+            ContentHashAlgorithm::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+        }
     }
 }
 
+/// Read the current commit to include in the cache key.
+///
+/// If the directory isn't in a Git repository (e.g., a source distribution produced by
+/// `git archive`), falls back to the commit recorded in `.git_archival.txt`, if any.
+fn read_commit(directory: &Path) -> Option<Commit> {
+    match Commit::from_repository(directory) {
+        Ok(commit) => return Some(commit),
+        Err(err) => {
+            debug!("Failed to read the current commit: {err}");
+        }
+    }
+    if directory.join(".git_archival.txt").is_file() {
+        match Commit::from_git_archival(directory) {
+            Ok(commit) => return Some(commit),
+            Err(err) => {
+                debug!("Failed to read the commit from `.git_archival.txt`: {err}");
+            }
+        }
+    }
+    None
+}
+
 /// A `pyproject.toml` with an (optional) `[tool.uv]` section.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -327,29 +1299,209 @@ struct Tool {
     uv: Option<ToolUv>,
 }
 
+/// A `pyproject.toml` with an (optional) `[build-system]` section.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BuildSystemToml {
+    build_system: Option<BuildSystem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BuildSystem {
+    requires: Option<Vec<String>>,
+}
+
+/// The `[tool.uv]` section of a `pyproject.toml`, or the top level of a `uv.toml`.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 struct ToolUv {
     cache_keys: Option<Vec<CacheKey>>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, Hash, serde::Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(untagged, rename_all = "kebab-case", deny_unknown_fields)]
 pub enum CacheKey {
     /// Ex) `"Cargo.lock"` or `"**/*.toml"`
     Path(Cow<'static, str>),
-    /// Ex) `{ file = "Cargo.lock" }` or `{ file = "**/*.toml" }`
-    File { file: Cow<'static, str> },
-    /// Ex) `{ dir = "src" }`
-    Directory { dir: Cow<'static, str> },
-    /// Ex) `{ git = true }` or `{ git = { commit = true, tags = false } }`
-    Git { git: GitPattern },
+    // Declared before `File`, since untagged variants are tried in order, and `File` would
+    // otherwise reject the `pattern` field.
+    /// Ex) `{ file = "src/foo/__init__.py", pattern = '__version__ = "(.+)"' }`
+    FileField {
+        file: Cow<'static, str>,
+        /// A regular expression to search for in the file. Only the first capture group of the
+        /// first match (or the entire match, if the pattern has no capture groups) is included
+        /// in the cache key.
+        pattern: String,
+    },
+    /// Ex) `{ file = "Cargo.lock" }`, `{ file = "**/*.toml" }`,
+    /// `{ file = "**/*.py", respect-gitignore = true }`, or `{ file = "Cargo.lock", hash = true }`
+    File {
+        file: Cow<'static, str>,
+        /// Whether to skip any files that match the glob, but are excluded by a `.gitignore` file
+        /// (e.g., generated build outputs). Nested `.gitignore` files are respected.
+        #[serde(default, rename = "respect-gitignore")]
+        respect_gitignore: bool,
+        /// Whether to include Python bytecode (`*.pyc` and `*.pyo` files, and the contents of
+        /// `__pycache__` directories) that matches the glob, which is excluded by default.
+        #[serde(default, rename = "include-bytecode")]
+        include_bytecode: bool,
+        /// Whether to anchor the glob to the directory in which it's defined, such that `*` only
+        /// matches within a single directory (e.g., `src/*.py` matches `src/main.py`, but not
+        /// `src/pkg/module.py`). Otherwise, as in a `.gitignore` file, a glob with wildcards only
+        /// in its last component (like `*.toml` or `src/*.py`) matches that component at any depth
+        /// below the preceding directories.
+        #[serde(default)]
+        anchored: bool,
+        /// Whether to key on the contents of the matching files, rather than their timestamps.
+        /// `true` uses a fast, non-cryptographic hash; alternatively, an algorithm (`"seahash"`,
+        /// `"sha256"`, or `"blake3"`) can be specified.
+        #[serde(default)]
+        hash: Option<HashPattern>,
+    },
+    /// Ex) `{ dir = "src" }` or `{ dir = "src", entries = false }`
+    Directory {
+        dir: Cow<'static, str>,
+        /// Whether adding, removing, or renaming an entry in the directory (as reflected in the
+        /// directory's own timestamp) should invalidate the cache. Changes to existing files
+        /// within the directory are not tracked either way.
+        #[serde(default = "default_true")]
+        entries: bool,
+    },
+    /// Ex) `{ git = true }`, `{ git = { commit = true, tags = false } }`, or
+    /// `{ git = true, path = "src" }`
+    Git {
+        git: GitPattern,
+        /// If set, key on the Git tree of this path (relative to the project directory) at the
+        /// current commit, rather than the current commit itself. If the path is the root of a
+        /// separate repository, key on that repository's current commit instead.
+        #[serde(default)]
+        path: Option<String>,
+    },
     /// Ex) `{ env = "UV_CACHE_INFO" }`
     Environment { env: String },
+    /// Ex) `{ markers = true }`
+    Markers { markers: bool },
+    /// Ex) `{ pyproject = true }`
+    PyProject { pyproject: bool },
+    /// Ex) `{ build-requires = true }`
+    BuildRequires {
+        #[serde(rename = "build-requires")]
+        build_requires: bool,
+    },
+    /// Ex) `{ host = true }`
+    Host { host: bool },
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+fn default_true() -> bool {
+    true
+}
+
+impl CacheKey {
+    /// Return the [`CacheKeyKind`] of this cache key.
+    pub fn kind(&self) -> CacheKeyKind {
+        match self {
+            Self::Path(_) => CacheKeyKind::Path,
+            Self::FileField { .. } => CacheKeyKind::FileField,
+            Self::File { .. } => CacheKeyKind::File,
+            Self::Directory { .. } => CacheKeyKind::Directory,
+            Self::Git { .. } => CacheKeyKind::Git,
+            Self::Environment { .. } => CacheKeyKind::Environment,
+            Self::Markers { .. } => CacheKeyKind::Markers,
+            Self::PyProject { .. } => CacheKeyKind::PyProject,
+            Self::BuildRequires { .. } => CacheKeyKind::BuildRequires,
+            Self::Host { .. } => CacheKeyKind::Host,
+        }
+    }
+
+    /// Return every supported [`CacheKeyKind`], in declaration order.
+    pub fn variants() -> &'static [CacheKeyKind] {
+        CacheKeyKind::ALL
+    }
+
+    /// Render the JSON Schema for a single entry in `tool.uv.cache-keys`.
+    #[cfg(feature = "schemars")]
+    pub fn schema() -> schemars::Schema {
+        schemars::schema_for!(Self)
+    }
+}
+
+/// The kind of a [`CacheKey`], without its value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CacheKeyKind {
+    /// Ex) `"Cargo.lock"`
+    Path,
+    /// Ex) `{ file = "src/foo/__init__.py", pattern = '__version__ = "(.+)"' }`
+    FileField,
+    /// Ex) `{ file = "Cargo.lock" }`
+    File,
+    /// Ex) `{ dir = "src" }`
+    Directory,
+    /// Ex) `{ git = true }`
+    Git,
+    /// Ex) `{ env = "UV_CACHE_INFO" }`
+    Environment,
+    /// Ex) `{ markers = true }`
+    Markers,
+    /// Ex) `{ pyproject = true }`
+    PyProject,
+    /// Ex) `{ build-requires = true }`
+    BuildRequires,
+    /// Ex) `{ host = true }`
+    Host,
+}
+
+impl CacheKeyKind {
+    const ALL: &'static [Self] = &[
+        Self::Path,
+        Self::FileField,
+        Self::File,
+        Self::Directory,
+        Self::Git,
+        Self::Environment,
+        Self::Markers,
+        Self::PyProject,
+        Self::BuildRequires,
+        Self::Host,
+    ];
+
+    /// Return the table key used to select this kind (e.g., `dir` in `{ dir = "src" }`), or
+    /// `None` for kinds that are written as a bare string.
+    pub fn key(self) -> Option<&'static str> {
+        match self {
+            Self::Path => None,
+            Self::FileField => Some("pattern"),
+            Self::File => Some("file"),
+            Self::Directory => Some("dir"),
+            Self::Git => Some("git"),
+            Self::Environment => Some("env"),
+            Self::Markers => Some("markers"),
+            Self::PyProject => Some("pyproject"),
+            Self::BuildRequires => Some("build-requires"),
+            Self::Host => Some("host"),
+        }
+    }
+}
+
+impl std::fmt::Display for CacheKeyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Path => write!(f, "path"),
+            Self::FileField => write!(f, "file-field"),
+            Self::File => write!(f, "file"),
+            Self::Directory => write!(f, "directory"),
+            Self::Git => write!(f, "git"),
+            Self::Environment => write!(f, "environment"),
+            Self::Markers => write!(f, "markers"),
+            Self::PyProject => write!(f, "pyproject"),
+            Self::BuildRequires => write!(f, "build-requires"),
+            Self::Host => write!(f, "host"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Hash, serde::Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(untagged, rename_all = "kebab-case", deny_unknown_fields)]
 pub enum GitPattern {
@@ -357,7 +1509,7 @@ pub enum GitPattern {
     Set(GitSet),
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, Hash, serde::Deserialize)]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct GitSet {
@@ -365,6 +1517,42 @@ pub struct GitSet {
     tags: Option<bool>,
 }
 
+#[derive(Debug, Clone, Hash, serde::Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(untagged, rename_all = "kebab-case", deny_unknown_fields)]
+pub enum HashPattern {
+    Bool(bool),
+    Algorithm(ContentHashAlgorithm),
+}
+
+impl HashPattern {
+    /// Return the algorithm with which to hash the matching files, if any.
+    pub fn algorithm(&self) -> Option<ContentHashAlgorithm> {
+        match self {
+            Self::Bool(true) => Some(ContentHashAlgorithm::default()),
+            Self::Bool(false) => None,
+            Self::Algorithm(algorithm) => Some(*algorithm),
+        }
+    }
+}
+
+/// The algorithm used to hash the contents of files in a cache key (via
+/// `{ file = "...", hash = ... }`).
+#[derive(
+    Debug, Default, Copy, Clone, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize,
+)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ContentHashAlgorithm {
+    /// A fast, non-cryptographic hash.
+    #[default]
+    Seahash,
+    /// SHA-256, e.g., for environments that require FIPS-approved algorithms.
+    Sha256,
+    /// BLAKE3, a fast cryptographic hash.
+    Blake3,
+}
+
 pub enum FilePattern {
     Glob(String),
     Path(PathBuf),
@@ -445,3 +1633,1262 @@ mod tests_unix {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+
+    use anyhow::Result;
+
+    use super::{
+        CacheInfo, CacheKey, CacheMarkers, Compatibility, ContentHashAlgorithm, GitPattern,
+        Timestamp, TimestampGranularity, hostname,
+    };
+    use crate::glob::{GlobOptions, glob_files};
+
+    #[test]
+    #[cfg(feature = "schemars")]
+    fn test_cache_key_schema_includes_variants() {
+        let schema = CacheKey::schema();
+        let variants = schema
+            .get("anyOf")
+            .and_then(|any_of| any_of.as_array())
+            .expect("`CacheKey` should be an untagged union");
+        assert_eq!(variants.len(), CacheKey::variants().len());
+
+        for kind in CacheKey::variants() {
+            let found = variants.iter().any(|variant| match kind.key() {
+                None => variant.get("type").and_then(|ty| ty.as_str()) == Some("string"),
+                Some(key) => variant
+                    .get("required")
+                    .and_then(|required| required.as_array())
+                    .is_some_and(|required| required.iter().any(|field| field == key)),
+            });
+            assert!(found, "missing `{kind}` in `CacheKey` schema");
+        }
+    }
+
+    #[test]
+    fn test_cache_key_variants_exhaustive() {
+        let keys = [
+            CacheKey::Path("Cargo.lock".into()),
+            CacheKey::FileField {
+                file: "src/foo/__init__.py".into(),
+                pattern: "__version__ = \"(.+)\"".to_string(),
+            },
+            CacheKey::File {
+                file: "Cargo.lock".into(),
+                respect_gitignore: false,
+                include_bytecode: false,
+                anchored: false,
+                hash: None,
+            },
+            CacheKey::Directory {
+                dir: "src".into(),
+                entries: true,
+            },
+            CacheKey::Git {
+                git: GitPattern::Bool(true),
+                path: None,
+            },
+            CacheKey::Environment {
+                env: "UV_CACHE_INFO".to_string(),
+            },
+            CacheKey::Markers { markers: true },
+            CacheKey::PyProject { pyproject: true },
+            CacheKey::BuildRequires {
+                build_requires: true,
+            },
+            CacheKey::Host { host: true },
+        ];
+        let kinds = keys.iter().map(CacheKey::kind).collect::<Vec<_>>();
+        assert_eq!(kinds, CacheKey::variants());
+    }
+
+    #[test]
+    fn test_cache_info_single_commit() -> Result<()> {
+        // Cache info written before per-path commits were supported still deserializes.
+        let commit = "8b8b6f2b5b8a8e7ba2ad8a7d0c8c4a0f4a6c1e3d";
+        let cache_info = toml::from_str::<CacheInfo>(&format!("commit = \"{commit}\"\n"))?;
+        assert!(!cache_info.is_empty());
+        assert!(cache_info.commits.is_empty());
+        assert_eq!(
+            toml::from_str::<CacheInfo>(&toml::to_string(&cache_info)?)?,
+            cache_info
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_msgpack() -> Result<()> {
+        // Cache info is persisted (e.g., in revision pointers) as MessagePack, which encodes
+        // structs positionally, so unset keys followed by set keys must round-trip.
+        let cache_info = CacheInfo::default()
+            .with_build_isolation(false)
+            .with_host("localhost");
+        assert_eq!(
+            rmp_serde::from_slice::<CacheInfo>(&rmp_serde::to_vec(&cache_info)?)?,
+            cache_info
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_explicit_keys_replace_defaults() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pyproject_toml = dir.path().join("pyproject.toml");
+        let setup_cfg = dir.path().join("setup.cfg");
+        fs_err::write(
+            &pyproject_toml,
+            "[tool.uv]\ncache-keys = [\"pyproject.toml\"]\n",
+        )?;
+        fs_err::write(&setup_cfg, "")?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(&pyproject_toml)?)
+        );
+
+        // Neither `setup.cfg` nor a new `src` directory (both default cache keys) invalidate the
+        // cache.
+        fs_err::File::options()
+            .write(true)
+            .open(&setup_cfg)?
+            .file()
+            .set_modified(SystemTime::now())?;
+        fs_err::create_dir(dir.path().join("src"))?;
+        assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        // An empty list disables the defaults entirely.
+        fs_err::write(&pyproject_toml, "[tool.uv]\ncache-keys = []\n")?;
+        assert!(CacheInfo::from_directory(dir.path())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_uv_toml() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pyproject_toml = dir.path().join("pyproject.toml");
+        let uv_toml = dir.path().join("uv.toml");
+        let setup_cfg = dir.path().join("setup.cfg");
+        fs_err::write(&pyproject_toml, "[project]\nname = \"foo\"\n")?;
+        fs_err::write(&setup_cfg, "")?;
+
+        // The cache keys are read from the `uv.toml`, in the absence of a `[tool.uv]` section.
+        fs_err::write(&uv_toml, "cache-keys = [\"setup.cfg\"]\n")?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(&setup_cfg)?)
+        );
+
+        // If both files define cache keys, the `uv.toml` takes precedence.
+        fs_err::write(
+            &pyproject_toml,
+            "[tool.uv]\ncache-keys = [{ env = \"FOO\" }]\n",
+        )?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(&setup_cfg)?)
+        );
+        assert!(cache_info.env.is_empty());
+
+        // If the `uv.toml` doesn't define cache keys, the `pyproject.toml` is used.
+        fs_err::write(&uv_toml, "native-tls = true\n")?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(cache_info.timestamp, None);
+        assert_eq!(cache_info.env.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_glob_timestamp() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut files = Vec::new();
+        for package in ["foo", "bar", "bar/nested"] {
+            fs_err::create_dir_all(dir.path().join(package))?;
+            for module in 0..50 {
+                let path = dir.path().join(package).join(format!("module_{module}.py"));
+                fs_err::write(&path, "")?;
+                files.push(path);
+            }
+        }
+        fs_err::write(dir.path().join(".gitignore"), "")?;
+
+        // The timestamp matches the most recent of the individual files, whether or not the
+        // metadata is reused from the directory listing.
+        let expected = files
+            .iter()
+            .map(Timestamp::from_path)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .max();
+        for cache_keys in [
+            "[\"**/*.py\"]",
+            "[{ file = \"**/*.py\", respect-gitignore = true }]",
+        ] {
+            fs_err::write(
+                dir.path().join("pyproject.toml"),
+                format!("[tool.uv]\ncache-keys = {cache_keys}\n"),
+            )?;
+            assert_eq!(CacheInfo::from_directory(dir.path())?.timestamp, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_parent_pyproject() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let package = dir.path().join("src").join("foo");
+        fs_err::create_dir_all(&package)?;
+        fs_err::write(dir.path().join("requirements.txt"), "")?;
+        fs_err::write(package.join("requirements.txt"), "")?;
+        fs_err::write(package.join("__init__.py"), "")?;
+
+        // The cache keys are read from the `pyproject.toml` in a parent directory, and resolved
+        // relative to it.
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [\"requirements.txt\"]\n",
+        )?;
+        let cache_info = CacheInfo::from_directory(&package)?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(dir.path().join("requirements.txt"))?)
+        );
+        assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        // If the parent doesn't define any cache keys, the defaults are resolved relative to the
+        // given directory.
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[project]\nname = \"foo\"\n",
+        )?;
+        assert_eq!(CacheInfo::from_directory(&package)?.timestamp, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_nested_project() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("requirements.txt"), "")?;
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [\"requirements.txt\"]\n",
+        )?;
+
+        // A `setup.py`-based project nested within another project uses its own (default) cache
+        // keys, rather than those of the enclosing project.
+        let project = dir.path().join("vendor").join("bar");
+        fs_err::create_dir_all(project.join("bar"))?;
+        fs_err::write(project.join("setup.py"), "")?;
+        assert_eq!(
+            CacheInfo::from_directory(&project)?.timestamp,
+            Some(Timestamp::from_path(project.join("setup.py"))?)
+        );
+        assert_eq!(
+            CacheInfo::from_directory(&project.join("bar"))?.timestamp,
+            None
+        );
+
+        // The search also stops at the root of a nested repository.
+        let repository = dir.path().join("vendor").join("baz");
+        fs_err::create_dir_all(repository.join(".git"))?;
+        fs_err::create_dir_all(repository.join("baz"))?;
+        assert_eq!(
+            CacheInfo::from_directory(&repository.join("baz"))?.timestamp,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_directory_entries() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src");
+        fs_err::create_dir(&src)?;
+        fs_err::write(src.join("main.py"), "")?;
+
+        // By default, only the presence of `src` is tracked.
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        fs_err::write(src.join("other.py"), "")?;
+        assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        // An explicit directory key also tracks the directory's entries, such that removing a
+        // file (which changes only the directory's timestamp) invalidates the cache.
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ dir = \"src\" }]\n",
+        )?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(cache_info.timestamp, Some(Timestamp::from_path(&src)?));
+        fs_err::remove_file(src.join("other.py"))?;
+        assert_ne!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        // Unless opted out.
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ dir = \"src\", entries = false }]\n",
+        )?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(cache_info.timestamp, None);
+        fs_err::write(src.join("other.py"), "")?;
+        assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_respect_gitignore() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let write = |path: &str, contents: &str| -> Result<()> {
+            let path = dir.path().join(path);
+            fs_err::create_dir_all(path.parent().unwrap())?;
+            fs_err::write(path, contents)?;
+            Ok(())
+        };
+        write(".gitignore", "build/\n*_pb2.py\n")?;
+        write("pkg/.gitignore", "_version.py\n")?;
+        write("pkg/__init__.py", "")?;
+        write(
+            "pyproject.toml",
+            "[tool.uv]\ncache-keys = [{ file = \"**/*.py\", respect-gitignore = true }]\n",
+        )?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(dir.path().join("pkg/__init__.py"))?)
+        );
+
+        // Generated files that are ignored, by the root or a nested `.gitignore`, are excluded.
+        write("build/lib/pkg/__init__.py", "")?;
+        write("pkg/api_pb2.py", "")?;
+        write("pkg/_version.py", "")?;
+        assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        // Other files are still included.
+        write("pkg/module.py", "")?;
+        assert_ne!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        // Without the flag, ignored files are included.
+        write(
+            "pyproject.toml",
+            "[tool.uv]\ncache-keys = [{ file = \"**/*.py\" }]\n",
+        )?;
+        write("pkg/_version.py", "")?;
+        assert_eq!(
+            CacheInfo::from_directory(dir.path())?.timestamp,
+            Some(Timestamp::from_path(dir.path().join("pkg/_version.py"))?)
+        );
+
+        // The `.gitignore` files above the base of the glob are respected, too.
+        let options = GlobOptions {
+            respect_gitignore: true,
+            ..GlobOptions::default()
+        };
+        let mut files = glob_files(dir.path(), &["pkg/**/*.py"], options)?
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                dir.path().join("pkg/__init__.py"),
+                dir.path().join("pkg/module.py")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_exclude_bytecode() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let write = |path: &str| -> Result<()> {
+            let path = dir.path().join(path);
+            fs_err::create_dir_all(path.parent().unwrap())?;
+            fs_err::write(path, "")?;
+            Ok(())
+        };
+        write("pkg/__init__.py")?;
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ file = \"pkg/**/*\" }]\n",
+        )?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(dir.path().join("pkg/__init__.py"))?)
+        );
+
+        // Touching bytecode doesn't invalidate the cache.
+        write("pkg/__pycache__/__init__.cpython-312.pyc")?;
+        write("pkg/legacy.pyc")?;
+        write("pkg/legacy.pyo")?;
+        assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        // Other files are still included.
+        write("pkg/module.py")?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(dir.path().join("pkg/module.py"))?)
+        );
+
+        // Unless bytecode is explicitly included.
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ file = \"pkg/**/*\", include-bytecode = true }]\n",
+        )?;
+        write("pkg/__pycache__/module.cpython-312.pyc")?;
+        assert_eq!(
+            CacheInfo::from_directory(dir.path())?.timestamp,
+            Some(Timestamp::from_path(
+                dir.path().join("pkg/__pycache__/module.cpython-312.pyc")
+            )?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_content_hash() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::create_dir_all(dir.path().join("src"))?;
+        fs_err::write(dir.path().join("requirements.txt"), "anyio\n")?;
+        fs_err::write(dir.path().join("src/a.py"), "a = 1\n")?;
+        fs_err::write(dir.path().join("src/b.py"), "b = 1\n")?;
+
+        for (algorithm, len) in [
+            ("true", 16),
+            ("\"seahash\"", 16),
+            ("\"sha256\"", 64),
+            ("\"blake3\"", 64),
+        ] {
+            fs_err::write(
+                dir.path().join("pyproject.toml"),
+                format!(
+                    "[tool.uv]\ncache-keys = [{{ file = \"requirements.txt\", hash = {algorithm} }}, {{ file = \"src/*.py\", hash = {algorithm} }}]\n"
+                ),
+            )?;
+            let cache_info = CacheInfo::from_directory(dir.path())?;
+            assert_eq!(cache_info.timestamp, None);
+            assert_eq!(cache_info.hashes.len(), 2, "{algorithm}");
+            assert!(
+                cache_info
+                    .hashes
+                    .values()
+                    .all(|hash| hash.digest.len() == len),
+                "{algorithm}"
+            );
+
+            // Rewriting a file with the same contents doesn't invalidate the cache.
+            fs_err::write(dir.path().join("src/a.py"), "a = 1\n")?;
+            assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+            // Changing its contents does.
+            fs_err::write(dir.path().join("src/a.py"), "a = 2\n")?;
+            assert_ne!(CacheInfo::from_directory(dir.path())?, cache_info);
+            fs_err::write(dir.path().join("src/a.py"), "a = 1\n")?;
+
+            // As does renaming it.
+            fs_err::rename(dir.path().join("src/b.py"), dir.path().join("src/c.py"))?;
+            assert_ne!(CacheInfo::from_directory(dir.path())?, cache_info);
+            fs_err::rename(dir.path().join("src/c.py"), dir.path().join("src/b.py"))?;
+            assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+        }
+
+        // With `hash = false`, the timestamps are used instead.
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ file = \"requirements.txt\", hash = false }]\n",
+        )?;
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert!(cache_info.hashes.is_empty());
+        assert!(cache_info.timestamp.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_file_field() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ file = \"__init__.py\", pattern = '__version__ = \"(.+)\"' }]\n",
+        )?;
+        fs_err::write(
+            dir.path().join("__init__.py"),
+            "__version__ = \"1.0.0\"\n\ndef main(): ...\n",
+        )?;
+
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(cache_info.timestamp, None);
+        assert_eq!(cache_info.fields["__init__.py"].len(), 1);
+
+        // Unrelated edits to the file don't invalidate the cache.
+        fs_err::write(
+            dir.path().join("__init__.py"),
+            "__version__ = \"1.0.0\"\n\ndef main():\n    pass\n",
+        )?;
+        assert_eq!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        // Changing the value does.
+        fs_err::write(
+            dir.path().join("__init__.py"),
+            "__version__ = \"1.0.1\"\n\ndef main():\n    pass\n",
+        )?;
+        assert_ne!(CacheInfo::from_directory(dir.path())?, cache_info);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_file_field_no_match() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("VERSION"), "1.0.0\n")?;
+
+        // A pattern that doesn't match contributes nothing to the cache info.
+        let cache_info = CacheInfo::builder(dir.path())
+            .file_field("VERSION", "^version: (.+)$")
+            .build()?;
+        assert!(cache_info.is_empty());
+
+        // Nor does a missing file.
+        let cache_info = CacheInfo::builder(dir.path())
+            .file_field("missing.txt", "(.+)")
+            .build()?;
+        assert!(cache_info.is_empty());
+
+        // Without a capture group, the entire match is used.
+        let matched = CacheInfo::builder(dir.path())
+            .file_field("VERSION", r"\d+\.\d+")
+            .build()?;
+        assert!(!matched.is_empty());
+        fs_err::write(dir.path().join("VERSION"), "1.0.1\n")?;
+        assert_eq!(
+            CacheInfo::builder(dir.path())
+                .file_field("VERSION", r"\d+\.\d+")
+                .build()?,
+            matched
+        );
+
+        // An invalid pattern is an error.
+        assert!(
+            CacheInfo::builder(dir.path())
+                .file_field("VERSION", "(")
+                .build()
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_content_hash_mismatch() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("requirements.txt"), "anyio\n")?;
+
+        let sha256 = CacheInfo::builder(dir.path())
+            .file_hash("requirements.txt", ContentHashAlgorithm::Sha256)
+            .build()?;
+        let blake3 = CacheInfo::builder(dir.path())
+            .file_hash("requirements.txt", ContentHashAlgorithm::Blake3)
+            .build()?;
+        assert_ne!(sha256, blake3);
+
+        // Cache info computed with one algorithm never matches that computed with another, even
+        // if the digests happen to be equal.
+        let mut forged = blake3.clone();
+        for (hash, other) in forged.hashes.values_mut().zip(sha256.hashes.values()) {
+            hash.digest.clone_from(&other.digest);
+        }
+        assert!(!forged.matches(&sha256, TimestampGranularity::Exact));
+        assert!(!forged.matches(&sha256, TimestampGranularity::Seconds));
+
+        // The algorithm is recorded alongside the digest.
+        assert!(
+            sha256
+                .hashes
+                .values()
+                .all(|hash| hash.algorithm == ContentHashAlgorithm::Sha256)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_builder() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let commit = "8b8b6f2b5b8a8e7ba2ad8a7d0c8c4a0f4a6c1e3d";
+        fs_err::write(dir.path().join("Cargo.lock"), "")?;
+        fs_err::write(
+            dir.path().join(".git_archival.txt"),
+            format!("node: {commit}\n"),
+        )?;
+        fs_err::create_dir(dir.path().join("src"))?;
+        fs_err::write(dir.path().join("src").join("lib.rs"), "")?;
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [\"pyproject.toml\"]\n",
+        )?;
+
+        // Compose cache keys from multiple sources; the `pyproject.toml` is ignored.
+        let builder = CacheInfo::builder(dir.path())
+            .file("Cargo.lock")
+            .file("src/**/*.rs")
+            .env("UV_TEST_CACHE_INFO_BUILDER")
+            .git("src")
+            .literal("x86_64-unknown-linux-gnu");
+        let cache_info = builder.clone().build()?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(
+                Timestamp::from_path(dir.path().join("Cargo.lock"))?
+                    .max(Timestamp::from_path(dir.path().join("src").join("lib.rs"))?)
+            )
+        );
+        assert_eq!(
+            cache_info.env,
+            BTreeMap::from([("UV_TEST_CACHE_INFO_BUILDER".to_string(), None)])
+        );
+        assert_eq!(
+            cache_info.commits.keys().collect::<Vec<_>>(),
+            vec![Path::new("src")]
+        );
+        assert_eq!(cache_info, builder.build()?);
+
+        // Literals contribute to the cache key.
+        let other = CacheInfo::builder(dir.path())
+            .file("Cargo.lock")
+            .file("src/**/*.rs")
+            .env("UV_TEST_CACHE_INFO_BUILDER")
+            .git("src")
+            .literal("aarch64-unknown-linux-gnu")
+            .build()?;
+        assert_ne!(other, cache_info);
+
+        // Without any keys, the cache info is empty.
+        assert!(CacheInfo::builder(dir.path()).build()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_pyproject() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pyproject_toml = dir.path().join("pyproject.toml");
+
+        // Define the cache keys in a `uv.toml`, so that they're still read when the
+        // `pyproject.toml` is unparseable.
+        fs_err::write(
+            dir.path().join("uv.toml"),
+            "cache-keys = [{ pyproject = true }]\n",
+        )?;
+        let write = |contents: &str| -> Result<CacheInfo> {
+            fs_err::write(&pyproject_toml, contents)?;
+            Ok(CacheInfo::from_directory(dir.path())?)
+        };
+
+        let cache_info = write("[project]\nname = \"foo\"\n\n[tool.black]\nline-length = 88\n")?;
+        assert!(cache_info.pyproject.is_some());
+        assert_eq!(cache_info.timestamp, None);
+
+        // Editing an irrelevant table (or the formatting) doesn't invalidate the cache.
+        assert_eq!(
+            write("[project]\nname = 'foo'\n\n[tool.black]\nline-length = 100\n")?,
+            cache_info
+        );
+
+        // Editing a relevant table does.
+        assert_ne!(
+            write(
+                "[project]\nname = \"foo\"\nversion = \"1.0.0\"\n\n[tool.black]\nline-length = 88\n"
+            )?,
+            cache_info
+        );
+        assert_ne!(
+            write("[project]\nname = \"foo\"\n\n[tool.setuptools]\npackages = [\"foo\"]\n")?,
+            cache_info
+        );
+
+        // An unparseable `pyproject.toml` falls back to its timestamp, under the same cache keys
+        // (rather than the defaults).
+        let fallback = write("[project\n")?;
+        assert_eq!(fallback.pyproject, None);
+        assert_eq!(
+            fallback.timestamp,
+            Some(Timestamp::from_path(&pyproject_toml)?)
+        );
+        assert_eq!(fallback.keys, cache_info.keys);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_build_requires() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pyproject_toml = dir.path().join("pyproject.toml");
+        let write = |contents: &str| -> Result<CacheInfo> {
+            fs_err::write(
+                &pyproject_toml,
+                format!("[tool.uv]\ncache-keys = [{{ build-requires = true }}]\n\n{contents}"),
+            )?;
+            Ok(CacheInfo::from_directory(dir.path())?)
+        };
+
+        let cache_info = write(
+            "[build-system]\nrequires = [\"setuptools==69.0.0\", \"wheel\"]\n\n[project]\nname = \"foo\"\n",
+        )?;
+        assert!(cache_info.build_requires.is_some());
+        assert_eq!(cache_info.timestamp, None);
+
+        // Editing the rest of the `pyproject.toml`, or reordering the requirements, doesn't
+        // invalidate the cache.
+        assert_eq!(
+            write(
+                "[build-system]\nrequires = [\"wheel\", \"setuptools==69.0.0\"]\n\n[project]\nname = \"foo\"\nversion = \"1.0.0\"\n"
+            )?,
+            cache_info
+        );
+
+        // Bumping a build requirement does.
+        assert_ne!(
+            write(
+                "[build-system]\nrequires = [\"setuptools==70.0.0\", \"wheel\"]\n\n[project]\nname = \"foo\"\n"
+            )?,
+            cache_info
+        );
+
+        // Without a `[build-system]` table, there's nothing to include.
+        assert!(write("[project]\nname = \"foo\"\n")?.is_empty());
+
+        // An unparseable `pyproject.toml` falls back to its timestamp.
+        let cache_info = write("[build-system]\nrequires = \"setuptools\"\n")?;
+        assert_eq!(cache_info.build_requires, None);
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(&pyproject_toml)?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_build_isolation() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+
+        // Toggling build isolation invalidates the cache.
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        let isolated = cache_info.clone().with_build_isolation(true);
+        let non_isolated = cache_info.clone().with_build_isolation(false);
+        assert_ne!(isolated, non_isolated);
+        assert_ne!(isolated, cache_info);
+        assert_eq!(
+            CacheInfo::from_directory(dir.path())?.with_build_isolation(true),
+            isolated
+        );
+
+        // The mode can also be provided via the builder.
+        let builder = CacheInfo::builder(dir.path()).file("pyproject.toml");
+        let non_isolated = builder.clone().build_isolation(false).build()?;
+        assert_ne!(builder.clone().build_isolation(true).build()?, non_isolated);
+        assert!(
+            !CacheInfo::builder(dir.path())
+                .build_isolation(false)
+                .build()?
+                .is_empty()
+        );
+
+        // The mode is preserved when serialized, but omitted if unknown.
+        assert_eq!(
+            toml::from_str::<CacheInfo>(&toml::to_string(&non_isolated)?)?,
+            non_isolated
+        );
+        assert!(!toml::to_string(&builder.build()?)?.contains("build-isolation"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_compatible() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        let builder = CacheInfo::builder(dir.path())
+            .file("pyproject.toml")
+            .literal("v1");
+
+        // Cache info computed with the same keys and values matches.
+        let info = builder.clone().build()?;
+        assert_eq!(
+            info.compatible(&builder.clone().build()?),
+            Compatibility::Match
+        );
+        assert_eq!(
+            CacheInfo::default().compatible(&CacheInfo::default()),
+            Compatibility::Match
+        );
+
+        // A key with a different value is a mismatch.
+        let changed = CacheInfo::builder(dir.path())
+            .file("pyproject.toml")
+            .literal("v2")
+            .build()?;
+        assert_eq!(info.compatible(&changed), Compatibility::Mismatch);
+
+        // A key that's only present in one (e.g., a file that only exists on one machine) can't be
+        // compared, in either direction.
+        let missing = CacheInfo::builder(dir.path())
+            .file("setup.py")
+            .literal("v1")
+            .build()?;
+        assert_eq!(info.compatible(&missing), Compatibility::Indeterminate);
+        assert_eq!(missing.compatible(&info), Compatibility::Indeterminate);
+        let isolated = builder.clone().build_isolation(true).build()?;
+        assert_eq!(info.compatible(&isolated), Compatibility::Indeterminate);
+
+        // But a mismatch in a shared key takes precedence.
+        let isolated_changed = CacheInfo::builder(dir.path())
+            .file("pyproject.toml")
+            .literal("v2")
+            .build_isolation(true)
+            .build()?;
+        assert_eq!(info.compatible(&isolated_changed), Compatibility::Mismatch);
+
+        // Cache info from another host is never comparable, even if the keys match.
+        let local = builder.clone().host("build-01").build()?;
+        let remote = builder.host("build-02").build()?;
+        assert_eq!(local.compatible(&remote), Compatibility::Indeterminate);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_compatible_deleted_file() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        fs_err::write(dir.path().join("requirements.txt"), "anyio")?;
+        let builder = CacheInfo::builder(dir.path())
+            .file("pyproject.toml")
+            .file_hash("requirements.txt", ContentHashAlgorithm::Seahash);
+        let info = builder.clone().build()?;
+
+        // A keyed file that was deleted since is a mismatch, rather than incomparable, since both
+        // were computed from the same keys.
+        fs_err::remove_file(dir.path().join("requirements.txt"))?;
+        let deleted = builder.clone().build()?;
+        assert_eq!(info.compatible(&deleted), Compatibility::Mismatch);
+        assert_eq!(deleted.compatible(&info), Compatibility::Mismatch);
+
+        // As is the only file keyed by timestamp.
+        let timestamped = CacheInfo::builder(dir.path()).file("setup.py");
+        fs_err::write(dir.path().join("setup.py"), "")?;
+        let info = timestamped.clone().build()?;
+        fs_err::remove_file(dir.path().join("setup.py"))?;
+        assert_eq!(
+            info.compatible(&timestamped.build()?),
+            Compatibility::Mismatch
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_compatible_key_set() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        fs_err::write(dir.path().join("requirements.txt"), "anyio")?;
+        let builder = CacheInfo::builder(dir.path())
+            .file("pyproject.toml")
+            .literal("v1");
+        let info = builder.clone().build()?;
+
+        // Cache info computed from different keys can't be compared, even if every shared key
+        // matches...
+        let extended = builder
+            .clone()
+            .file_hash("requirements.txt", ContentHashAlgorithm::Seahash)
+            .build()?;
+        assert_eq!(info.compatible(&extended), Compatibility::Indeterminate);
+        assert_eq!(extended.compatible(&info), Compatibility::Indeterminate);
+        let rehashed = builder
+            .clone()
+            .file_hash("pyproject.toml", ContentHashAlgorithm::Sha256)
+            .build()?;
+        assert_eq!(info.compatible(&rehashed), Compatibility::Indeterminate);
+
+        // ...but a mismatch in a shared key takes precedence.
+        let changed = CacheInfo::builder(dir.path())
+            .file("pyproject.toml")
+            .file_hash("requirements.txt", ContentHashAlgorithm::Seahash)
+            .literal("v2")
+            .build()?;
+        assert_eq!(info.compatible(&changed), Compatibility::Mismatch);
+
+        // Cache info computed from the same keys round-trips its key set.
+        assert_eq!(toml::from_str::<CacheInfo>(&toml::to_string(&info)?)?, info);
+        assert_eq!(
+            info.compatible(&toml::from_str::<CacheInfo>(&toml::to_string(&info)?)?),
+            Compatibility::Match
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_host() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        let builder = CacheInfo::builder(dir.path()).file("pyproject.toml");
+
+        // Cache info computed on the same host is comparable.
+        let local = builder.clone().host("build-01").build()?;
+        assert!(local.is_comparable(&builder.clone().host("build-01").build()?));
+        assert!(local.matches(
+            &builder.clone().host("build-01").build()?,
+            TimestampGranularity::Exact
+        ));
+
+        // Cache info computed on another host (or without a host) isn't, even if the timestamps
+        // match.
+        let remote = builder.clone().host("build-02").build()?;
+        assert!(!local.is_comparable(&remote));
+        assert!(!local.matches(&remote, TimestampGranularity::Exact));
+        let hostless = builder.clone().build()?;
+        assert!(!local.is_comparable(&hostless));
+        assert!(hostless.is_comparable(&builder.clone().build()?));
+
+        // The hostname itself isn't recorded, but its identity survives a round-trip.
+        let serialized = toml::to_string(&local)?;
+        assert!(!serialized.contains("build-01"));
+        assert_eq!(toml::from_str::<CacheInfo>(&serialized)?, local);
+
+        // The `host` cache key records the identity of the current machine.
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ file = \"pyproject.toml\" }, { host = true }]\n",
+        )?;
+        let keyed = CacheInfo::from_directory(dir.path())?;
+        assert!(!keyed.is_comparable(&remote));
+        assert!(!keyed.is_comparable(&hostless));
+        assert!(keyed.is_comparable(&builder.host(hostname().expect("hostname")).build()?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_digest() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        let builder = CacheInfo::builder(dir.path())
+            .file("pyproject.toml")
+            .literal("cuda-12");
+
+        // The digest is stable for identical inputs.
+        let digest = builder.clone().build()?.digest();
+        assert!(digest.is_some());
+        assert_eq!(builder.clone().build()?.digest(), digest);
+        let serialized = toml::to_string(&builder.clone().build()?)?;
+        assert_eq!(toml::from_str::<CacheInfo>(&serialized)?.digest(), digest);
+
+        // Any change to the recorded keys changes the digest.
+        let variants = [
+            builder.clone().literal("cpu").build()?,
+            builder.clone().host("build-01").build()?,
+            builder.clone().build()?.with_build_isolation(false),
+            CacheInfo::builder(dir.path()).literal("cuda-12").build()?,
+            CacheInfo::from_timestamp(Timestamp::from(SystemTime::UNIX_EPOCH)),
+        ];
+        for variant in &variants {
+            assert_ne!(variant.digest(), digest, "{variant:?}");
+        }
+
+        // Empty cache info has no digest.
+        assert_eq!(CacheInfo::default().digest(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_granularity() {
+        let seconds = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let a = CacheInfo::from_timestamp(Timestamp::from(seconds + Duration::from_nanos(100)));
+        let b = CacheInfo::from_timestamp(Timestamp::from(seconds + Duration::from_millis(900)));
+        let c = CacheInfo::from_timestamp(Timestamp::from(seconds + Duration::from_secs(1)));
+
+        // By default, sub-second differences are significant.
+        assert_ne!(a, b);
+        assert!(!a.matches(&b, TimestampGranularity::Exact));
+
+        // At whole-second granularity, only the sub-second components are ignored.
+        assert!(a.matches(&b, TimestampGranularity::Seconds));
+        assert!(!a.matches(&c, TimestampGranularity::Seconds));
+        assert!(!b.matches(&c, TimestampGranularity::Seconds));
+    }
+
+    #[test]
+    fn test_cache_info_ignore_timestamps() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::create_dir_all(dir.path().join(".git"))?;
+        fs_err::create_dir_all(dir.path().join("src"))?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        let touch = |path: &str, seconds: u64| -> Result<()> {
+            fs_err::File::options()
+                .write(true)
+                .open(dir.path().join(path))?
+                .file()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))?;
+            Ok(())
+        };
+        let checkout = |commit: &str| {
+            fs_err::write(dir.path().join(".git").join("HEAD"), format!("{commit}\n"))
+        };
+        let builder = CacheInfo::builder(dir.path())
+            .file("pyproject.toml")
+            .directory("src")
+            .git(".");
+
+        checkout("8b8b6f2b5b8a8e7ba2ad8a7d0c8c4a0f4a6c1e3d")?;
+        touch("pyproject.toml", 1_700_000_000)?;
+        let cache_info = builder.clone().ignore_timestamps().build()?;
+        assert!(!cache_info.is_empty());
+
+        // Touching the files doesn't invalidate the cache.
+        touch("pyproject.toml", 1_800_000_000)?;
+        assert_ne!(builder.clone().build()?, cache_info);
+        assert_eq!(builder.clone().ignore_timestamps().build()?, cache_info);
+        assert!(
+            builder
+                .clone()
+                .build()?
+                .matches(&cache_info, TimestampGranularity::Ignore)
+        );
+
+        // Changing the commit does.
+        checkout("0c3a5f7e2b1d4c6a8e9f0b1c2d3e4f5a6b7c8d9e")?;
+        assert_ne!(builder.clone().ignore_timestamps().build()?, cache_info);
+        assert!(
+            !builder
+                .clone()
+                .build()?
+                .matches(&cache_info, TimestampGranularity::Ignore)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_recursive_glob() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for path in [
+            "src/main.py",
+            "src/pkg/__init__.py",
+            "src/pkg/a/b/c/module.py",
+        ] {
+            let path = dir.path().join(path);
+            fs_err::create_dir_all(path.parent().unwrap())?;
+            fs_err::write(path, "")?;
+        }
+
+        // Expand a glob, returning the matching files relative to the project directory.
+        let matches = |glob: &str, anchored: bool| -> Result<Vec<String>> {
+            let options = GlobOptions {
+                anchored,
+                ..GlobOptions::default()
+            };
+            let mut files = glob_files(dir.path(), &[glob], options)?
+                .into_iter()
+                .map(|(path, _)| {
+                    path.strip_prefix(dir.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect::<Vec<_>>();
+            files.sort();
+            Ok(files)
+        };
+
+        // `**` matches across any number of directories, including none, either way.
+        for anchored in [false, true] {
+            assert_eq!(
+                matches("src/**/*.py", anchored)?,
+                [
+                    "src/main.py",
+                    "src/pkg/__init__.py",
+                    "src/pkg/a/b/c/module.py"
+                ]
+            );
+            assert_eq!(
+                matches("**/module.py", anchored)?,
+                ["src/pkg/a/b/c/module.py"]
+            );
+            assert_eq!(
+                matches("src/pkg/**/__init__.py", anchored)?,
+                ["src/pkg/__init__.py"]
+            );
+            assert_eq!(matches("src/*/*.py", anchored)?, ["src/pkg/__init__.py"]);
+        }
+
+        // By default, a glob with wildcards only in its last component matches at any depth.
+        assert_eq!(
+            matches("src/*.py", false)?,
+            [
+                "src/main.py",
+                "src/pkg/__init__.py",
+                "src/pkg/a/b/c/module.py"
+            ]
+        );
+        assert_eq!(matches("*.py", false)?.len(), 3);
+
+        // Once anchored, `*` only matches within a single directory.
+        assert_eq!(matches("src/*.py", true)?, ["src/main.py"]);
+        assert!(matches("*.py", true)?.is_empty());
+
+        // Anchoring is opt-in via the `anchored` field.
+        let timestamp = |cache_key: &str| -> Result<Option<Timestamp>> {
+            fs_err::write(
+                dir.path().join("pyproject.toml"),
+                format!("[tool.uv]\ncache-keys = [{cache_key}]\n"),
+            )?;
+            Ok(CacheInfo::from_directory(dir.path())?.timestamp)
+        };
+        assert!(timestamp(r#"{ file = "*.py" }"#)?.is_some());
+        assert!(timestamp(r#"{ file = "*.py", anchored = false }"#)?.is_some());
+        assert!(timestamp(r#"{ file = "*.py", anchored = true }"#)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_timestamp_floor() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        let modified = Timestamp::from_path(dir.path().join("pyproject.toml"))?;
+
+        // A floor below the file's timestamp has no effect.
+        let below = Timestamp::from(SystemTime::UNIX_EPOCH);
+        let cache_info = CacheInfo::from_directory_with_floor(dir.path(), Some(below), None)?;
+        assert_eq!(cache_info.timestamp, Some(modified));
+
+        // A floor above the file's timestamp replaces it.
+        let above = Timestamp::from(SystemTime::now() + Duration::from_secs(60 * 60));
+        let cache_info = CacheInfo::from_directory_with_floor(dir.path(), Some(above), None)?;
+        assert_eq!(cache_info.timestamp, Some(above));
+
+        // Touching the file doesn't invalidate the cache while it remains below the floor.
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        let cache_info_after = CacheInfo::from_directory_with_floor(dir.path(), Some(above), None)?;
+        assert_eq!(cache_info, cache_info_after);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_defaults_only() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("setup.py"), "")?;
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ env = \"UV_CACHE_INFO_TEST\" }]\n",
+        )?;
+
+        // The custom cache keys are respected by default.
+        let cache_info = CacheInfo::from_directory(dir.path())?;
+        assert_eq!(cache_info.timestamp, None);
+        assert_eq!(cache_info.env.len(), 1);
+
+        // But the `pyproject.toml` isn't parsed when using the defaults, only timestamped.
+        let cache_info = CacheInfo::from_directory_defaults_only(dir.path())?;
+        assert!(cache_info.env.is_empty());
+        assert_eq!(
+            cache_info.timestamp,
+            Some(
+                Timestamp::from_path(dir.path().join("pyproject.toml"))?
+                    .max(Timestamp::from_path(dir.path().join("setup.py"))?)
+            )
+        );
+
+        // Even if it's invalid.
+        fs_err::write(dir.path().join("pyproject.toml"), "[tool.uv\n")?;
+        let cache_info = CacheInfo::from_directory_defaults_only(dir.path())?;
+        assert_eq!(
+            cache_info.timestamp,
+            Some(Timestamp::from_path(dir.path().join("pyproject.toml"))?)
+        );
+
+        // Without any custom cache keys, the two are equivalent.
+        fs_err::create_dir_all(dir.path().join("src"))?;
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[project]\nname = \"foo\"\n",
+        )?;
+        assert_eq!(
+            CacheInfo::from_directory_defaults_only(dir.path())?,
+            CacheInfo::from_directory(dir.path())?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_future_timestamp() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        let modified = Timestamp::from_path(dir.path().join("pyproject.toml"))?;
+        let cache_keys = || vec![CacheKey::Path("pyproject.toml".into())];
+
+        // A timestamp in the past is recorded as-is.
+        let now = Timestamp::from(SystemTime::now() + Duration::from_secs(60 * 60));
+        let cache_info =
+            CacheInfo::from_cache_keys(dir.path(), cache_keys(), Vec::new(), None, None, now)?;
+        assert_eq!(cache_info.timestamp, Some(modified));
+
+        // As is a timestamp that's only slightly in the future.
+        let now = Timestamp::from(SystemTime::now() - Duration::from_secs(10));
+        let cache_info =
+            CacheInfo::from_cache_keys(dir.path(), cache_keys(), Vec::new(), None, None, now)?;
+        assert_eq!(cache_info.timestamp, Some(modified));
+
+        // A timestamp further in the future (here, by rewinding the clock) is clamped to the
+        // current time.
+        let now = Timestamp::from(SystemTime::now() - Duration::from_secs(60 * 60));
+        let cache_info =
+            CacheInfo::from_cache_keys(dir.path(), cache_keys(), Vec::new(), None, None, now)?;
+        assert_eq!(cache_info.timestamp, Some(now));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_info_markers() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let markers = CacheMarkers::new("linux", "x86_64", "3.12.1");
+
+        // Without the `markers` cache key, the marker environment is ignored.
+        fs_err::write(dir.path().join("pyproject.toml"), "")?;
+        let cache_info = CacheInfo::from_directory_with_markers(dir.path(), &markers)?;
+        assert_eq!(
+            cache_info,
+            CacheInfo::from_directory_with_markers(
+                dir.path(),
+                &CacheMarkers::new("darwin", "arm64", "3.13.0")
+            )?
+        );
+
+        // With the `markers` cache key, each marker value contributes to the cache key.
+        fs_err::write(
+            dir.path().join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ markers = true }]\n",
+        )?;
+        let cache_info = CacheInfo::from_directory_with_markers(dir.path(), &markers)?;
+        assert_eq!(
+            cache_info,
+            CacheInfo::from_directory_with_markers(dir.path(), &markers)?
+        );
+        for other in [
+            CacheMarkers::new("darwin", "x86_64", "3.12.1"),
+            CacheMarkers::new("linux", "aarch64", "3.12.1"),
+            CacheMarkers::new("linux", "x86_64", "3.12.2"),
+        ] {
+            assert_ne!(
+                cache_info,
+                CacheInfo::from_directory_with_markers(dir.path(), &other)?
+            );
+        }
+
+        // Without a marker environment, the `markers` cache key is ignored.
+        assert!(CacheInfo::from_directory(dir.path())?.markers.is_none());
+
+        Ok(())
+    }
+}
@@ -1,9 +1,14 @@
 use std::collections::BTreeMap;
-use std::path::{Path, PathBuf};
+use std::fmt::Write as _;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
 
+use flate2::read::ZlibDecoder;
 use tracing::warn;
 use walkdir::WalkDir;
 
+use uv_fs::normalize_path;
+
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum GitInfoError {
     #[error("The repository at {0} is missing a `.git` directory")]
@@ -14,6 +19,16 @@ pub(crate) enum GitInfoError {
     MissingRefs(PathBuf),
     #[error("The repository at {0} has an invalid reference: `{1}`")]
     InvalidRef(PathBuf, String),
+    #[error("`{1}` is outside of the repository at {0}")]
+    OutsidePath(PathBuf, String),
+    #[error("`{1}` isn't tracked in the current commit of the repository at {0}")]
+    UntrackedPath(PathBuf, String),
+    #[error("The repository at {0} is missing the object `{1}`")]
+    MissingObject(PathBuf, String),
+    #[error("The repository at {0} has an invalid object: `{1}`")]
+    InvalidObject(PathBuf, String),
+    #[error("The archival file at {0} is missing a `node` entry")]
+    InvalidArchival(PathBuf),
     #[error("The discovered commit has an invalid length (expected 40 characters): `{0}`")]
     WrongLength(String),
     #[error("The discovered commit has an invalid character (expected hexadecimal): `{0}`")]
@@ -22,7 +37,8 @@ pub(crate) enum GitInfoError {
     Io(#[from] std::io::Error),
 }
 
-/// The current commit for a repository (i.e., a 40-character hexadecimal string).
+/// The current commit for a repository (i.e., a 40-character hexadecimal string), or the ID of
+/// another object within it (like a tree).
 #[derive(Default, Debug, Clone, Hash, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub(crate) struct Commit(String);
 
@@ -38,7 +54,7 @@ impl Commit {
 
         let git_head_path =
             git_head(&git_dir).ok_or_else(|| GitInfoError::MissingHead(git_dir.clone()))?;
-        let git_head_contents = fs_err::read_to_string(git_head_path)?;
+        let git_head_contents = fs_err::read_to_string(&git_head_path)?;
 
         // The contents are either a commit or a reference in the following formats
         // - "<commit>" when the head is detached
@@ -50,21 +66,91 @@ impl Commit {
             .next()
             .ok_or_else(|| GitInfoError::InvalidRef(git_dir.clone(), git_head_contents.clone()))?;
         let commit = if let Some(git_ref) = git_ref_parts.next() {
-            let git_ref_path = git_dir.join(git_ref);
-            let commit = fs_err::read_to_string(git_ref_path)?;
-            commit.trim().to_string()
+            let common_dir = git_common_dir(&git_head_path);
+            resolve_ref(&common_dir, git_ref)?
+                .ok_or_else(|| GitInfoError::InvalidRef(git_dir.clone(), git_ref.to_string()))?
         } else {
             commit_or_ref.to_string()
         };
 
-        // The commit should be 40 hexadecimal characters.
+        Self::validate(commit)
+    }
+
+    /// Return the ID of the tree (or blob) at the given path (relative to `directory`) in the
+    /// current commit, without invoking `git`.
+    ///
+    /// Unlike the current commit, the ID only changes when a commit modifies the path, such that
+    /// commits elsewhere in the repository are ignored.
+    pub(crate) fn from_path(directory: &Path, path: &str) -> Result<Self, GitInfoError> {
+        // Find the `.git` directory, searching through parent directories if necessary.
+        let git_dir = directory
+            .ancestors()
+            .map(|ancestor| ancestor.join(".git"))
+            .find(|git_dir| git_dir.exists())
+            .ok_or_else(|| GitInfoError::MissingGitDir(directory.to_path_buf()))?;
+        let git_head_path =
+            git_head(&git_dir).ok_or_else(|| GitInfoError::MissingHead(git_dir.clone()))?;
+        let objects = Objects::new(git_common_dir(&git_head_path).join("objects"));
+
+        // Determine the path relative to the root of the working tree.
+        let root = git_dir.parent().unwrap_or(Path::new(""));
+        let relative = normalize_path(&directory.join(path))
+            .strip_prefix(normalize_path(root))
+            .map(Path::to_path_buf)
+            .map_err(|_| GitInfoError::OutsidePath(root.to_path_buf(), path.to_string()))?;
+
+        // Walk from the root tree of the current commit down to the path.
+        let commit = Self::from_repository(directory)?;
+        let mut id = objects
+            .read(&commit.0, ObjectKind::Commit)?
+            .strip_prefix(b"tree ")
+            .and_then(|rest| rest.get(..40))
+            .and_then(|id| std::str::from_utf8(id).ok())
+            .map(ToString::to_string)
+            .ok_or_else(|| GitInfoError::InvalidObject(objects.dir.clone(), commit.0.clone()))?;
+        for component in relative.components() {
+            let Component::Normal(name) = component else {
+                continue;
+            };
+            let tree = objects
+                .read(&id, ObjectKind::Tree)
+                .map_err(|err| match err {
+                    // A blob can't contain any further paths.
+                    GitInfoError::InvalidObject(..) => {
+                        GitInfoError::UntrackedPath(root.to_path_buf(), path.to_string())
+                    }
+                    err => err,
+                })?;
+            id = tree_entry(&tree, name.as_encoded_bytes())
+                .ok_or_else(|| GitInfoError::UntrackedPath(root.to_path_buf(), path.to_string()))?;
+        }
+        Self::validate(id)
+    }
+
+    /// Return the [`Commit`] recorded in the `.git_archival.txt` file in the given directory, as
+    /// embedded by `git archive` (e.g., for projects that use `setuptools_scm`).
+    ///
+    /// The file is expected to contain a `node: <commit>` line, as substituted by `git archive`
+    /// from the `$Format:%H$` placeholder.
+    pub(crate) fn from_git_archival(directory: &Path) -> Result<Self, GitInfoError> {
+        let path = directory.join(".git_archival.txt");
+        let contents = fs_err::read_to_string(&path)?;
+        let commit = contents
+            .lines()
+            .find_map(|line| line.strip_prefix("node:"))
+            .map(str::trim)
+            .ok_or_else(|| GitInfoError::InvalidArchival(path.clone()))?;
+        Self::validate(commit.to_string())
+    }
+
+    /// Validate that the commit is a 40-character hexadecimal string.
+    fn validate(commit: String) -> Result<Self, GitInfoError> {
         if commit.len() != 40 {
             return Err(GitInfoError::WrongLength(commit));
         }
         if commit.chars().any(|c| !c.is_ascii_hexdigit()) {
             return Err(GitInfoError::WrongDigit(commit));
         }
-
         Ok(Self(commit))
     }
 }
@@ -146,7 +232,49 @@ fn git_head(git_dir: &Path) -> Option<PathBuf> {
         return None;
     }
     let worktree_path = worktree_path.trim();
-    Some(PathBuf::from(worktree_path))
+    Some(PathBuf::from(worktree_path).join("HEAD"))
+}
+
+/// Return the directory that contains the shared `refs` and `packed-refs` of a Git repository,
+/// given the path to its `HEAD` file.
+///
+/// For a standard repository, this is the `.git` directory itself. For a worktree, the
+/// per-worktree directory (e.g., `.git/worktrees/pr2`) contains a `commondir` file that points
+/// to the main `.git` directory.
+fn git_common_dir(git_head_path: &Path) -> PathBuf {
+    let Some(git_dir) = git_head_path.parent() else {
+        return PathBuf::new();
+    };
+    match fs_err::read_to_string(git_dir.join("commondir")) {
+        Ok(common_dir) => git_dir.join(common_dir.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// Resolve a reference (e.g., `refs/heads/main`) to a commit, without invoking `git`.
+///
+/// Loose references are checked first, followed by the `packed-refs` file, in which Git stores
+/// references after (e.g.) `git gc` or `git pack-refs`.
+fn resolve_ref(common_dir: &Path, git_ref: &str) -> Result<Option<String>, GitInfoError> {
+    match fs_err::read_to_string(common_dir.join(git_ref)) {
+        Ok(commit) => return Ok(Some(commit.trim().to_string())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    // Each line in `packed-refs` is either a comment (`# pack-refs with: ...`), a reference
+    // (`<commit> <ref>`), or the peeled commit of the preceding annotated tag (`^<commit>`).
+    let packed_refs = match fs_err::read_to_string(common_dir.join("packed-refs")) {
+        Ok(packed_refs) => packed_refs,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(packed_refs
+        .lines()
+        .filter(|line| !line.starts_with(['#', '^']))
+        .filter_map(|line| line.split_once(' '))
+        .find(|(_, name)| name.trim() == git_ref)
+        .map(|(commit, _)| commit.to_string()))
 }
 
 /// Return the path to the `refs` directory of a Git repository, taking worktrees into account.
@@ -177,3 +305,594 @@ fn git_refs(git_dir: &Path) -> Option<PathBuf> {
     let refs_path = worktree_path.parent()?.parent()?.join("refs");
     Some(refs_path)
 }
+
+/// The kind of an object in a Git repository.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ObjectKind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl ObjectKind {
+    /// Parse the kind from the header of a loose object (e.g., `tree`).
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"commit" => Some(Self::Commit),
+            b"tree" => Some(Self::Tree),
+            b"blob" => Some(Self::Blob),
+            b"tag" => Some(Self::Tag),
+            _ => None,
+        }
+    }
+
+    /// Parse the kind from the type bits of a packed object.
+    fn from_packed(kind: u8) -> Option<Self> {
+        match kind {
+            1 => Some(Self::Commit),
+            2 => Some(Self::Tree),
+            3 => Some(Self::Blob),
+            4 => Some(Self::Tag),
+            _ => None,
+        }
+    }
+}
+
+/// The packed type of an object stored as a delta against an object at an earlier offset in the
+/// same pack.
+const OFS_DELTA: u8 = 6;
+
+/// The packed type of an object stored as a delta against an object with the given ID.
+const REF_DELTA: u8 = 7;
+
+/// A reader for the object database of a Git repository (i.e., the `.git/objects` directory),
+/// supporting both loose objects and pack files.
+///
+/// Only SHA-1 repositories are supported, and alternate object databases are ignored.
+struct Objects {
+    dir: PathBuf,
+}
+
+impl Objects {
+    fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Read the object with the given ID, which must be of the given kind.
+    fn read(&self, id: &str, expected: ObjectKind) -> Result<Vec<u8>, GitInfoError> {
+        let (kind, data) = self.read_any(id)?;
+        if kind != expected {
+            return Err(self.invalid(id));
+        }
+        Ok(data)
+    }
+
+    /// Read the object with the given ID, along with its kind.
+    fn read_any(&self, id: &str) -> Result<(ObjectKind, Vec<u8>), GitInfoError> {
+        let oid = decode_oid(id).ok_or_else(|| self.invalid(id))?;
+
+        // Loose objects are stored as `objects/ab/cdef...`, compressed, with a `<kind> <size>\0`
+        // header.
+        match fs_err::File::open(self.dir.join(&id[..2]).join(&id[2..])) {
+            Ok(file) => {
+                let mut contents = Vec::new();
+                ZlibDecoder::new(file).read_to_end(&mut contents)?;
+                let header_len = contents
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .ok_or_else(|| self.invalid(id))?;
+                let kind = contents[..header_len]
+                    .split(|&byte| byte == b' ')
+                    .next()
+                    .and_then(ObjectKind::from_name)
+                    .ok_or_else(|| self.invalid(id))?;
+                contents.drain(..=header_len);
+                return Ok((kind, contents));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        // Otherwise, look for the object in each pack.
+        let packs = match fs_err::read_dir(self.dir.join("pack")) {
+            Ok(packs) => packs,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(GitInfoError::MissingObject(
+                    self.dir.clone(),
+                    id.to_string(),
+                ));
+            }
+            Err(err) => return Err(err.into()),
+        };
+        for entry in packs {
+            let index = entry?.path();
+            if index.extension().is_none_or(|extension| extension != "idx") {
+                continue;
+            }
+            if let Some(offset) = self.find_packed(&index, &oid)? {
+                return self.read_packed(&index.with_extension("pack"), offset);
+            }
+        }
+        Err(GitInfoError::MissingObject(
+            self.dir.clone(),
+            id.to_string(),
+        ))
+    }
+
+    /// Find the offset of the object with the given ID in the pack with the given (version 2)
+    /// index, if present.
+    fn find_packed(&self, index: &Path, oid: &[u8; 20]) -> Result<Option<u64>, GitInfoError> {
+        let mut file = BufReader::new(fs_err::File::open(index)?);
+
+        // The index starts with a magic number and version, followed by a fan-out table, in which
+        // the Nth entry is the number of objects whose ID starts with a byte of at most N.
+        let mut header = [0; 8 + 256 * 4];
+        file.read_exact(&mut header)?;
+        if header[..8] != [0xff, b't', b'O', b'c', 0, 0, 0, 2] {
+            return Err(self.invalid(&index.display().to_string()));
+        }
+        let fanout = |byte: usize| {
+            let start = 8 + byte * 4;
+            u64::from(u32::from_be_bytes(
+                header[start..start + 4].try_into().unwrap(),
+            ))
+        };
+        let count = fanout(255);
+        let mut low = if oid[0] == 0 {
+            0
+        } else {
+            fanout(usize::from(oid[0]) - 1)
+        };
+        let mut high = fanout(usize::from(oid[0]));
+
+        // The fan-out table is followed by the sorted object IDs, the CRCs of the packed objects,
+        // and their offsets into the pack.
+        let ids = 8 + 256 * 4;
+        let offsets = ids + count * (20 + 4);
+        let large_offsets = offsets + count * 4;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let mut candidate = [0; 20];
+            file.seek(SeekFrom::Start(ids + middle * 20))?;
+            file.read_exact(&mut candidate)?;
+            match candidate.cmp(oid) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => {
+                    let mut offset = [0; 4];
+                    file.seek(SeekFrom::Start(offsets + middle * 4))?;
+                    file.read_exact(&mut offset)?;
+                    let offset = u32::from_be_bytes(offset);
+
+                    // Offsets beyond 2 GiB are stored in a separate table of 8-byte offsets.
+                    if offset & 0x8000_0000 == 0 {
+                        return Ok(Some(u64::from(offset)));
+                    }
+                    let mut large_offset = [0; 8];
+                    file.seek(SeekFrom::Start(
+                        large_offsets + u64::from(offset & 0x7fff_ffff) * 8,
+                    ))?;
+                    file.read_exact(&mut large_offset)?;
+                    return Ok(Some(u64::from_be_bytes(large_offset)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Read the object at the given offset in a pack, resolving any deltas.
+    fn read_packed(&self, pack: &Path, offset: u64) -> Result<(ObjectKind, Vec<u8>), GitInfoError> {
+        let mut file = BufReader::new(fs_err::File::open(pack)?);
+        file.seek(SeekFrom::Start(offset))?;
+        let mut next = || -> Result<u8, GitInfoError> {
+            let mut byte = [0];
+            file.read_exact(&mut byte)?;
+            Ok(byte[0])
+        };
+
+        // Each object starts with its type and (variable-length) inflated size.
+        let mut byte = next()?;
+        let kind = (byte >> 4) & 0b111;
+        let mut size = u64::from(byte & 0b1111);
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            byte = next()?;
+            size |= u64::from(byte & 0x7f) << shift;
+            shift += 7;
+        }
+
+        // Deltas are preceded by a reference to their base object.
+        let base = match kind {
+            OFS_DELTA => {
+                byte = next()?;
+                let mut distance = u64::from(byte & 0x7f);
+                while byte & 0x80 != 0 {
+                    byte = next()?;
+                    distance = ((distance + 1) << 7) | u64::from(byte & 0x7f);
+                }
+                let base_offset = offset
+                    .checked_sub(distance)
+                    .ok_or_else(|| self.invalid(&pack.display().to_string()))?;
+                Some(self.read_packed(pack, base_offset)?)
+            }
+            REF_DELTA => {
+                let mut base_id = String::with_capacity(40);
+                for _ in 0..20 {
+                    write!(base_id, "{:02x}", next()?).unwrap();
+                }
+                Some(self.read_any(&base_id)?)
+            }
+            _ => None,
+        };
+
+        let mut data = Vec::with_capacity(usize::try_from(size).unwrap_or_default());
+        ZlibDecoder::new(file).take(size).read_to_end(&mut data)?;
+
+        if let Some((kind, base)) = base {
+            let data = apply_delta(&base, &data)
+                .ok_or_else(|| self.invalid(&pack.display().to_string()))?;
+            Ok((kind, data))
+        } else {
+            let kind = ObjectKind::from_packed(kind)
+                .ok_or_else(|| self.invalid(&pack.display().to_string()))?;
+            Ok((kind, data))
+        }
+    }
+
+    fn invalid(&self, id: &str) -> GitInfoError {
+        GitInfoError::InvalidObject(self.dir.clone(), id.to_string())
+    }
+}
+
+/// Decode a 40-character hexadecimal object ID.
+fn decode_oid(id: &str) -> Option<[u8; 20]> {
+    if id.len() != 40 {
+        return None;
+    }
+    let mut oid = [0; 20];
+    for (index, byte) in oid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(id.get(index * 2..index * 2 + 2)?, 16).ok()?;
+    }
+    Some(oid)
+}
+
+/// Return the ID of the entry with the given name in a tree object.
+///
+/// Each entry is stored as `<mode> <name>\0` followed by the 20-byte object ID.
+fn tree_entry(tree: &[u8], name: &[u8]) -> Option<String> {
+    let mut rest = tree;
+    while !rest.is_empty() {
+        let header_len = rest.iter().position(|&byte| byte == 0)?;
+        let header = &rest[..header_len];
+        let entry_name = &header[header.iter().position(|&byte| byte == b' ')? + 1..];
+        let oid = rest.get(header_len + 1..header_len + 21)?;
+        if entry_name == name {
+            let mut id = String::with_capacity(40);
+            for byte in oid {
+                write!(id, "{byte:02x}").unwrap();
+            }
+            return Some(id);
+        }
+        rest = &rest[header_len + 21..];
+    }
+    None
+}
+
+/// Apply a delta (as stored in a pack) to the given base object.
+///
+/// The delta starts with the (variable-length) sizes of the base and result, followed by a
+/// sequence of instructions, each of which either copies a range of the base or inserts literal
+/// data.
+fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    let mut delta = delta.iter().copied();
+    let mut size = || {
+        let mut size = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = delta.next()?;
+            size |= usize::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Some(size);
+            }
+        }
+    };
+    let base_size = size()?;
+    let result_size = size()?;
+    if base_size != base.len() {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(result_size);
+    while let Some(instruction) = delta.next() {
+        if instruction & 0x80 != 0 {
+            // Copy a range of the base, with the offset and size encoded in up to four and three
+            // bytes, respectively, as indicated by the low bits of the instruction.
+            let mut offset = 0usize;
+            for index in 0..4 {
+                if instruction & (1 << index) != 0 {
+                    offset |= usize::from(delta.next()?) << (8 * index);
+                }
+            }
+            let mut length = 0usize;
+            for index in 0..3 {
+                if instruction & (0x10 << index) != 0 {
+                    length |= usize::from(delta.next()?) << (8 * index);
+                }
+            }
+            if length == 0 {
+                length = 0x10000;
+            }
+            result.extend_from_slice(base.get(offset..offset.checked_add(length)?)?);
+        } else if instruction != 0 {
+            // Insert the following bytes.
+            for _ in 0..instruction {
+                result.push(delta.next()?);
+            }
+        } else {
+            return None;
+        }
+    }
+    (result.len() == result_size).then_some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use std::path::Path;
+    use std::process::Command;
+
+    use anyhow::{Result, bail};
+
+    use super::{Commit, ObjectKind, Objects};
+    use crate::CacheInfo;
+
+    /// Run a `git` command in the given directory, returning its trimmed output.
+    fn git(dir: &Path, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .args([
+                "-c",
+                "user.name=uv",
+                "-c",
+                "user.email=uv@astral.sh",
+                "-c",
+                "commit.gpgsign=false",
+                "-c",
+                "init.defaultBranch=main",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .output()?;
+        if !output.status.success() {
+            bail!(
+                "`git {}` failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+
+    #[test]
+    fn commit_matches_git() -> Result<()> {
+        if Command::new("git").arg("--version").output().is_err() {
+            return Ok(());
+        }
+
+        let dir = tempfile::tempdir()?;
+        let repo = dir.path().join("repo");
+        fs_err::create_dir_all(&repo)?;
+        git(&repo, &["init"])?;
+        git(&repo, &["commit", "--allow-empty", "-m", "first"])?;
+
+        // A loose reference.
+        let head = git(&repo, &["rev-parse", "HEAD"])?;
+        assert_eq!(Commit::from_repository(&repo)?, Commit(head));
+
+        // A packed reference.
+        git(&repo, &["commit", "--allow-empty", "-m", "second"])?;
+        git(&repo, &["pack-refs", "--all"])?;
+        assert!(!repo.join(".git/refs/heads/main").exists());
+        let head = git(&repo, &["rev-parse", "HEAD"])?;
+        assert_eq!(Commit::from_repository(&repo)?, Commit(head));
+
+        // A detached `HEAD`.
+        git(&repo, &["checkout", "--detach", "HEAD~1"])?;
+        let head = git(&repo, &["rev-parse", "HEAD"])?;
+        assert_eq!(Commit::from_repository(&repo)?, Commit(head));
+
+        // A worktree, whose branch is stored in the main repository.
+        let worktree = dir.path().join("worktree");
+        git(
+            &repo,
+            &[
+                "worktree",
+                "add",
+                "-b",
+                "feature",
+                worktree.to_str().unwrap(),
+            ],
+        )?;
+        git(&worktree, &["commit", "--allow-empty", "-m", "third"])?;
+        let head = git(&worktree, &["rev-parse", "HEAD"])?;
+        assert_eq!(Commit::from_repository(&worktree)?, Commit(head));
+
+        Ok(())
+    }
+
+    #[test]
+    fn commit_for_path() -> Result<()> {
+        if Command::new("git").arg("--version").output().is_err() {
+            return Ok(());
+        }
+
+        // A monorepo with a project in a subdirectory.
+        let dir = tempfile::tempdir()?;
+        let repo = dir.path().join("repo");
+        let project = repo.join("project");
+        fs_err::create_dir_all(&project)?;
+        fs_err::create_dir_all(repo.join("other"))?;
+        fs_err::write(
+            project.join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ git = true, path = \".\" }]\n",
+        )?;
+        git(&repo, &["init"])?;
+        git(&repo, &["add", "."])?;
+        git(&repo, &["commit", "-m", "first"])?;
+        let first = git(&repo, &["rev-parse", "HEAD:project"])?;
+        assert_eq!(Commit::from_path(&project, ".")?, Commit(first));
+        let cache_info = CacheInfo::from_directory(&project)?;
+
+        // An unrelated commit doesn't invalidate the project.
+        fs_err::write(repo.join("other").join("README.md"), "")?;
+        git(&repo, &["add", "."])?;
+        git(&repo, &["commit", "-m", "second"])?;
+        assert_eq!(CacheInfo::from_directory(&project)?, cache_info);
+
+        // A commit that touches the project does.
+        fs_err::write(project.join("README.md"), "")?;
+        git(&repo, &["add", "."])?;
+        git(&repo, &["commit", "-m", "third"])?;
+        let third = git(&repo, &["rev-parse", "HEAD:project"])?;
+        assert_eq!(Commit::from_path(&project, ".")?, Commit(third));
+        assert_ne!(CacheInfo::from_directory(&project)?, cache_info);
+
+        // A path that isn't tracked is an error.
+        assert!(Commit::from_path(&project, "missing").is_err());
+        assert!(Commit::from_path(&project, "README.md/missing").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn commit_for_path_packed() -> Result<()> {
+        if Command::new("git").arg("--version").output().is_err() {
+            return Ok(());
+        }
+
+        // A file that's edited across several commits, such that packing stores its revisions as
+        // deltas.
+        let dir = tempfile::tempdir()?;
+        let repo = dir.path().join("repo");
+        let project = repo.join("project");
+        fs_err::create_dir_all(&project)?;
+        git(&repo, &["init"])?;
+        let mut contents = String::new();
+        for line in 0..1000 {
+            writeln!(contents, "line = {line}")?;
+        }
+        for revision in 0..5 {
+            writeln!(contents, "revision = {revision}")?;
+            fs_err::write(project.join("module.py"), &contents)?;
+            git(&repo, &["add", "."])?;
+            git(&repo, &["commit", "-m", &format!("revision {revision}")])?;
+        }
+        git(&repo, &["gc", "--aggressive", "--prune=now"])?;
+        assert!(
+            !repo
+                .join(".git/objects")
+                .read_dir()?
+                .any(|entry| { entry.is_ok_and(|entry| entry.file_name().len() == 2) })
+        );
+
+        for (path, spec) in [
+            (".", "HEAD:project"),
+            ("module.py", "HEAD:project/module.py"),
+        ] {
+            let expected = git(&repo, &["rev-parse", spec])?;
+            assert_eq!(Commit::from_path(&project, path)?, Commit(expected));
+        }
+
+        // Reading the blob exercises delta resolution, since the latest revision of a file is
+        // typically stored in full and older revisions as deltas.
+        let objects = Objects::new(repo.join(".git/objects"));
+        for revision in 0..5 {
+            let id = git(
+                &repo,
+                &["rev-parse", &format!("HEAD~{revision}:project/module.py")],
+            )?;
+            let expected = git(&repo, &["cat-file", "blob", &id])?;
+            let blob = objects.read(&id, ObjectKind::Blob)?;
+            assert_eq!(String::from_utf8(blob)?.trim(), expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn commits_for_multiple_roots() -> Result<()> {
+        if Command::new("git").arg("--version").output().is_err() {
+            return Ok(());
+        }
+
+        // A project with a vendored tree that has its own repository.
+        let dir = tempfile::tempdir()?;
+        let project = dir.path().join("project");
+        let vendored = project.join("vendor").join("lib");
+        fs_err::create_dir_all(&vendored)?;
+        fs_err::write(
+            project.join("pyproject.toml"),
+            "[tool.uv]\ncache-keys = [{ git = true }, { git = true, path = \"vendor/lib\" }]\n",
+        )?;
+        fs_err::write(project.join(".gitignore"), "vendor/\n")?;
+        git(&project, &["init"])?;
+        git(&project, &["add", "."])?;
+        git(&project, &["commit", "-m", "first"])?;
+        git(&vendored, &["init"])?;
+        git(&vendored, &["commit", "--allow-empty", "-m", "first"])?;
+        let cache_info = CacheInfo::from_directory(&project)?;
+
+        // A commit to the vendored repository invalidates the project, even though the
+        // enclosing repository is unchanged.
+        git(&vendored, &["commit", "--allow-empty", "-m", "second"])?;
+        let vendored_commit = CacheInfo::from_directory(&project)?;
+        assert_ne!(vendored_commit, cache_info);
+
+        // As does a commit to the enclosing repository.
+        git(&project, &["commit", "--allow-empty", "-m", "second"])?;
+        let project_commit = CacheInfo::from_directory(&project)?;
+        assert_ne!(project_commit, vendored_commit);
+        assert_eq!(CacheInfo::from_directory(&project)?, project_commit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn commit_from_git_archival() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let commit = "8b8b6f2b5b8a8e7ba2ad8a7d0c8c4a0f4a6c1e3d";
+        let cache_keys = "[tool.uv]\ncache-keys = [{ git = { commit = true } }]\n";
+        fs_err::write(dir.path().join("pyproject.toml"), cache_keys)?;
+        let without_commit = CacheInfo::from_directory(dir.path())?;
+
+        // An archive produced by `git archive`, with the placeholders substituted.
+        fs_err::write(
+            dir.path().join(".git_archival.txt"),
+            format!(
+                "node: {commit}\nnode-date: 2024-01-01T00:00:00+00:00\nref-names: HEAD -> main\n"
+            ),
+        )?;
+        assert_eq!(
+            Commit::from_git_archival(dir.path())?,
+            Commit(commit.to_string())
+        );
+        let with_commit = CacheInfo::from_directory(dir.path())?;
+        assert_ne!(with_commit, without_commit);
+
+        // An archival file whose placeholders weren't substituted degrades to no commit.
+        fs_err::write(
+            dir.path().join(".git_archival.txt"),
+            "node: $Format:%H$\nnode-date: $Format:%cI$\n",
+        )?;
+        assert!(Commit::from_git_archival(dir.path()).is_err());
+        assert_eq!(CacheInfo::from_directory(dir.path())?, without_commit);
+
+        // As does an archival file without a `node` entry.
+        fs_err::write(dir.path().join(".git_archival.txt"), "ref-names: HEAD\n")?;
+        assert!(Commit::from_git_archival(dir.path()).is_err());
+        assert_eq!(CacheInfo::from_directory(dir.path())?, without_commit);
+
+        Ok(())
+    }
+}
@@ -3,6 +3,10 @@ use std::{
     path::{Component, Components, Path, PathBuf},
 };
 
+use tracing::warn;
+
+use crate::cache_info::CacheInfoError;
+
 /// Check if a component of the path looks like it may be a glob pattern.
 ///
 /// Note: this function is being used when splitting a glob pattern into a long possible
@@ -160,6 +164,225 @@ pub(crate) fn cluster_globs(patterns: &[impl AsRef<str>]) -> Vec<(PathBuf, Vec<S
         .collect()
 }
 
+/// Returns `true` if the cache key path contains any glob metacharacters.
+pub(crate) fn is_glob(path: &str) -> bool {
+    path.chars().any(|c| matches!(c, '*' | '?' | '[' | '{'))
+}
+
+/// Returns `true` if the (relative) path is Python bytecode, i.e., a `.pyc` or `.pyo` file, or any
+/// file within a `__pycache__` directory.
+///
+/// Bytecode is rewritten on import, so it's excluded from globs by default.
+fn is_bytecode(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == "__pycache__")
+        || path
+            .extension()
+            .is_some_and(|extension| extension == "pyc" || extension == "pyo")
+}
+
+/// The options with which a group of globs is expanded, as set on a `{ file = "..." }` cache key.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct GlobOptions {
+    /// Whether to skip files that are excluded by a `.gitignore` file.
+    pub(crate) respect_gitignore: bool,
+    /// Whether to include Python bytecode.
+    pub(crate) include_bytecode: bool,
+    /// Whether to anchor each glob to its base directory.
+    pub(crate) anchored: bool,
+}
+
+/// Expand the given globs, relative to `directory`, into the files (including resolved symlinks)
+/// that they match, along with their metadata.
+///
+/// The globs are clustered by their longest common prefix, such that each cluster is expanded in
+/// a single pass.
+pub(crate) fn glob_files(
+    directory: &Path,
+    globs: &[impl AsRef<str>],
+    options: GlobOptions,
+) -> Result<Vec<(PathBuf, std::fs::Metadata)>, CacheInfoError> {
+    let GlobOptions {
+        respect_gitignore,
+        include_bytecode,
+        anchored,
+    } = options;
+    let mut files = Vec::new();
+    for (glob_base, glob_patterns) in cluster_globs(globs) {
+        // `globwalk` follows `.gitignore` semantics, under which a pattern without a separator
+        // (like `*.py`, including `src/*.py` once clustered under `src`) matches at any depth. If
+        // requested, anchor each pattern to the base directory, such that `*` only matches within
+        // a single directory, while `**` matches across any number of nested directories.
+        let glob_patterns = if anchored {
+            glob_patterns
+                .iter()
+                .map(|pattern| format!("/{pattern}"))
+                .collect::<Vec<_>>()
+        } else {
+            glob_patterns
+        };
+        let root = directory.join(glob_base);
+        let entries: Box<dyn Iterator<Item = Result<GlobEntry, String>>> = if respect_gitignore {
+            Box::new(walk_gitignore(directory, &root, &glob_patterns)?)
+        } else {
+            let walker = globwalk::GlobWalkerBuilder::from_patterns(&root, &glob_patterns)
+                .file_type(globwalk::FileType::FILE | globwalk::FileType::SYMLINK)
+                .build()?;
+            Box::new(walker.map(|entry| {
+                entry
+                    .map(|entry| {
+                        let is_symlink = entry.path_is_symlink();
+                        let metadata = if is_symlink {
+                            None
+                        } else {
+                            listed_metadata(|| entry.metadata().ok())
+                        };
+                        GlobEntry {
+                            path: entry.into_path(),
+                            is_symlink,
+                            metadata,
+                        }
+                    })
+                    .map_err(|err| err.to_string())
+            }))
+        };
+        for entry in entries {
+            let GlobEntry {
+                path,
+                is_symlink,
+                metadata,
+            } = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    warn!("Failed to read glob entry: {err}");
+                    continue;
+                }
+            };
+            if !include_bytecode && is_bytecode(path.strip_prefix(&root).unwrap_or(&path)) {
+                continue;
+            }
+            let metadata = if is_symlink {
+                // resolve symlinks for leaf entries without following symlinks while globbing
+                match fs_err::metadata(&path) {
+                    Ok(metadata) => metadata,
+                    Err(err) => {
+                        warn!("Failed to resolve symlink for glob entry: {err}");
+                        continue;
+                    }
+                }
+            } else if let Some(metadata) = metadata {
+                metadata
+            } else {
+                match path.symlink_metadata() {
+                    Ok(metadata) => metadata,
+                    Err(err) => {
+                        warn!("Failed to read metadata for glob entry: {err}");
+                        continue;
+                    }
+                }
+            };
+            if !metadata.is_file() {
+                if !is_symlink {
+                    // don't warn if it was a symlink - it may legitimately resolve to a directory
+                    warn!(
+                        "Expected file for cache key, but found directory: `{}`",
+                        path.display()
+                    );
+                }
+                continue;
+            }
+            files.push((path, metadata));
+        }
+    }
+    Ok(files)
+}
+
+/// A file (or symlink) matched while expanding a glob.
+struct GlobEntry {
+    path: PathBuf,
+    is_symlink: bool,
+    /// The entry's metadata, if it was captured while reading its parent directory.
+    metadata: Option<std::fs::Metadata>,
+}
+
+/// Return the metadata for a (non-symlink) entry, if the platform includes it in directory
+/// listings, such that it can be reused rather than requesting it separately for each file.
+///
+/// Only Windows includes metadata in directory listings; elsewhere, this returns `None`, and the
+/// caller is expected to read the metadata itself. Either way, the resulting timestamps are
+/// identical.
+fn listed_metadata(
+    metadata: impl FnOnce() -> Option<std::fs::Metadata>,
+) -> Option<std::fs::Metadata> {
+    if cfg!(windows) { metadata() } else { None }
+}
+
+/// Walk the files (and symlinks) under `root` that match any of the given glob patterns, skipping
+/// any paths excluded by a `.gitignore` file, including nested `.gitignore` files.
+///
+/// If `root` is within `directory` (the project root), the walk starts at `directory` and descends
+/// directly to `root`, such that the `.gitignore` files between the two are respected too.
+/// `.gitignore` files are respected even if `root` isn't within a Git repository.
+fn walk_gitignore(
+    directory: &Path,
+    root: &Path,
+    patterns: &[String],
+) -> Result<impl Iterator<Item = Result<GlobEntry, String>> + use<>, CacheInfoError> {
+    let mut builder = ignore::overrides::OverrideBuilder::new(root);
+    for pattern in patterns {
+        builder.add(pattern)?;
+    }
+    let globs = builder.build()?;
+
+    let start = if root
+        .strip_prefix(directory)
+        .is_ok_and(|base| base.components().all(|c| matches!(c, Component::Normal(_))))
+    {
+        directory
+    } else {
+        root
+    };
+    let root = root.to_path_buf();
+    let walker = ignore::WalkBuilder::new(start)
+        .standard_filters(false)
+        .git_ignore(true)
+        .require_git(false)
+        .filter_entry({
+            let root = root.clone();
+            move |entry| root.starts_with(entry.path()) || entry.path().starts_with(&root)
+        })
+        .build();
+    Ok(walker.filter_map(move |entry| {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => return Some(Err(err.to_string())),
+        };
+        let file_type = entry.file_type()?;
+        if !(file_type.is_file() || file_type.is_symlink()) {
+            return None;
+        }
+        if !entry.path().starts_with(&root) {
+            return None;
+        }
+        // Match the globs separately, rather than as overrides on the walker, since overrides
+        // take precedence over `.gitignore` files.
+        if !globs.matched(entry.path(), false).is_whitelist() {
+            return None;
+        }
+        let is_symlink = entry.path_is_symlink();
+        let metadata = if is_symlink {
+            None
+        } else {
+            listed_metadata(|| entry.metadata().ok())
+        };
+        Some(Ok(GlobEntry {
+            path: entry.into_path(),
+            is_symlink,
+            metadata,
+        }))
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::{GlobParts, cluster_globs, split_glob};
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::path::Path;
+use tracing::warn;
+
+use uv_static::EnvVars;
 
 /// A timestamp used to measure changes to a file.
 ///
@@ -39,10 +42,68 @@ impl Timestamp {
         }
     }
 
+    /// Return the [`Timestamp`] given by the `SOURCE_DATE_EPOCH` environment variable, if set.
+    ///
+    /// See: <https://reproducible-builds.org/specs/source-date-epoch/>
+    pub fn from_source_date_epoch() -> Option<Self> {
+        let value = std::env::var(EnvVars::SOURCE_DATE_EPOCH).ok()?;
+        match value.trim().parse::<u64>() {
+            Ok(seconds) => Some(Self(
+                std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds),
+            )),
+            Err(err) => {
+                warn!("Ignoring invalid `SOURCE_DATE_EPOCH` value `{value}`: {err}");
+                None
+            }
+        }
+    }
+
     /// Return the current [`Timestamp`].
     pub fn now() -> Self {
         Self(std::time::SystemTime::now())
     }
+
+    /// Returns `true` if the [`Timestamp`] is later than `now` by more than the given tolerance.
+    pub(crate) fn is_after(self, now: Self, tolerance: std::time::Duration) -> bool {
+        now.0
+            .checked_add(tolerance)
+            .is_some_and(|limit| self.0 > limit)
+    }
+
+    /// Truncate the [`Timestamp`] to the given granularity.
+    #[must_use]
+    pub fn truncate(self, granularity: TimestampGranularity) -> Self {
+        match granularity {
+            TimestampGranularity::Exact => self,
+            TimestampGranularity::Seconds => match self.0.duration_since(std::time::UNIX_EPOCH) {
+                Ok(duration) => {
+                    Self(std::time::UNIX_EPOCH + std::time::Duration::from_secs(duration.as_secs()))
+                }
+                Err(_) => self,
+            },
+            TimestampGranularity::Ignore => Self(std::time::UNIX_EPOCH),
+        }
+    }
+}
+
+/// The granularity at which to compare [`Timestamp`]s.
+///
+/// Filesystems differ in timestamp resolution (e.g., some record timestamps at whole-second
+/// resolution, while ext4 records nanoseconds), so timestamps recorded on one filesystem may
+/// differ from those read on another by sub-second noise.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampGranularity {
+    /// Compare timestamps at the full resolution reported by the filesystem.
+    #[default]
+    Exact,
+    /// Compare timestamps at whole-second resolution, ignoring any sub-second components.
+    Seconds,
+    /// Ignore timestamps entirely, such that only the presence of the timestamped files and
+    /// directories (along with any non-timestamp keys, like the Git commit) is compared.
+    ///
+    /// Suitable for immutable sources, where timestamps change on (e.g.) a fresh checkout even
+    /// though the contents don't.
+    Ignore,
 }
 
 impl From<std::time::SystemTime> for Timestamp {
@@ -1,4 +1,8 @@
+use std::path::PathBuf;
+
 use uv_normalize::PackageName;
+use uv_pep440::VersionSpecifiers;
+use uv_pypi_types::VerbatimParsedUrl;
 
 pub trait Diagnostic {
     /// Convert the diagnostic into a user-facing message.
@@ -6,4 +10,25 @@ pub trait Diagnostic {
 
     /// Returns `true` if the [`PackageName`] is involved in this diagnostic.
     fn includes(&self, name: &PackageName) -> bool;
+
+    /// Returns a machine-actionable remediation for the diagnostic, if there's a clear one.
+    fn suggested_fix(&self) -> Option<SuggestedFix> {
+        None
+    }
+}
+
+/// A structured remediation for a [`Diagnostic`], separate from its user-facing message (e.g., to
+/// offer an "apply fix" action).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SuggestedFix {
+    /// Reinstall the package.
+    Reinstall(PackageName),
+    /// Install a package that satisfies the requirement.
+    Install(Box<uv_pep508::Requirement<VerbatimParsedUrl>>),
+    /// Remove the file or directory at the given path.
+    RemovePath(PathBuf),
+    /// Use a Python version that satisfies the given specifiers.
+    UpgradePython(VersionSpecifiers),
+    /// Recreate the environment.
+    RecreateEnvironment,
 }
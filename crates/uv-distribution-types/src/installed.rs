@@ -2,7 +2,7 @@ use std::borrow::Cow;
 use std::io::BufReader;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
 use fs_err as fs;
 use thiserror::Error;
@@ -67,6 +67,10 @@ pub enum InstalledDistError {
         #[source]
         err: Box<MetadataError>,
     },
+
+    /// A failure from a previous read, as cached on the distribution.
+    #[error(transparent)]
+    Cached(Arc<Self>),
 }
 
 #[derive(Debug, Clone)]
@@ -74,7 +78,7 @@ pub struct InstalledDist {
     pub kind: InstalledDistKind,
     // Cache data that must be read from the `.dist-info` directory. These are safe to cache as
     // the `InstalledDist` is immutable after creation.
-    metadata_cache: OnceLock<uv_pypi_types::ResolutionMetadata>,
+    metadata_cache: OnceLock<Result<uv_pypi_types::ResolutionMetadata, Arc<InstalledDistError>>>,
     tags_cache: OnceLock<Option<ExpandedTags>>,
 }
 
@@ -168,7 +172,10 @@ impl InstalledDist {
     /// See: <https://packaging.python.org/en/latest/specifications/recording-installed-packages/#recording-installed-packages>
     pub fn try_from_path(path: &Path) -> Result<Option<Self>, InstalledDistError> {
         // Ex) `cffi-1.16.0.dist-info`
-        if path.extension().is_some_and(|ext| ext == "dist-info") {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("dist-info"))
+        {
             let Some(file_stem) = path.file_stem() else {
                 return Ok(None);
             };
@@ -225,7 +232,10 @@ impl InstalledDist {
         }
 
         // Ex) `zstandard-0.22.0-py3.12.egg-info` or `vtk-9.2.6.egg-info`
-        if path.extension().is_some_and(|ext| ext == "egg-info") {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("egg-info"))
+        {
             let metadata = match fs_err::metadata(path) {
                 Ok(metadata) => metadata,
                 Err(err) => {
@@ -292,7 +302,10 @@ impl InstalledDist {
         }
 
         // Ex) `zstandard.egg-link`
-        if path.extension().is_some_and(|ext| ext == "egg-link") {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("egg-link"))
+        {
             let Some(file_stem) = path.file_stem() else {
                 return Ok(None);
             };
@@ -430,11 +443,17 @@ impl InstalledDist {
     }
 
     /// Read the `METADATA` file from a `.dist-info` directory.
+    ///
+    /// The result is cached, including any failure, such that the file is read at most once.
     pub fn read_metadata(&self) -> Result<&uv_pypi_types::ResolutionMetadata, InstalledDistError> {
-        if let Some(metadata) = self.metadata_cache.get() {
-            return Ok(metadata);
-        }
+        self.metadata_cache
+            .get_or_init(|| self.parse_metadata().map_err(Arc::new))
+            .as_ref()
+            .map_err(|err| InstalledDistError::Cached(err.clone()))
+    }
 
+    /// Read and parse the `METADATA` (or `PKG-INFO`) file, bypassing the cache.
+    fn parse_metadata(&self) -> Result<uv_pypi_types::ResolutionMetadata, InstalledDistError> {
         let metadata = match &self.kind {
             InstalledDistKind::Registry(_) | InstalledDistKind::Url(_) => {
                 let path = self.install_path().join("METADATA");
@@ -470,8 +489,7 @@ impl InstalledDist {
             }
         };
 
-        let _ = self.metadata_cache.set(metadata);
-        Ok(self.metadata_cache.get().expect("metadata should be set"))
+        Ok(metadata)
     }
 
     /// Return the `INSTALLER` of the distribution.
@@ -9,23 +9,28 @@ use uv_distribution_types::{
 };
 use uv_normalize::PackageName;
 use uv_platform_tags::Tags;
-use uv_pypi_types::HashDigests;
-use uv_types::HashStrategy;
+use uv_pypi_types::{HashDigests, ResolverMarkerEnvironment};
+use uv_types::{BuildIsolation, HashStrategy};
 
 use crate::Error;
 use crate::index::cached_wheel::{CachedWheel, ResolvedWheel};
-use crate::source::{HTTP_REVISION, HttpRevisionPointer, LOCAL_REVISION, LocalRevisionPointer};
+use crate::source::{
+    HTTP_REVISION, HttpRevisionPointer, LOCAL_REVISION, LocalRevisionPointer,
+    source_tree_cache_info,
+};
 
 /// A local index of built distributions for a specific source distribution.
 #[derive(Debug)]
 pub struct BuiltWheelIndex<'a> {
     cache: &'a Cache,
     tags: &'a Tags,
+    markers: &'a ResolverMarkerEnvironment,
     hasher: &'a HashStrategy,
     config_settings: &'a ConfigSettings,
     config_settings_package: &'a PackageConfigSettings,
     extra_build_requires: &'a ExtraBuildRequires,
     extra_build_variables: &'a ExtraBuildVariables,
+    build_isolation: BuildIsolation<'a>,
 }
 
 impl<'a> BuiltWheelIndex<'a> {
@@ -33,20 +38,24 @@ impl<'a> BuiltWheelIndex<'a> {
     pub fn new(
         cache: &'a Cache,
         tags: &'a Tags,
+        markers: &'a ResolverMarkerEnvironment,
         hasher: &'a HashStrategy,
         config_settings: &'a ConfigSettings,
         config_settings_package: &'a PackageConfigSettings,
         extra_build_requires: &'a ExtraBuildRequires,
         extra_build_variables: &'a ExtraBuildVariables,
+        build_isolation: BuildIsolation<'a>,
     ) -> Self {
         Self {
             cache,
             tags,
+            markers,
             hasher,
             config_settings,
             config_settings_package,
             extra_build_requires,
             extra_build_variables,
+            build_isolation,
         }
     }
 
@@ -110,8 +119,9 @@ impl<'a> BuiltWheelIndex<'a> {
         };
 
         // If the distribution is stale, omit it from the index.
-        let cache_info =
-            CacheInfo::from_file(&source_dist.install_path).map_err(Error::CacheRead)?;
+        let cache_info = CacheInfo::from_file(&source_dist.install_path)
+            .map_err(Error::CacheRead)?
+            .with_build_isolation(self.build_isolation.is_isolated(Some(&source_dist.name)));
         if cache_info != *pointer.cache_info() {
             return Ok(None);
         }
@@ -162,7 +172,11 @@ impl<'a> BuiltWheelIndex<'a> {
         };
 
         // If the distribution is stale, omit it from the index.
-        let cache_info = CacheInfo::from_directory(&source_dist.install_path)?;
+        let cache_info = source_tree_cache_info(
+            &source_dist.install_path,
+            self.markers,
+            self.build_isolation.is_isolated(Some(&source_dist.name)),
+        )?;
         if cache_info != *pointer.cache_info() {
             return Ok(None);
         }
@@ -305,3 +319,178 @@ impl<'a> BuiltWheelIndex<'a> {
         self.extra_build_variables.get(name)
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::path::Path;
+    use std::str::FromStr;
+
+    use anyhow::Result;
+
+    use uv_cache::{Cache, CacheBucket, WheelCache};
+    use uv_cache_info::CacheInfo;
+    use uv_distribution_types::{
+        ConfigSettings, DirectorySourceDist, ExtraBuildRequires, ExtraBuildVariables,
+        PackageConfigSettings,
+    };
+    use uv_normalize::PackageName;
+    use uv_pep508::{MarkerEnvironment, MarkerEnvironmentBuilder, VerbatimUrl};
+    use uv_platform_tags::{AbiTag, LanguageTag, PlatformTag, Tags};
+    use uv_pypi_types::ResolverMarkerEnvironment;
+    use uv_types::{BuildIsolation, HashStrategy};
+
+    use super::BuiltWheelIndex;
+    use crate::source::{LOCAL_REVISION, LocalRevisionPointer, source_tree_cache_info};
+
+    /// Return a marker environment for a Linux machine with the given Python version.
+    fn markers(python_full_version: &str) -> Result<ResolverMarkerEnvironment> {
+        Ok(ResolverMarkerEnvironment::from(
+            MarkerEnvironment::try_from(MarkerEnvironmentBuilder {
+                implementation_name: "cpython",
+                implementation_version: python_full_version,
+                os_name: "posix",
+                platform_machine: "x86_64",
+                platform_python_implementation: "CPython",
+                platform_release: "6.5.0-1016-azure",
+                platform_system: "Linux",
+                platform_version: "#16~22.04.1-Ubuntu SMP Fri Feb 16 15:42:02 UTC 2024",
+                python_full_version,
+                python_version: "3.12",
+                sys_platform: "linux",
+            })?,
+        ))
+    }
+
+    /// Create a project with the given `pyproject.toml`, and return it as a source distribution.
+    fn project(root: &Path, pyproject_toml: &str) -> Result<DirectorySourceDist> {
+        let project = root.join("project");
+        fs_err::create_dir_all(&project)?;
+        fs_err::write(project.join("pyproject.toml"), pyproject_toml)?;
+        Ok(DirectorySourceDist {
+            name: PackageName::from_str("foo")?,
+            install_path: project.clone().into_boxed_path(),
+            editable: Some(false),
+            r#virtual: None,
+            url: VerbatimUrl::from_absolute_path(&project)?,
+        })
+    }
+
+    /// Persist a revision with the given cache info (as the source distribution builder does),
+    /// along with a wheel built from it.
+    fn persist(
+        cache: &Cache,
+        source_dist: &DirectorySourceDist,
+        cache_info: CacheInfo,
+    ) -> Result<()> {
+        let shard = cache.shard(
+            CacheBucket::SourceDistributions,
+            WheelCache::Path(&source_dist.url).root(),
+        );
+        let pointer = LocalRevisionPointer::new(cache_info);
+        let entry = shard.entry(LOCAL_REVISION);
+        fs_err::create_dir_all(entry.dir())?;
+        fs_err::write(entry.path(), rmp_serde::to_vec(&pointer)?)?;
+        fs_err::create_dir_all(
+            shard
+                .shard(pointer.revision().id())
+                .join("foo-0.1.0-py3-none-any"),
+        )?;
+        Ok(())
+    }
+
+    /// Look up the wheel built from the source distribution.
+    fn lookup(
+        cache: &Cache,
+        source_dist: &DirectorySourceDist,
+        markers: &ResolverMarkerEnvironment,
+        build_isolation: BuildIsolation,
+    ) -> Result<bool> {
+        let tags = Tags::new(vec![(
+            LanguageTag::Python {
+                major: 3,
+                minor: None,
+            },
+            AbiTag::None,
+            PlatformTag::Any,
+        )]);
+        let wheel = BuiltWheelIndex::new(
+            cache,
+            &tags,
+            markers,
+            &HashStrategy::None,
+            &ConfigSettings::default(),
+            &PackageConfigSettings::default(),
+            &ExtraBuildRequires::default(),
+            &ExtraBuildVariables::default(),
+            build_isolation,
+        )
+        .directory(source_dist)?;
+        Ok(wheel.is_some_and(|wheel| wheel.filename.to_string() == "foo-0.1.0-py3-none-any.whl"))
+    }
+
+    #[test]
+    fn directory_with_markers() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let cache = Cache::from_path(temp_dir.path().join("cache"));
+        let source_dist = project(
+            temp_dir.path(),
+            "[tool.uv]\ncache-keys = [{ file = \"pyproject.toml\" }, { markers = true }]\n",
+        )?;
+        let markers = markers("3.12.2")?;
+        persist(
+            &cache,
+            &source_dist,
+            source_tree_cache_info(&source_dist.install_path, &markers, true)?,
+        )?;
+
+        // The wheel is found under the same markers, but not under a different Python version.
+        assert!(lookup(
+            &cache,
+            &source_dist,
+            &markers,
+            BuildIsolation::Isolated
+        )?);
+        assert!(!lookup(
+            &cache,
+            &source_dist,
+            &self::markers("3.12.3")?,
+            BuildIsolation::Isolated
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn directory_with_build_isolation() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let cache = Cache::from_path(temp_dir.path().join("cache"));
+        let source_dist = project(temp_dir.path(), "[project]\nname = \"foo\"\n")?;
+        let markers = markers("3.12.2")?;
+
+        // A wheel built without build isolation isn't reused for an isolated build.
+        persist(
+            &cache,
+            &source_dist,
+            source_tree_cache_info(&source_dist.install_path, &markers, false)?,
+        )?;
+        assert!(!lookup(
+            &cache,
+            &source_dist,
+            &markers,
+            BuildIsolation::Isolated
+        )?);
+
+        // Once rebuilt in isolation, it's found.
+        persist(
+            &cache,
+            &source_dist,
+            source_tree_cache_info(&source_dist.install_path, &markers, true)?,
+        )?;
+        assert!(lookup(
+            &cache,
+            &source_dist,
+            &markers,
+            BuildIsolation::Isolated
+        )?);
+        Ok(())
+    }
+}
@@ -24,7 +24,7 @@ use uv_redacted::DisplaySafeUrl;
 use zip::ZipArchive;
 
 use uv_cache::{Cache, CacheBucket, CacheEntry, CacheShard, Removal, WheelCache};
-use uv_cache_info::CacheInfo;
+use uv_cache_info::{CacheInfo, CacheInfoError, CacheMarkers};
 use uv_client::{
     CacheControl, CachedClientError, Connectivity, DataWithCachePolicy, RegistryClient,
 };
@@ -42,7 +42,10 @@ use uv_metadata::read_archive_metadata;
 use uv_normalize::PackageName;
 use uv_pep440::{Version, release_specifiers_to_ranges};
 use uv_platform_tags::Tags;
-use uv_pypi_types::{HashAlgorithm, HashDigest, HashDigests, PyProjectToml, ResolutionMetadata};
+use uv_pypi_types::{
+    HashAlgorithm, HashDigest, HashDigests, PyProjectToml, ResolutionMetadata,
+    ResolverMarkerEnvironment,
+};
 use uv_types::{BuildContext, BuildKey, BuildStack, SourceBuildTrait};
 use uv_workspace::pyproject::ToolUvSources;
 
@@ -1098,7 +1101,13 @@ impl<'a, T: BuildContext> SourceDistributionBuilder<'a, T> {
         }
 
         // Determine the last-modified time of the source distribution.
-        let cache_info = CacheInfo::from_file(&resource.path).map_err(Error::CacheRead)?;
+        let cache_info = CacheInfo::from_file(&resource.path)
+            .map_err(Error::CacheRead)?
+            .with_build_isolation(
+                self.build_context
+                    .build_isolation()
+                    .is_isolated(source.name()),
+            );
 
         // Read the existing metadata from the cache.
         let revision_entry = cache_shard.entry(LOCAL_REVISION);
@@ -1446,7 +1455,16 @@ impl<'a, T: BuildContext> SourceDistributionBuilder<'a, T> {
         }
 
         // Determine the last-modified time of the source distribution.
-        let cache_info = CacheInfo::from_directory(&resource.install_path)?;
+        let markers = self
+            .build_context
+            .interpreter()
+            .await
+            .resolver_marker_environment();
+        let build_isolation = self
+            .build_context
+            .build_isolation()
+            .is_isolated(source.name());
+        let cache_info = source_tree_cache_info(&resource.install_path, &markers, build_isolation)?;
 
         // Read the existing metadata from the cache.
         let entry = cache_shard.entry(LOCAL_REVISION);
@@ -1475,11 +1493,7 @@ impl<'a, T: BuildContext> SourceDistributionBuilder<'a, T> {
         }
 
         // Otherwise, we need to create a new revision.
-        let revision = Revision::new();
-        let pointer = LocalRevisionPointer {
-            cache_info,
-            revision,
-        };
+        let pointer = LocalRevisionPointer::new(cache_info);
         pointer.write_to(&entry).await?;
 
         Ok(pointer)
@@ -2935,6 +2949,14 @@ pub(crate) struct LocalRevisionPointer {
 }
 
 impl LocalRevisionPointer {
+    /// Create a [`LocalRevisionPointer`] to a new [`Revision`] with the given [`CacheInfo`].
+    pub(crate) fn new(cache_info: CacheInfo) -> Self {
+        Self {
+            cache_info,
+            revision: Revision::new(),
+        }
+    }
+
     /// Read an [`LocalRevisionPointer`] from the cache.
     pub(crate) fn read_from(path: impl AsRef<Path>) -> Result<Option<Self>, Error> {
         match fs_err::read(path) {
@@ -2970,6 +2992,27 @@ impl LocalRevisionPointer {
     }
 }
 
+/// Compute the [`CacheInfo`] for a local source tree, including the marker environment if the
+/// project requests it via `cache-keys`, and whether the source tree is built in isolation.
+///
+/// The same cache info must be computed when persisting a revision and when looking up the wheels
+/// built from it (as in [`crate::BuiltWheelIndex`]), or the revision will never be fresh.
+pub(crate) fn source_tree_cache_info(
+    path: &Path,
+    markers: &ResolverMarkerEnvironment,
+    build_isolation: bool,
+) -> Result<CacheInfo, CacheInfoError> {
+    Ok(CacheInfo::from_directory_with_markers(
+        path,
+        &CacheMarkers::new(
+            markers.sys_platform(),
+            markers.platform_machine(),
+            markers.python_full_version().to_string(),
+        ),
+    )?
+    .with_build_isolation(build_isolation))
+}
+
 /// Read the [`ResolutionMetadata`] from a source distribution's `PKG-INFO` file, if it uses Metadata 2.2
 /// or later _and_ none of the required fields (`Requires-Python`, `Requires-Dist`, and
 /// `Provides-Extra`) are marked as dynamic.
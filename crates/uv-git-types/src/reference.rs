@@ -55,6 +55,14 @@ impl GitReference {
         }
     }
 
+    /// Returns the revision if it could be a (possibly abbreviated) commit hash.
+    pub fn as_commit(&self) -> Option<&str> {
+        match self {
+            Self::BranchOrTagOrCommit(rev) if looks_like_commit_hash(rev) => Some(rev),
+            _ => None,
+        }
+    }
+
     /// Returns the kind of this reference.
     pub fn kind_str(&self) -> &str {
         match self {
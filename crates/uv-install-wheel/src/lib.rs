@@ -13,8 +13,9 @@ use uv_pypi_types::Scheme;
 
 pub use install::install_wheel;
 pub use linker::{LinkMode, Locks};
+pub use script::script_names;
 pub use uninstall::{Uninstall, uninstall_egg, uninstall_legacy_editable, uninstall_wheel};
-pub use wheel::{LibKind, WheelFile, read_record_file};
+pub use wheel::{LibKind, WheelFile, read_record_file, script_path};
 
 mod install;
 mod linker;
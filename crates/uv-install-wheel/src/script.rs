@@ -107,6 +107,17 @@ pub(crate) fn scripts_from_ini(
     Ok((console_scripts, gui_scripts))
 }
 
+/// Returns the names of the console and GUI scripts defined in an `entry_points.txt` file, as
+/// they're installed for the given Python minor version.
+pub fn script_names(entry_points: String, python_minor: u8) -> Result<Vec<String>, Error> {
+    let (console_scripts, gui_scripts) = scripts_from_ini(None, python_minor, entry_points)?;
+    Ok(console_scripts
+        .into_iter()
+        .chain(gui_scripts)
+        .map(|script| script.name)
+        .collect())
+}
+
 #[cfg(test)]
 mod test {
     use crate::script::{Script, scripts_from_ini};
@@ -160,19 +160,24 @@ fn get_script_executable(python_executable: &Path, is_gui: bool) -> PathBuf {
 
 /// Determine the absolute path to an entrypoint script.
 fn entrypoint_path(entrypoint: &Script, layout: &Layout) -> PathBuf {
+    script_path(&layout.scheme.scripts, &entrypoint.name)
+}
+
+/// Determine the absolute path to the entrypoint script with the given name, within the given
+/// scripts directory (e.g., `bin` or `Scripts`).
+pub fn script_path(scripts: &Path, name: &str) -> PathBuf {
     if cfg!(windows) {
         // On windows we actually build an .exe wrapper
-        let script_name = entrypoint
-            .name
+        let script_name = name
             // FIXME: What are the in-reality rules here for names?
             .strip_suffix(".py")
-            .unwrap_or(&entrypoint.name)
+            .unwrap_or(name)
             .to_string()
             + ".exe";
 
-        layout.scheme.scripts.join(script_name)
+        scripts.join(script_name)
     } else {
-        layout.scheme.scripts.join(&entrypoint.name)
+        scripts.join(name)
     }
 }
 
@@ -0,0 +1,84 @@
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+
+use uv_distribution_types::InstalledDist;
+use uv_pep440::VersionSpecifiers;
+use uv_pep508::{Requirement, VersionOrUrl};
+use uv_pypi_types::VerbatimParsedUrl;
+
+/// A graph of the distributions installed in an environment.
+///
+/// Each node is an installed distribution. Each edge points from a distribution to an installed
+/// distribution that satisfies one of its `Requires-Dist` entries, and is weighted by that
+/// requirement.
+#[derive(Debug, Default)]
+pub struct DependencyGraph<'env> {
+    graph: Graph<&'env InstalledDist, &'env Requirement<VerbatimParsedUrl>>,
+}
+
+impl<'env> DependencyGraph<'env> {
+    /// Add an installed distribution to the graph.
+    pub(crate) fn add_node(&mut self, distribution: &'env InstalledDist) -> NodeIndex {
+        self.graph.add_node(distribution)
+    }
+
+    /// Add a dependency edge from `source` to `target`.
+    pub(crate) fn add_edge(
+        &mut self,
+        source: NodeIndex,
+        target: NodeIndex,
+        requirement: &'env Requirement<VerbatimParsedUrl>,
+    ) {
+        self.graph.add_edge(source, target, requirement);
+    }
+
+    /// Return the number of installed distributions in the graph.
+    pub fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    /// Return the number of dependency edges in the graph.
+    pub fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Iterate over the installed distributions in the graph.
+    pub fn nodes(&self) -> impl Iterator<Item = &'env InstalledDist> + '_ {
+        self.graph.node_weights().copied()
+    }
+
+    /// Iterate over the dependency edges in the graph.
+    pub fn edges(&self) -> impl Iterator<Item = DependencyEdge<'env>> + '_ {
+        self.graph.edge_references().map(|edge| DependencyEdge {
+            source: self.graph[edge.source()],
+            target: self.graph[edge.target()],
+            requirement: edge.weight(),
+        })
+    }
+
+    /// Return the underlying [`Graph`].
+    pub fn graph(&self) -> &Graph<&'env InstalledDist, &'env Requirement<VerbatimParsedUrl>> {
+        &self.graph
+    }
+}
+
+/// A dependency from one installed distribution on another.
+#[derive(Debug, Copy, Clone)]
+pub struct DependencyEdge<'env> {
+    /// The distribution that declares the dependency.
+    pub source: &'env InstalledDist,
+    /// The installed distribution that satisfies the dependency.
+    pub target: &'env InstalledDist,
+    /// The requirement, as declared in the source distribution's metadata.
+    pub requirement: &'env Requirement<VerbatimParsedUrl>,
+}
+
+impl DependencyEdge<'_> {
+    /// Return the version specifiers of the requirement, if it isn't a URL requirement.
+    pub fn specifier(&self) -> Option<&VersionSpecifiers> {
+        match &self.requirement.version_or_url {
+            Some(VersionOrUrl::VersionSpecifier(specifier)) => Some(specifier),
+            Some(VersionOrUrl::Url(_)) | None => None,
+        }
+    }
+}
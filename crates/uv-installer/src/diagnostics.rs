@@ -0,0 +1,3353 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use fs_err as fs;
+use rustc_hash::{FxHashMap, FxHashSet};
+use same_file::is_same_file;
+use url::Url;
+use version_ranges::Ranges;
+
+use uv_distribution_filename::ExpandedTags;
+use uv_distribution_types::{
+    Diagnostic, InstalledDirectUrlDist, InstalledDist, InstalledDistKind, SuggestedFix,
+};
+use uv_fs::Simplified;
+use uv_install_wheel::{LibKind, read_record_file};
+use uv_normalize::PackageName;
+use uv_pep440::{Version, VersionSpecifiers};
+use uv_pep508::{MarkerTree, VerbatimUrl, VersionOrUrl};
+use uv_platform_tags::{AbiTag, IncompatibleTag, TagCompatibility, Tags};
+use uv_pypi_types::{DirectUrl, Metadata23, ResolverMarkerEnvironment, VerbatimParsedUrl};
+use uv_python::Interpreter;
+
+use crate::site_packages::{SitePackages, check_cancelled, invalid_version, record_files};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SitePackagesDiagnostic {
+    MetadataUnavailable {
+        /// The package that is missing metadata.
+        package: PackageName,
+        /// The path to the package.
+        path: PathBuf,
+    },
+    TruncatedMetadata {
+        /// The package with an empty `METADATA` file.
+        package: PackageName,
+        /// The path to the package.
+        path: PathBuf,
+    },
+    EditableLayoutMismatch {
+        /// The editable package that isn't importable from its `.pth` entries.
+        package: PackageName,
+        /// The paths added by the package's `.pth` files.
+        search_paths: Vec<PathBuf>,
+        /// The directory in the project that contains the importable package.
+        found: PathBuf,
+    },
+    SplitDistribution {
+        /// The package whose files are split across `purelib` and `platlib`.
+        package: PackageName,
+        /// The path to the package's `.dist-info` directory.
+        path: PathBuf,
+        /// The files that were found in the other library directory.
+        files: Vec<PathBuf>,
+    },
+    MissingInstalledFile {
+        /// The package that's missing a file.
+        package: PackageName,
+        /// The path to the file listed in the package's `RECORD`.
+        path: PathBuf,
+    },
+    DuplicateRecordEntry {
+        /// The package whose `RECORD` lists the file more than once.
+        package: PackageName,
+        /// The path to the file listed in the package's `RECORD`.
+        path: PathBuf,
+    },
+    IncompleteUninstall {
+        /// The package whose `.dist-info` directory remains, but most of whose files are missing.
+        package: PackageName,
+        /// The number of files listed in the `RECORD` that are missing, excluding those in the
+        /// `.dist-info` directory.
+        missing: usize,
+        /// The number of files listed in the `RECORD`, excluding those in the `.dist-info`
+        /// directory.
+        total: usize,
+    },
+    RecordNameMismatch {
+        /// The package whose `RECORD` refers to a different `.dist-info` directory.
+        package: PackageName,
+        /// The name of the `.dist-info` directory, as referenced in the `RECORD`.
+        record_name: String,
+        /// The name of the `.dist-info` directory on disk.
+        disk_name: String,
+    },
+    RelocatedEnvironment {
+        /// The package that installed the script.
+        package: PackageName,
+        /// The name of the script.
+        script: String,
+        /// The path to the environment's interpreter.
+        expected: PathBuf,
+        /// The path to the interpreter referenced by the script's shebang.
+        found: PathBuf,
+    },
+    StaleBytecode {
+        /// The package that contains the stale bytecode.
+        package: PackageName,
+        /// The path to the `.pyc` file.
+        path: PathBuf,
+    },
+    InvalidVersion {
+        /// The package with the invalid version.
+        package: PackageName,
+        /// The version, as recorded in the `.dist-info` directory name.
+        raw: String,
+    },
+    UnverifiedRecordSignature {
+        /// The package whose `RECORD` signature couldn't be verified.
+        package: PackageName,
+    },
+    MissingBuildTool {
+        /// The editable package that is rebuilt on import.
+        package: PackageName,
+        /// The build backend that rebuilds the package.
+        backend: PackageName,
+        /// The build tool that isn't installed.
+        tool: PackageName,
+    },
+    MalformedWheelMetadata {
+        /// The package whose `WHEEL` file is missing or lacks a `Wheel-Version`.
+        package: PackageName,
+    },
+    UnresolvedDynamicMetadata {
+        /// The package whose `METADATA` lists `Dynamic` fields.
+        package: PackageName,
+        /// The fields that are marked as `Dynamic`.
+        fields: Vec<String>,
+    },
+    TagsUnavailable {
+        /// The package that is missing tags.
+        package: PackageName,
+        /// The path to the package.
+        path: PathBuf,
+    },
+    IncompatiblePythonVersion {
+        /// The package that requires a different version of Python.
+        package: PackageName,
+        /// The version of Python that is installed.
+        version: Version,
+        /// The version of Python that is required.
+        requires_python: VersionSpecifiers,
+    },
+    IncompatiblePlatform {
+        /// The package that was built for a different platform.
+        package: PackageName,
+    },
+    IncompatibleInterpreterBuild {
+        /// The package that was built for a different build of the same Python version (e.g., a
+        /// GIL-enabled build, rather than a free-threaded build).
+        package: PackageName,
+    },
+    MissingDependency {
+        /// The package that is missing a dependency.
+        package: PackageName,
+        /// The dependency that is missing.
+        requirement: uv_pep508::Requirement<VerbatimParsedUrl>,
+    },
+    IncompatibleDependency {
+        /// The package that has an incompatible dependency.
+        package: PackageName,
+        /// The version of the package that is installed.
+        version: Version,
+        /// The dependency that is incompatible.
+        requirement: uv_pep508::Requirement<VerbatimParsedUrl>,
+    },
+    SelfDependency {
+        /// The package that declares a dependency on itself.
+        package: PackageName,
+    },
+    PeerConflict {
+        /// The dependency that's shared by multiple packages.
+        dependency: PackageName,
+        /// The packages that require the dependency, along with their version specifiers.
+        requiring: Vec<(PackageName, VersionSpecifiers)>,
+        /// The installed version of the dependency.
+        installed: Version,
+    },
+    DuplicatePackage {
+        /// The package that has multiple installed distributions.
+        package: PackageName,
+        /// The installed versions of the package.
+        paths: Vec<PathBuf>,
+    },
+    DuplicateSameVersion {
+        /// The package that has multiple installed distributions with the same version.
+        package: PackageName,
+        /// The version shared by the distributions.
+        version: Version,
+        /// The paths to the distributions.
+        paths: Vec<PathBuf>,
+    },
+}
+
+impl SitePackagesDiagnostic {
+    /// Returns a stable, machine-readable identifier for the kind of diagnostic (e.g.,
+    /// `missing-dependency`).
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MetadataUnavailable { .. } => "metadata-unavailable",
+            Self::TruncatedMetadata { .. } => "truncated-metadata",
+            Self::EditableLayoutMismatch { .. } => "editable-layout-mismatch",
+            Self::SplitDistribution { .. } => "split-distribution",
+            Self::MissingInstalledFile { .. } => "missing-installed-file",
+            Self::DuplicateRecordEntry { .. } => "duplicate-record-entry",
+            Self::IncompleteUninstall { .. } => "incomplete-uninstall",
+            Self::RecordNameMismatch { .. } => "record-name-mismatch",
+            Self::RelocatedEnvironment { .. } => "relocated-environment",
+            Self::StaleBytecode { .. } => "stale-bytecode",
+            Self::MissingBuildTool { .. } => "missing-build-tool",
+            Self::UnverifiedRecordSignature { .. } => "unverified-record-signature",
+            Self::InvalidVersion { .. } => "invalid-version",
+            Self::MalformedWheelMetadata { .. } => "malformed-wheel-metadata",
+            Self::UnresolvedDynamicMetadata { .. } => "unresolved-dynamic-metadata",
+            Self::TagsUnavailable { .. } => "tags-unavailable",
+            Self::IncompatiblePythonVersion { .. } => "incompatible-python-version",
+            Self::IncompatiblePlatform { .. } => "incompatible-platform",
+            Self::IncompatibleInterpreterBuild { .. } => "incompatible-interpreter-build",
+            Self::MissingDependency { .. } => "missing-dependency",
+            Self::IncompatibleDependency { .. } => "incompatible-dependency",
+            Self::SelfDependency { .. } => "self-dependency",
+            Self::PeerConflict { .. } => "peer-conflict",
+            Self::DuplicatePackage { .. } => "duplicate-package",
+            Self::DuplicateSameVersion { .. } => "duplicate-same-version",
+        }
+    }
+
+    /// Returns the severity of the diagnostic.
+    ///
+    /// Errors indicate that a package may fail to import or run (e.g., a missing dependency or
+    /// file), while warnings indicate that the environment is inconsistent, but likely usable.
+    pub fn severity(&self) -> DiagnosticSeverity {
+        match self {
+            Self::MetadataUnavailable { .. }
+            | Self::TruncatedMetadata { .. }
+            | Self::SplitDistribution { .. }
+            | Self::MissingInstalledFile { .. }
+            | Self::IncompleteUninstall { .. }
+            | Self::RelocatedEnvironment { .. }
+            | Self::TagsUnavailable { .. }
+            | Self::IncompatiblePythonVersion { .. }
+            | Self::IncompatiblePlatform { .. }
+            | Self::IncompatibleInterpreterBuild { .. }
+            | Self::MissingDependency { .. }
+            | Self::IncompatibleDependency { .. }
+            | Self::DuplicateSameVersion { .. }
+            | Self::UnverifiedRecordSignature { .. }
+            | Self::InvalidVersion { .. } => DiagnosticSeverity::Error,
+            Self::EditableLayoutMismatch { .. }
+            | Self::DuplicateRecordEntry { .. }
+            | Self::RecordNameMismatch { .. }
+            | Self::StaleBytecode { .. }
+            | Self::MissingBuildTool { .. }
+            | Self::MalformedWheelMetadata { .. }
+            | Self::UnresolvedDynamicMetadata { .. }
+            | Self::SelfDependency { .. }
+            | Self::PeerConflict { .. }
+            | Self::DuplicatePackage { .. } => DiagnosticSeverity::Warning,
+        }
+    }
+}
+
+/// The severity of a [`SitePackagesDiagnostic`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticSeverity {
+    /// The environment is inconsistent, but likely usable.
+    Warning,
+    /// A package may fail to import or run.
+    Error,
+}
+
+/// An overview of the health of an environment, as returned by [`SitePackages::health_summary`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HealthSummary {
+    /// The overall status, derived from the most severe diagnostic.
+    pub status: HealthStatus,
+    /// The number of diagnostics of each kind, keyed by [`SitePackagesDiagnostic::code`].
+    pub counts: BTreeMap<&'static str, usize>,
+}
+
+/// The overall status in a [`HealthSummary`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HealthStatus {
+    /// No diagnostics were found.
+    Ok,
+    /// At least one warning, but no errors, was found.
+    Warning,
+    /// At least one error was found.
+    Error,
+}
+
+impl From<DiagnosticSeverity> for HealthStatus {
+    fn from(severity: DiagnosticSeverity) -> Self {
+        match severity {
+            DiagnosticSeverity::Warning => Self::Warning,
+            DiagnosticSeverity::Error => Self::Error,
+        }
+    }
+}
+
+impl Diagnostic for SitePackagesDiagnostic {
+    /// Convert the diagnostic into a user-facing message.
+    fn message(&self) -> String {
+        match self {
+            Self::MetadataUnavailable { package, path } => format!(
+                "The package `{package}` is broken or incomplete (unable to read `METADATA`). Consider recreating the virtualenv, or removing the package directory at: {}.",
+                path.display(),
+            ),
+            Self::TruncatedMetadata { package, path } => format!(
+                "The package `{package}` is broken or incomplete (`METADATA` is empty, likely due to an interrupted install). Consider reinstalling it with `--reinstall`, or removing the package directory at: {}.",
+                path.display(),
+            ),
+            Self::EditableLayoutMismatch {
+                package,
+                search_paths,
+                found,
+            } => format!(
+                "The editable package `{package}` was found at `{}`, which isn't on the path added by its `.pth` file ({}). Consider checking whether the project is configured for a `src` or flat layout.",
+                found.user_display(),
+                search_paths
+                    .iter()
+                    .map(|path| format!("`{}`", path.user_display()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Self::SplitDistribution {
+                package,
+                path,
+                files,
+            } => format!(
+                "The package `{package}` is installed at `{}`, but some of its files were found in a different library directory (`purelib` vs. `platlib`): {}. Consider reinstalling it with `--reinstall`.",
+                path.user_display(),
+                files
+                    .iter()
+                    .map(|path| format!("`{}`", path.user_display()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Self::MissingInstalledFile { package, path } => format!(
+                "The package `{package}` is broken or incomplete (`{}` is listed in its `RECORD`, but doesn't exist). Consider reinstalling it with `--reinstall`.",
+                path.user_display(),
+            ),
+            Self::DuplicateRecordEntry { package, path } => format!(
+                "The package `{package}` has a malformed `RECORD` (`{}` is listed more than once). Consider reinstalling it with `--reinstall`.",
+                path.user_display(),
+            ),
+            Self::IncompleteUninstall {
+                package,
+                missing,
+                total,
+            } => format!(
+                "The package `{package}` appears to be partially uninstalled ({missing} of the {total} files listed in its `RECORD` don't exist). Consider reinstalling it with `--reinstall`."
+            ),
+            Self::RecordNameMismatch {
+                package,
+                record_name,
+                disk_name,
+            } => format!(
+                "The package `{package}` has a `RECORD` that refers to `{record_name}`, but its metadata is installed at `{disk_name}`, so uninstalling it may leave files behind. Consider reinstalling it with `--reinstall`."
+            ),
+            Self::RelocatedEnvironment {
+                package,
+                script,
+                expected,
+                found,
+            } => format!(
+                "The script `{script}` from package `{package}` refers to the interpreter at `{}`, but the environment's interpreter is at `{}`, which indicates that the environment was moved. Consider recreating the virtualenv, or reinstalling the package with `--reinstall`.",
+                found.user_display(),
+                expected.user_display(),
+            ),
+            Self::StaleBytecode { package, path } => format!(
+                "The package `{package}` has stale bytecode at `{}`, which doesn't match its source file or the current Python version. Consider removing it, or reinstalling the package with `--reinstall`.",
+                path.user_display(),
+            ),
+            Self::MissingBuildTool {
+                package,
+                backend,
+                tool,
+            } => format!(
+                "The editable package `{package}` is rebuilt on import by `{backend}`, but the build tool `{tool}` isn't installed. Consider installing it, or reinstalling the package without `--editable`."
+            ),
+            Self::InvalidVersion { package, raw } => format!(
+                "The package `{package}` is installed with an invalid version (`{raw}`), so it can't be checked against version requirements. Consider reinstalling it with `--reinstall`."
+            ),
+            Self::UnverifiedRecordSignature { package } => format!(
+                "The package `{package}` has a `RECORD` signature that couldn't be verified against the trusted keys, which may indicate that its files were modified after signing. Consider reinstalling it with `--reinstall`."
+            ),
+            Self::MalformedWheelMetadata { package } => format!(
+                "The package `{package}` has a missing or malformed `WHEEL` file (no `Wheel-Version` was found), which may indicate a hand-assembled or corrupted wheel. Consider reinstalling it with `--reinstall`."
+            ),
+            Self::UnresolvedDynamicMetadata { package, fields } => format!(
+                "The package `{package}` was installed from a wheel whose metadata marks fields as dynamic ({}), which is only valid for source distributions. Its dependencies may be incomplete.",
+                fields
+                    .iter()
+                    .map(|field| format!("`{field}`"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Self::TagsUnavailable { package, path } => format!(
+                "The package `{package}` is broken or incomplete (unable to read `WHEEL` file). Consider recreating the virtualenv, or removing the package directory at: {}.",
+                path.display(),
+            ),
+            Self::IncompatiblePythonVersion {
+                package,
+                version,
+                requires_python,
+            } => format!(
+                "The package `{package}` requires Python {requires_python}, but `{version}` is installed"
+            ),
+            Self::IncompatiblePlatform { package } => {
+                format!("The package `{package}` was built for a different platform")
+            }
+            Self::IncompatibleInterpreterBuild { package } => {
+                format!(
+                    "The package `{package}` was built for a different interpreter build (e.g., free-threaded or GIL-enabled)"
+                )
+            }
+            Self::MissingDependency {
+                package,
+                requirement,
+            } => {
+                format!("The package `{package}` requires `{requirement}`, but it's not installed")
+            }
+            Self::IncompatibleDependency {
+                package,
+                version,
+                requirement,
+            } => format!(
+                "The package `{package}` requires `{requirement}`, but `{version}` is installed"
+            ),
+            Self::SelfDependency { package } => format!(
+                "The package `{package}` declares a dependency on itself, which indicates that its metadata is broken"
+            ),
+            Self::PeerConflict {
+                dependency,
+                requiring,
+                installed,
+            } => format!(
+                "The packages that depend on `{dependency}` require conflicting versions, but `{installed}` is installed: {}",
+                requiring
+                    .iter()
+                    .fold(String::new(), |acc, (package, specifiers)| acc
+                        + &format!(
+                            "\n  - `{package}` requires `{dependency}{specifiers}`"
+                        ))
+            ),
+            Self::DuplicatePackage { package, paths } => {
+                let mut paths = paths.clone();
+                paths.sort();
+                format!(
+                    "The package `{package}` has multiple installed distributions: {}",
+                    paths.iter().fold(String::new(), |acc, path| acc
+                        + &format!("\n  - {}", path.display()))
+                )
+            }
+            Self::DuplicateSameVersion {
+                package,
+                version,
+                paths,
+            } => {
+                let mut paths = paths.clone();
+                paths.sort();
+                format!(
+                    "The package `{package}` has multiple installed distributions for the same version (`{version}`), which indicates that its installation is corrupt. Consider reinstalling it with `--reinstall`: {}",
+                    paths.iter().fold(String::new(), |acc, path| acc
+                        + &format!("\n  - {}", path.display()))
+                )
+            }
+        }
+    }
+
+    /// Returns `true` if the [`PackageName`] is involved in this diagnostic.
+    fn includes(&self, name: &PackageName) -> bool {
+        match self {
+            Self::MetadataUnavailable { package, .. } => name == package,
+            Self::TruncatedMetadata { package, .. } => name == package,
+            Self::EditableLayoutMismatch { package, .. } => name == package,
+            Self::SplitDistribution { package, .. } => name == package,
+            Self::MissingInstalledFile { package, .. } => name == package,
+            Self::DuplicateRecordEntry { package, .. } => name == package,
+            Self::IncompleteUninstall { package, .. } => name == package,
+            Self::RecordNameMismatch { package, .. } => name == package,
+            Self::RelocatedEnvironment { package, .. } => name == package,
+            Self::StaleBytecode { package, .. } => name == package,
+            Self::MissingBuildTool { package, tool, .. } => name == package || name == tool,
+            Self::UnverifiedRecordSignature { package } => name == package,
+            Self::InvalidVersion { package, .. } => name == package,
+            Self::MalformedWheelMetadata { package } => name == package,
+            Self::UnresolvedDynamicMetadata { package, .. } => name == package,
+            Self::TagsUnavailable { package, .. } => name == package,
+            Self::IncompatiblePythonVersion { package, .. } => name == package,
+            Self::IncompatiblePlatform { package } => name == package,
+            Self::IncompatibleInterpreterBuild { package } => name == package,
+            Self::MissingDependency { package, .. } => name == package,
+            Self::IncompatibleDependency {
+                package,
+                requirement,
+                ..
+            } => name == package || &requirement.name == name,
+            Self::SelfDependency { package } => name == package,
+            Self::PeerConflict {
+                dependency,
+                requiring,
+                ..
+            } => name == dependency || requiring.iter().any(|(package, _)| package == name),
+            Self::DuplicatePackage { package, .. } => name == package,
+            Self::DuplicateSameVersion { package, .. } => name == package,
+        }
+    }
+
+    /// Returns a machine-actionable remediation for the diagnostic, if there's a clear one.
+    fn suggested_fix(&self) -> Option<SuggestedFix> {
+        match self {
+            Self::MetadataUnavailable { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::TruncatedMetadata { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::EditableLayoutMismatch { .. } => None,
+            Self::SplitDistribution { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::MissingInstalledFile { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::DuplicateRecordEntry { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::IncompleteUninstall { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::RecordNameMismatch { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::RelocatedEnvironment { .. } => Some(SuggestedFix::RecreateEnvironment),
+            Self::StaleBytecode { path, .. } => Some(SuggestedFix::RemovePath(path.clone())),
+            Self::UnverifiedRecordSignature { package } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::InvalidVersion { package, .. } => Some(SuggestedFix::Reinstall(package.clone())),
+            Self::MissingBuildTool { tool, .. } => {
+                Some(SuggestedFix::Install(Box::new(uv_pep508::Requirement {
+                    name: tool.clone(),
+                    extras: Box::default(),
+                    version_or_url: None,
+                    marker: MarkerTree::TRUE,
+                    origin: None,
+                })))
+            }
+            Self::MalformedWheelMetadata { package } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::UnresolvedDynamicMetadata { .. } => None,
+            Self::TagsUnavailable { package, .. } => Some(SuggestedFix::Reinstall(package.clone())),
+            Self::IncompatiblePythonVersion {
+                requires_python, ..
+            } => Some(SuggestedFix::UpgradePython(requires_python.clone())),
+            Self::IncompatiblePlatform { package } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::IncompatibleInterpreterBuild { package } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::MissingDependency { requirement, .. } => {
+                Some(SuggestedFix::Install(Box::new(requirement.clone())))
+            }
+            Self::IncompatibleDependency { requirement, .. } => {
+                Some(SuggestedFix::Install(Box::new(requirement.clone())))
+            }
+            Self::SelfDependency { .. } => None,
+            Self::PeerConflict { .. } => None,
+            Self::DuplicatePackage { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+            Self::DuplicateSameVersion { package, .. } => {
+                Some(SuggestedFix::Reinstall(package.clone()))
+            }
+        }
+    }
+}
+
+/// A [`SitePackagesDiagnostic`], or a group of related diagnostics that were collapsed into one.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum GroupedDiagnostic {
+    /// A diagnostic that wasn't grouped with any others.
+    Single(SitePackagesDiagnostic),
+    /// A dependency that isn't installed, but is required by multiple packages.
+    MissingDependency {
+        /// The dependency that is missing.
+        dependency: PackageName,
+        /// The packages that require the dependency, along with their requirements.
+        requirements: Vec<(PackageName, uv_pep508::Requirement<VerbatimParsedUrl>)>,
+    },
+}
+
+impl Diagnostic for GroupedDiagnostic {
+    /// Convert the diagnostic into a user-facing message.
+    fn message(&self) -> String {
+        match self {
+            Self::Single(diagnostic) => diagnostic.message(),
+            Self::MissingDependency {
+                dependency,
+                requirements,
+            } => format!(
+                "The package `{dependency}` is required by {} packages, but it's not installed: {}",
+                requirements.len(),
+                requirements
+                    .iter()
+                    .fold(String::new(), |acc, (package, requirement)| acc
+                        + &format!("\n  - `{package}` requires `{requirement}`"))
+            ),
+        }
+    }
+
+    /// Returns `true` if the [`PackageName`] is involved in this diagnostic.
+    fn includes(&self, name: &PackageName) -> bool {
+        match self {
+            Self::Single(diagnostic) => diagnostic.includes(name),
+            Self::MissingDependency { requirements, .. } => {
+                requirements.iter().any(|(package, _)| name == package)
+            }
+        }
+    }
+
+    /// Returns a machine-actionable remediation for the diagnostic, if there's a clear one.
+    fn suggested_fix(&self) -> Option<SuggestedFix> {
+        match self {
+            Self::Single(diagnostic) => diagnostic.suggested_fix(),
+            // The packages may require different versions of the dependency.
+            Self::MissingDependency { .. } => None,
+        }
+    }
+}
+
+/// Collapse related [`SitePackagesDiagnostic`]s into a [`GroupedDiagnostic`].
+///
+/// Missing dependencies that are required by more than one package are reported once, listing
+/// every package that requires them. All other diagnostics are preserved as-is, in their original
+/// order.
+pub fn group_diagnostics(diagnostics: Vec<SitePackagesDiagnostic>) -> Vec<GroupedDiagnostic> {
+    #[allow(clippy::large_enum_variant)]
+    enum Entry {
+        Diagnostic(SitePackagesDiagnostic),
+        MissingDependency(PackageName),
+    }
+
+    // Collect the packages that require each missing dependency, in order of first appearance.
+    let mut entries = Vec::with_capacity(diagnostics.len());
+    let mut missing: FxHashMap<PackageName, Vec<_>> = FxHashMap::default();
+    for diagnostic in diagnostics {
+        match diagnostic {
+            SitePackagesDiagnostic::MissingDependency {
+                package,
+                requirement,
+            } => {
+                missing
+                    .entry(requirement.name.clone())
+                    .or_insert_with(|| {
+                        entries.push(Entry::MissingDependency(requirement.name.clone()));
+                        Vec::new()
+                    })
+                    .push((package, requirement));
+            }
+            diagnostic => entries.push(Entry::Diagnostic(diagnostic)),
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            Entry::Diagnostic(diagnostic) => GroupedDiagnostic::Single(diagnostic),
+            Entry::MissingDependency(dependency) => {
+                let mut requirements = missing.remove(&dependency).unwrap_or_default();
+                if requirements.len() == 1 {
+                    let (package, requirement) = requirements.remove(0);
+                    GroupedDiagnostic::Single(SitePackagesDiagnostic::MissingDependency {
+                        package,
+                        requirement,
+                    })
+                } else {
+                    GroupedDiagnostic::MissingDependency {
+                        dependency,
+                        requirements,
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Merge the [`SitePackagesDiagnostic`]s from multiple environments (e.g., a base environment and
+/// an overlay), dropping any duplicates.
+///
+/// The merged diagnostics are sorted by message, such that the output is independent of the
+/// order in which the environments were scanned.
+pub fn merge_diagnostics(
+    diagnostics: &[Vec<SitePackagesDiagnostic>],
+) -> Vec<SitePackagesDiagnostic> {
+    let mut merged: Vec<SitePackagesDiagnostic> = Vec::new();
+    for diagnostic in diagnostics.iter().flatten() {
+        if !merged.contains(diagnostic) {
+            merged.push(diagnostic.clone());
+        }
+    }
+    merged.sort_by_cached_key(Diagnostic::message);
+    merged
+}
+
+impl SitePackages {
+    /// Validate the byte-compiled (`.pyc`) files of the installed packages against their sources.
+    ///
+    /// For each `.py` file listed in a distribution's `RECORD`, the corresponding `__pycache__`
+    /// entries for the current interpreter are checked for a magic number that doesn't match the
+    /// interpreter, or a recorded source modification time or size that doesn't match the
+    /// source file (e.g., because the source was edited after compilation). Hash-based `.pyc`
+    /// files are skipped.
+    ///
+    /// Unlike [`SitePackages::diagnostics`], this reads every `.pyc` file in the environment, so
+    /// it's opt-in. Only CPython is supported.
+    pub fn bytecode_diagnostics(&self) -> Vec<SitePackagesDiagnostic> {
+        let mut diagnostics = Vec::new();
+        if self.interpreter().implementation_name() != "cpython" {
+            return diagnostics;
+        }
+        let cache_tag = format!(
+            "cpython-{}{}",
+            self.interpreter().python_major(),
+            self.interpreter().python_minor()
+        );
+        let magic_number = cpython_magic_number(self.interpreter().python_minor());
+
+        let mut names = self.by_name.keys().collect::<Vec<_>>();
+        names.sort();
+        for package in names {
+            for distribution in self.get_packages(package) {
+                let Ok(Some(files)) = record_files(distribution) else {
+                    continue;
+                };
+                for source in files {
+                    if source.extension().is_none_or(|ext| ext != "py") {
+                        continue;
+                    }
+                    let (Some(parent), Some(stem)) = (source.parent(), source.file_stem()) else {
+                        continue;
+                    };
+                    let Ok(metadata) = fs::metadata(&source) else {
+                        continue;
+                    };
+                    for optimization in ["", ".opt-1", ".opt-2"] {
+                        let bytecode = parent.join("__pycache__").join(format!(
+                            "{}.{cache_tag}{optimization}.pyc",
+                            stem.to_string_lossy()
+                        ));
+                        if is_stale_bytecode(&bytecode, &metadata, magic_number) {
+                            diagnostics.push(SitePackagesDiagnostic::StaleBytecode {
+                                package: package.clone(),
+                                path: bytecode,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Validate that editable installs which are rebuilt on import have their build tools
+    /// installed.
+    ///
+    /// Some build backends (e.g., `meson-python`) install editable projects as import hooks that
+    /// recompile the project on import, which requires build tools like `meson` and `ninja` to be
+    /// importable from the environment. The build backend is read from the `[build-system]` table
+    /// of each editable project's `pyproject.toml`.
+    ///
+    /// Unlike [`SitePackages::diagnostics`], this reads the `pyproject.toml` of every editable
+    /// project, and the tools may instead be provided on the `PATH`, so it's opt-in and
+    /// best-effort.
+    pub fn build_tool_diagnostics(&self) -> Vec<SitePackagesDiagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut names = self.by_name.keys().collect::<Vec<_>>();
+        names.sort();
+        for package in names {
+            for distribution in self.get_packages(package) {
+                let Some(project) = editable_project(distribution) else {
+                    continue;
+                };
+                let Some((backend, tools)) = rebuild_tools(&project) else {
+                    continue;
+                };
+                for tool in tools {
+                    let tool = PackageName::from_str(tool).expect("valid package name");
+                    if !self.is_installed(&tool) {
+                        diagnostics.push(SitePackagesDiagnostic::MissingBuildTool {
+                            package: package.clone(),
+                            backend: backend.clone(),
+                            tool,
+                        });
+                    }
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Validate any signatures of the installed packages' `RECORD` files against the given trusted
+    /// Ed25519 public keys.
+    ///
+    /// A `RECORD.jws` file is expected to hold a JSON Web Signature in the compact serialization,
+    /// signed with `EdDSA`, whose payload is `{"hash": "sha256=..."}`, with the URL-safe base64
+    /// encoding (without padding) of the SHA-256 digest of the `RECORD` file. The signature must
+    /// be valid for one of the trusted keys, and the digest must match the `RECORD` on disk.
+    /// `RECORD.p7s` (PKCS #7) signatures aren't supported, and so are always unverifiable.
+    ///
+    /// Packages without a `RECORD` signature aren't flagged. Unlike
+    /// [`SitePackages::diagnostics`], this requires a trust store, so it's opt-in.
+    pub fn record_signature_diagnostics(
+        &self,
+        trusted_keys: &[[u8; 32]],
+    ) -> Vec<SitePackagesDiagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut names = self.by_name.keys().collect::<Vec<_>>();
+        names.sort();
+        for package in names {
+            for distribution in self.get_packages(package) {
+                if !matches!(
+                    distribution.kind,
+                    InstalledDistKind::Registry(_) | InstalledDistKind::Url(_)
+                ) {
+                    continue;
+                }
+                let dist_info = distribution.install_path();
+                let verified = match fs::read_to_string(dist_info.join("RECORD.jws")) {
+                    Ok(signature) => fs::read(dist_info.join("RECORD")).is_ok_and(|record| {
+                        verify_record_signature(&signature, &record, trusted_keys)
+                    }),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                        !dist_info.join("RECORD.p7s").exists()
+                    }
+                    Err(_) => false,
+                };
+                if !verified {
+                    diagnostics.push(SitePackagesDiagnostic::UnverifiedRecordSignature {
+                        package: package.clone(),
+                    });
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Check that the files listed in a distribution's `RECORD` live in the same library
+    /// directory as its `.dist-info` directory.
+    ///
+    /// Returns a diagnostic if any files are missing from the distribution's own library
+    /// directory, but present in the other (e.g., a `Root-Is-Purelib: true` wheel whose modules
+    /// ended up in `platlib`).
+    fn split_distribution(
+        &self,
+        package: &PackageName,
+        distribution: &InstalledDist,
+    ) -> Option<SitePackagesDiagnostic> {
+        let purelib = self.interpreter().purelib();
+        let platlib = self.interpreter().platlib();
+        if purelib == platlib {
+            return None;
+        }
+        let (library, other) = match self.library(distribution)? {
+            LibKind::Pure => (purelib, platlib),
+            LibKind::Plat => (platlib, purelib),
+        };
+
+        let record = fs::File::open(distribution.install_path().join("RECORD")).ok()?;
+        let record = read_record_file(&mut BufReader::new(record)).ok()?;
+        let files = record
+            .iter()
+            .map(|entry| Path::new(&entry.path))
+            .filter(|path| path.is_relative() && !path.starts_with(".."))
+            .filter(|path| !library.join(path).exists() && other.join(path).exists())
+            .map(|path| other.join(path))
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            return None;
+        }
+
+        Some(SitePackagesDiagnostic::SplitDistribution {
+            package: package.clone(),
+            path: distribution.install_path().to_owned(),
+            files,
+        })
+    }
+
+    /// Validate the installed packages in the virtual environment.
+    pub fn diagnostics(
+        &self,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+    ) -> Result<Vec<SitePackagesDiagnostic>> {
+        let mut diagnostics = Vec::new();
+        self.diagnostics_stream(markers, tags, |diagnostic| diagnostics.push(diagnostic))?;
+        Ok(diagnostics)
+    }
+
+    /// Validate the installed packages in the virtual environment, returning only those
+    /// diagnostics at or above the given severity.
+    ///
+    /// Checks that can only produce diagnostics below the threshold are skipped entirely, so
+    /// (e.g.) validating with [`DiagnosticSeverity::Error`] avoids reading the files needed for
+    /// warning-level checks.
+    pub fn diagnostics_with_min_severity(
+        &self,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+        min_severity: DiagnosticSeverity,
+    ) -> Result<Vec<SitePackagesDiagnostic>> {
+        let mut diagnostics = Vec::new();
+        self.diagnostics_impl(markers, tags, None, min_severity, |diagnostic| {
+            diagnostics.push(diagnostic);
+        })?;
+        Ok(diagnostics)
+    }
+
+    /// Validate the installed packages in the virtual environment, returning the number of
+    /// diagnostics of each kind and an overall status, rather than the diagnostics themselves.
+    pub fn health_summary(
+        &self,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+    ) -> Result<HealthSummary> {
+        let mut status = HealthStatus::Ok;
+        let mut counts = BTreeMap::new();
+        self.diagnostics_stream(markers, tags, |diagnostic| {
+            status = status.max(HealthStatus::from(diagnostic.severity()));
+            *counts.entry(diagnostic.code()).or_default() += 1;
+        })?;
+        Ok(HealthSummary { status, counts })
+    }
+
+    /// Validate the installed packages in the virtual environment, passing each diagnostic to
+    /// `sink` as soon as it's found.
+    ///
+    /// Packages are visited in name order, so diagnostics are emitted in a deterministic order.
+    /// Diagnostics that span multiple packages (e.g., [`SitePackagesDiagnostic::PeerConflict`])
+    /// are emitted after all packages have been visited.
+    pub fn diagnostics_stream(
+        &self,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+        sink: impl FnMut(SitePackagesDiagnostic),
+    ) -> Result<()> {
+        self.diagnostics_impl(markers, tags, None, DiagnosticSeverity::Warning, sink)
+    }
+
+    /// Like [`SitePackages::diagnostics_stream`], but checks the cancellation token between
+    /// packages, returning a [`Cancelled`] error once it's set.
+    ///
+    /// Any diagnostics passed to `sink` before cancellation remain valid.
+    pub fn diagnostics_stream_cancellable(
+        &self,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+        cancel: &AtomicBool,
+        sink: impl FnMut(SitePackagesDiagnostic),
+    ) -> Result<()> {
+        self.diagnostics_impl(
+            markers,
+            tags,
+            Some(cancel),
+            DiagnosticSeverity::Warning,
+            sink,
+        )
+    }
+
+    fn diagnostics_impl(
+        &self,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+        cancel: Option<&AtomicBool>,
+        min_severity: DiagnosticSeverity,
+        mut sink: impl FnMut(SitePackagesDiagnostic),
+    ) -> Result<()> {
+        // Skip any checks that only produce warnings if they'd be filtered out, and filter any
+        // warnings that are produced as a by-product of other checks.
+        let warnings = min_severity <= DiagnosticSeverity::Warning;
+        let mut sink = |diagnostic: SitePackagesDiagnostic| {
+            if diagnostic.severity() >= min_severity {
+                sink(diagnostic);
+            }
+        };
+
+        let mut peers: BTreeMap<PackageName, Vec<(PackageName, VersionSpecifiers)>> =
+            BTreeMap::new();
+
+        // Report any `.dist-info` directories whose version couldn't be parsed, which are
+        // excluded from the index.
+        for path in &self.invalid_versions {
+            if let Some((package, raw)) = invalid_version(path) {
+                sink(SitePackagesDiagnostic::InvalidVersion { package, raw });
+            }
+        }
+
+        let mut by_name = self.by_name.iter().collect::<Vec<_>>();
+        by_name.sort_unstable_by_key(|(name, _)| *name);
+
+        for (package, indexes) in by_name {
+            check_cancelled(cancel)?;
+
+            let mut distributions = indexes.iter().flat_map(|index| &self.distributions[*index]);
+
+            // Find the installed distribution for the given package.
+            let Some(distribution) = distributions.next() else {
+                continue;
+            };
+
+            if let Some(conflict) = distributions.next() {
+                // There are multiple installed distributions for the same package.
+                let distributions = std::iter::once(distribution)
+                    .chain(std::iter::once(conflict))
+                    .chain(distributions)
+                    .collect::<Vec<_>>();
+
+                // If the distributions have different versions, one shadows the other.
+                let mut by_version: BTreeMap<&Version, Vec<PathBuf>> = BTreeMap::new();
+                for dist in &distributions {
+                    by_version
+                        .entry(dist.version())
+                        .or_default()
+                        .push(dist.install_path().to_owned());
+                }
+                if warnings && by_version.len() > 1 {
+                    sink(SitePackagesDiagnostic::DuplicatePackage {
+                        package: package.clone(),
+                        paths: distributions
+                            .iter()
+                            .map(|dist| dist.install_path().to_owned())
+                            .collect(),
+                    });
+                }
+
+                // If any have the same version, the installation is corrupt.
+                for (version, paths) in by_version {
+                    if paths.len() > 1 {
+                        sink(SitePackagesDiagnostic::DuplicateSameVersion {
+                            package: package.clone(),
+                            version: version.clone(),
+                            paths,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            for index in indexes {
+                let Some(distribution) = &self.distributions[*index] else {
+                    continue;
+                };
+
+                // Detect an empty `METADATA` file (e.g., from an interrupted install) before
+                // attempting to parse it.
+                if has_empty_metadata(distribution) {
+                    sink(SitePackagesDiagnostic::TruncatedMetadata {
+                        package: package.clone(),
+                        path: distribution.install_path().to_owned(),
+                    });
+                    continue;
+                }
+
+                // Determine the dependencies for the given package.
+                let Ok(metadata) = distribution.read_metadata() else {
+                    sink(SitePackagesDiagnostic::MetadataUnavailable {
+                        package: package.clone(),
+                        path: distribution.install_path().to_owned(),
+                    });
+                    continue;
+                };
+
+                // Verify that the package is compatible with the current Python version and tags.
+                self.compatibility_diagnostics(
+                    package,
+                    distribution,
+                    metadata.requires_python.as_ref(),
+                    markers,
+                    tags,
+                    &mut sink,
+                );
+
+                // Verify that the wheel's metadata doesn't defer any fields to build time, in
+                // which case they (e.g., `Requires-Dist`) may be incomplete.
+                if warnings && self.file_checks {
+                    let fields = unresolved_dynamic_fields(distribution);
+                    if !fields.is_empty() {
+                        sink(SitePackagesDiagnostic::UnresolvedDynamicMetadata {
+                            package: package.clone(),
+                            fields,
+                        });
+                    }
+                }
+
+                if self.file_checks {
+                    self.file_diagnostics(package, distribution, warnings, &mut sink);
+                }
+
+                // Verify that the dependencies are installed.
+                for dependency in &metadata.requires_dist {
+                    if !dependency.evaluate_markers(markers, &[]) {
+                        continue;
+                    }
+
+                    // A package may depend on its own extras (e.g., `foo[all]` requiring
+                    // `foo[cli]`), but never on itself.
+                    if dependency.name == *package && dependency.extras.is_empty() {
+                        sink(SitePackagesDiagnostic::SelfDependency {
+                            package: package.clone(),
+                        });
+                        continue;
+                    }
+
+                    let installed = self.get_packages(&dependency.name);
+                    match installed.as_slice() {
+                        [] => {
+                            // No version installed.
+                            sink(SitePackagesDiagnostic::MissingDependency {
+                                package: package.clone(),
+                                requirement: dependency.clone(),
+                            });
+                        }
+                        [installed] => {
+                            match &dependency.version_or_url {
+                                None | Some(VersionOrUrl::Url(_)) => {
+                                    // Nothing to do (accept any installed version).
+                                }
+                                Some(VersionOrUrl::VersionSpecifier(version_specifier)) => {
+                                    if warnings {
+                                        peers
+                                            .entry(dependency.name.clone())
+                                            .or_default()
+                                            .push((package.clone(), version_specifier.clone()));
+                                    }
+
+                                    // The installed version doesn't satisfy the requirement.
+                                    if !version_specifier.contains(installed.version()) {
+                                        sink(SitePackagesDiagnostic::IncompatibleDependency {
+                                            package: package.clone(),
+                                            version: installed.version().clone(),
+                                            requirement: dependency.clone(),
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                        _ => {
+                            // There are multiple installed distributions for the same package.
+                        }
+                    }
+                }
+            }
+        }
+
+        // Verify that packages that share a dependency agree on its version, i.e., that some
+        // version satisfies all of the packages that require it. (If so, but the installed version
+        // isn't one of them, each unsatisfied requirement is reported on its own.)
+        for (dependency, mut requiring) in peers {
+            let installed = self.get_packages(&dependency);
+            let [installed] = installed.as_slice() else {
+                continue;
+            };
+            let compatible = requiring
+                .iter()
+                .fold(Ranges::full(), |range, (_, specifiers)| {
+                    range.intersection(&Ranges::from(specifiers.clone()))
+                });
+            if !compatible.is_empty() {
+                continue;
+            }
+            requiring.sort_by(|(a, _), (b, _)| a.cmp(b));
+            sink(SitePackagesDiagnostic::PeerConflict {
+                dependency,
+                requiring,
+                installed: installed.version().clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Verify the files installed by the distribution, as enabled by
+    /// [`SitePackages::with_file_checks`].
+    fn file_diagnostics(
+        &self,
+        package: &PackageName,
+        distribution: &InstalledDist,
+        warnings: bool,
+        sink: &mut impl FnMut(SitePackagesDiagnostic),
+    ) {
+        // Verify that the distribution's files weren't split across `purelib` and
+        // `platlib`.
+        let mut missing = FxHashSet::default();
+        if let Some(diagnostic) = self.split_distribution(package, distribution) {
+            sink(diagnostic);
+        } else {
+            // Verify that the files listed in the `RECORD` exist, and are only listed once.
+            // Bytecode (`.pyc` and `.pyo` files) is exempt from the former check, since it's
+            // regenerated lazily.
+            if let Ok(Some(files)) = record_files(distribution) {
+                // Verify that the `RECORD` refers to the `.dist-info` directory on disk;
+                // if not, its entries for that directory are expected to be missing.
+                let mismatch = record_name_mismatch(distribution, &files);
+                if let Some((record_name, disk_name)) = &mismatch {
+                    sink(SitePackagesDiagnostic::RecordNameMismatch {
+                        package: package.clone(),
+                        record_name: record_name.clone(),
+                        disk_name: disk_name.clone(),
+                    });
+                }
+                let mismatched = mismatch.as_ref().and_then(|(record_name, _)| {
+                    Some(distribution.install_path().parent()?.join(record_name))
+                });
+
+                if warnings {
+                    let mut seen = FxHashSet::default();
+                    let mut duplicates = BTreeSet::new();
+                    for path in &files {
+                        if !seen.insert(path) {
+                            duplicates.insert(path);
+                        }
+                    }
+                    for path in duplicates {
+                        sink(SitePackagesDiagnostic::DuplicateRecordEntry {
+                            package: package.clone(),
+                            path: path.clone(),
+                        });
+                    }
+                }
+                // Track the files outside the `.dist-info` directory separately: if most
+                // of them are missing, the package was likely partially uninstalled.
+                let mut absent = Vec::new();
+                let mut total = 0;
+                let mut removed = 0;
+                for path in files {
+                    if path
+                        .extension()
+                        .is_some_and(|ext| ext == "pyc" || ext == "pyo")
+                    {
+                        continue;
+                    }
+                    if mismatched
+                        .as_ref()
+                        .is_some_and(|mismatched| path.starts_with(mismatched))
+                    {
+                        continue;
+                    }
+                    let metadata = path.starts_with(distribution.install_path());
+                    if !metadata {
+                        total += 1;
+                    }
+                    if !path.exists() {
+                        if !metadata {
+                            removed += 1;
+                        }
+                        absent.push(path);
+                    }
+                }
+                if removed * 2 > total {
+                    sink(SitePackagesDiagnostic::IncompleteUninstall {
+                        package: package.clone(),
+                        missing: removed,
+                        total,
+                    });
+                    missing.extend(absent);
+                } else {
+                    for path in absent {
+                        missing.insert(path.clone());
+                        sink(SitePackagesDiagnostic::MissingInstalledFile {
+                            package: package.clone(),
+                            path,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Verify that the distribution's scripts exist, even if they're absent from the
+        // `RECORD` (as for some legacy installers).
+        for (script, path) in self.distribution_scripts(distribution) {
+            if !path.exists() {
+                if !missing.contains(&path) {
+                    sink(SitePackagesDiagnostic::MissingInstalledFile {
+                        package: package.clone(),
+                        path,
+                    });
+                }
+                continue;
+            }
+
+            // Verify that the script refers to the environment's interpreter, which it
+            // won't if the environment was moved (or copied) after installation.
+            if let Some(found) = script_interpreter(&path) {
+                let expected = self.interpreter().sys_executable();
+                if found != expected && !is_same_file(&found, expected).unwrap_or(false) {
+                    sink(SitePackagesDiagnostic::RelocatedEnvironment {
+                        package: package.clone(),
+                        script,
+                        expected: expected.to_path_buf(),
+                        found,
+                    });
+                }
+            }
+        }
+
+        // Verify that editable installs point to the importable package.
+        if warnings {
+            if let Some(diagnostic) = editable_layout_mismatch(package, distribution) {
+                sink(diagnostic);
+            }
+        }
+    }
+
+    /// Validate the Python version and platform compatibility of the installed packages that
+    /// contain compiled extension modules (i.e., `.so` or `.pyd` files listed in their `RECORD`).
+    ///
+    /// Pure-Python packages are skipped, as are the other checks performed by
+    /// [`SitePackages::diagnostics`] (e.g., missing dependencies), to focus on the packages whose
+    /// incompatibility can crash the interpreter.
+    pub fn diagnostics_native(
+        &self,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+    ) -> Vec<SitePackagesDiagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut sink = |diagnostic| diagnostics.push(diagnostic);
+
+        let mut by_name = self.by_name.iter().collect::<Vec<_>>();
+        by_name.sort_unstable_by_key(|(name, _)| *name);
+
+        for (package, indexes) in by_name {
+            for index in indexes {
+                let Some(distribution) = &self.distributions[*index] else {
+                    continue;
+                };
+                if !has_extension_modules(distribution) {
+                    continue;
+                }
+                let requires_python = distribution
+                    .read_metadata()
+                    .ok()
+                    .and_then(|metadata| metadata.requires_python.as_ref());
+                self.compatibility_diagnostics(
+                    package,
+                    distribution,
+                    requires_python,
+                    markers,
+                    tags,
+                    &mut sink,
+                );
+            }
+        }
+        diagnostics
+    }
+
+    /// Verify that the distribution is compatible with the current Python version and tags.
+    fn compatibility_diagnostics(
+        &self,
+        package: &PackageName,
+        distribution: &InstalledDist,
+        requires_python: Option<&VersionSpecifiers>,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+        sink: &mut impl FnMut(SitePackagesDiagnostic),
+    ) {
+        if let Some(requires_python) = requires_python {
+            if !requires_python.contains(markers.python_full_version()) {
+                sink(SitePackagesDiagnostic::IncompatiblePythonVersion {
+                    package: package.clone(),
+                    version: self.interpreter().python_version().clone(),
+                    requires_python: requires_python.clone(),
+                });
+            }
+        }
+
+        match distribution.read_tags() {
+            Ok(Some(wheel_tags)) => {
+                if !wheel_tags.is_compatible(tags) {
+                    if is_interpreter_build_mismatch(wheel_tags, tags, self.interpreter()) {
+                        sink(SitePackagesDiagnostic::IncompatibleInterpreterBuild {
+                            package: package.clone(),
+                        });
+                    } else {
+                        // TODO(charlie): Show the expanded tag hint, that explains _why_ it doesn't match.
+                        sink(SitePackagesDiagnostic::IncompatiblePlatform {
+                            package: package.clone(),
+                        });
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(_) if has_malformed_wheel_metadata(distribution) => {
+                sink(SitePackagesDiagnostic::MalformedWheelMetadata {
+                    package: package.clone(),
+                });
+            }
+            Err(_) => {
+                sink(SitePackagesDiagnostic::TagsUnavailable {
+                    package: package.clone(),
+                    path: distribution.install_path().to_owned(),
+                });
+            }
+        }
+    }
+}
+
+/// The protected header of a `RECORD.jws` signature.
+#[derive(serde::Deserialize)]
+struct RecordSignatureHeader {
+    alg: String,
+}
+
+/// The payload of a `RECORD.jws` signature.
+#[derive(serde::Deserialize)]
+struct RecordSignaturePayload {
+    hash: String,
+}
+
+/// Returns `true` if the given `RECORD.jws` signature (in the JWS compact serialization) is valid
+/// for one of the trusted Ed25519 keys, and attests to the given `RECORD` contents.
+fn verify_record_signature(signature: &str, record: &[u8], trusted_keys: &[[u8; 32]]) -> bool {
+    let Some((signing_input, signature)) = signature.trim().rsplit_once('.') else {
+        return false;
+    };
+    let Some((header, payload)) = signing_input.split_once('.') else {
+        return false;
+    };
+    let decode = |part: &str| URL_SAFE_NO_PAD.decode(part).ok();
+    let (Some(header), Some(payload), Some(signature)) =
+        (decode(header), decode(payload), decode(signature))
+    else {
+        return false;
+    };
+
+    // Only `EdDSA` signatures are supported.
+    let Ok(header) = serde_json::from_slice::<RecordSignatureHeader>(&header) else {
+        return false;
+    };
+    if header.alg != "EdDSA" {
+        return false;
+    }
+
+    // The payload must match the `RECORD` on disk.
+    let Ok(payload) = serde_json::from_slice::<RecordSignaturePayload>(&payload) else {
+        return false;
+    };
+    let digest = ring::digest::digest(&ring::digest::SHA256, record);
+    if payload.hash != format!("sha256={}", URL_SAFE_NO_PAD.encode(digest)) {
+        return false;
+    }
+
+    trusted_keys.iter().any(|key| {
+        ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, key)
+            .verify(signing_input.as_bytes(), &signature)
+            .is_ok()
+    })
+}
+
+/// Returns the name of the `.dist-info` directory referenced by a distribution's `RECORD`, along
+/// with the name of the directory on disk, if the `RECORD` never refers to the latter.
+///
+/// Some tools write the `.dist-info` directory using a non-normalized name (or rename it after
+/// the fact), in which case the `RECORD`'s entries for its own metadata are stale.
+fn record_name_mismatch(
+    distribution: &InstalledDist,
+    files: &[PathBuf],
+) -> Option<(String, String)> {
+    let site_packages = distribution.install_path().parent()?;
+    let disk_name = distribution.install_path().file_name()?.to_str()?;
+    let record_names = files
+        .iter()
+        .filter_map(|path| {
+            path.strip_prefix(site_packages)
+                .ok()?
+                .components()
+                .next()?
+                .as_os_str()
+                .to_str()
+        })
+        .filter(|name| {
+            Path::new(name)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("dist-info"))
+        })
+        .collect::<BTreeSet<_>>();
+    if record_names.contains(disk_name) {
+        return None;
+    }
+    let record_name = record_names.into_iter().next()?;
+    Some((record_name.to_string(), disk_name.to_string()))
+}
+
+/// Returns the magic number for `.pyc` files written by the given CPython 3.x minor version, for
+/// versions in which it's known.
+fn cpython_magic_number(minor: u8) -> Option<u16> {
+    match minor {
+        8 => Some(3413),
+        9 => Some(3425),
+        10 => Some(3439),
+        11 => Some(3495),
+        12 => Some(3531),
+        13 => Some(3571),
+        _ => None,
+    }
+}
+
+/// Returns `true` if the `.pyc` file exists, but its header is truncated or doesn't match the
+/// interpreter's magic number (if known) or the source file's modification time and size.
+///
+/// See: <https://peps.python.org/pep-0552/>
+fn is_stale_bytecode(
+    bytecode: &Path,
+    source: &std::fs::Metadata,
+    magic_number: Option<u16>,
+) -> bool {
+    // Only read the 16-byte header, rather than the entire file.
+    let Ok(mut file) = fs::File::open(bytecode) else {
+        return false;
+    };
+    let mut header = [0u8; 16];
+    if let Err(err) = file.read_exact(&mut header) {
+        // A truncated header can't be valid.
+        return err.kind() == std::io::ErrorKind::UnexpectedEof;
+    }
+    let word = |offset: usize| {
+        u32::from_le_bytes([
+            header[offset],
+            header[offset + 1],
+            header[offset + 2],
+            header[offset + 3],
+        ])
+    };
+
+    // The first word is the magic number, followed by `\r\n`.
+    if let Some(magic_number) = magic_number {
+        if header[..4]
+            != [
+                magic_number.to_le_bytes()[0],
+                magic_number.to_le_bytes()[1],
+                b'\r',
+                b'\n',
+            ]
+        {
+            return true;
+        }
+    }
+
+    // The second word is a bit field; if set, the `.pyc` is hash-based.
+    if word(4) != 0 {
+        return false;
+    }
+
+    // Otherwise, the third and fourth words are the source's modification time and size,
+    // truncated to 32 bits.
+    let Ok(modified) = source.modified().map_err(|_| ()).and_then(|modified| {
+        modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|_| ())
+    }) else {
+        return false;
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let (mtime, size) = (modified.as_secs() as u32, source.len() as u32);
+    word(8) != mtime || word(12) != size
+}
+
+/// Returns `true` if a wheel is incompatible with the interpreter only because it targets a
+/// different build of the same CPython version, i.e., a GIL-enabled build rather than a
+/// free-threaded build, or vice versa. (The stable ABI is unavailable on free-threaded builds.)
+fn is_interpreter_build_mismatch(
+    wheel_tags: &ExpandedTags,
+    tags: &Tags,
+    interpreter: &Interpreter,
+) -> bool {
+    // If the Python tag is compatible, but the ABI isn't, inspect the ABI.
+    if wheel_tags.compatibility(tags) != TagCompatibility::Incompatible(IncompatibleTag::Abi) {
+        return false;
+    }
+    wheel_tags.abi_tags().any(|abi_tag| match abi_tag {
+        AbiTag::CPython {
+            gil_disabled,
+            python_version,
+        } => {
+            *gil_disabled != interpreter.gil_disabled()
+                && *python_version == interpreter.python_tuple()
+        }
+        AbiTag::Abi3 => interpreter.gil_disabled(),
+        _ => false,
+    })
+}
+
+/// Returns `true` if the distribution's `RECORD` lists any compiled extension modules.
+fn has_extension_modules(distribution: &InstalledDist) -> bool {
+    let Ok(Some(files)) = record_files(distribution) else {
+        return false;
+    };
+    files.iter().any(|path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("so") || ext.eq_ignore_ascii_case("pyd"))
+    })
+}
+
+/// Returns `true` if the distribution's `METADATA` file exists, but is empty.
+fn has_empty_metadata(distribution: &InstalledDist) -> bool {
+    match &distribution.kind {
+        InstalledDistKind::Registry(_) | InstalledDistKind::Url(_) => {
+            fs::metadata(distribution.install_path().join("METADATA"))
+                .is_ok_and(|metadata| metadata.len() == 0)
+        }
+        InstalledDistKind::EggInfoFile(_)
+        | InstalledDistKind::EggInfoDirectory(_)
+        | InstalledDistKind::LegacyEditable(_) => false,
+    }
+}
+
+/// Returns the fields marked as `Dynamic` in the distribution's `METADATA` file.
+///
+/// Only `.dist-info` directories are considered, since `Dynamic` fields are only valid in source
+/// distributions, and should be resolved by the time a wheel is built.
+fn unresolved_dynamic_fields(distribution: &InstalledDist) -> Vec<String> {
+    match &distribution.kind {
+        InstalledDistKind::Registry(_) | InstalledDistKind::Url(_) => {
+            let Ok(contents) = fs::read(distribution.install_path().join("METADATA")) else {
+                return Vec::new();
+            };
+            Metadata23::parse(&contents)
+                .map(|metadata| metadata.dynamic)
+                .unwrap_or_default()
+        }
+        InstalledDistKind::EggInfoFile(_)
+        | InstalledDistKind::EggInfoDirectory(_)
+        | InstalledDistKind::LegacyEditable(_) => Vec::new(),
+    }
+}
+
+/// Returns the interpreter referenced by the shebang of a text script, as written by
+/// `uv-install-wheel`.
+///
+/// Returns `None` for binary launchers (as on Windows), for relocatable scripts, which locate
+/// the interpreter relative to the script itself, and for scripts that resolve the interpreter on
+/// the `PATH` (e.g., `#!/usr/bin/env python3`).
+fn script_interpreter(path: &Path) -> Option<PathBuf> {
+    let mut contents = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(4096)
+        .read_to_end(&mut contents)
+        .ok()?;
+    let contents = std::str::from_utf8(contents.strip_prefix(b"#!")?).ok()?;
+    let mut lines = contents.lines();
+    let shebang = lines.next()?.trim();
+    if shebang != "/bin/sh" {
+        // Ex) `/path/to/python -sP`, where the interpreter is followed by its arguments.
+        let mut words = shebang.split_ascii_whitespace();
+        let executable = words.next()?;
+        if Path::new(executable)
+            .file_name()
+            .is_some_and(|name| name == "env")
+        {
+            // Ex) `/usr/bin/env python3`, or `/usr/bin/env -S /path/to/python -sP`.
+            let executable = words.find(|word| !word.starts_with('-'))?;
+            return Path::new(executable)
+                .is_absolute()
+                .then(|| PathBuf::from(executable));
+        }
+        return Some(PathBuf::from(executable));
+    }
+
+    // Ex) `'''exec' '/path/to/python' "$0" "$@"`, for interpreter paths that are too long (or
+    // contain spaces) to use in a shebang directly.
+    let executable = lines
+        .next()?
+        .strip_prefix("'''exec' '")?
+        .strip_suffix(r#"' "$0" "$@""#)?;
+    Some(PathBuf::from(executable.replace(r"'\''", "'")))
+}
+
+/// Returns `true` if the distribution's `WHEEL` file is missing, or lacks a `Wheel-Version`, as
+/// for wheels that were assembled by hand or by non-compliant tooling.
+fn has_malformed_wheel_metadata(distribution: &InstalledDist) -> bool {
+    match &distribution.kind {
+        InstalledDistKind::Registry(_) | InstalledDistKind::Url(_) => {
+            match fs::read_to_string(distribution.install_path().join("WHEEL")) {
+                Ok(contents) => !contents.lines().any(|line| {
+                    line.split_once(':').is_some_and(|(key, value)| {
+                        key.trim().eq_ignore_ascii_case("Wheel-Version") && !value.trim().is_empty()
+                    })
+                }),
+                Err(err) => err.kind() == std::io::ErrorKind::NotFound,
+            }
+        }
+        InstalledDistKind::EggInfoFile(_)
+        | InstalledDistKind::EggInfoDirectory(_)
+        | InstalledDistKind::LegacyEditable(_) => false,
+    }
+}
+
+/// Returns the project directory of an editable installation, if it was installed from a local
+/// directory.
+fn editable_project(distribution: &InstalledDist) -> Option<PathBuf> {
+    let InstalledDistKind::Url(InstalledDirectUrlDist {
+        direct_url,
+        editable: true,
+        ..
+    }) = &distribution.kind
+    else {
+        return None;
+    };
+    let DirectUrl::LocalDirectory { url, .. } = direct_url.as_ref() else {
+        return None;
+    };
+    Url::parse(url).ok()?.to_file_path().ok()
+}
+
+/// Returns the build backend of the given project, along with the build tools it needs at
+/// import time, if its editable installs are rebuilt on import.
+fn rebuild_tools(project: &Path) -> Option<(PackageName, &'static [&'static str])> {
+    let contents = fs::read_to_string(project.join("pyproject.toml")).ok()?;
+    let pyproject = toml::from_str::<toml::Table>(&contents).ok()?;
+    let requires = pyproject.get("build-system")?.get("requires")?.as_array()?;
+    requires
+        .iter()
+        .filter_map(toml::Value::as_str)
+        .filter_map(|requirement| uv_pep508::Requirement::<VerbatimUrl>::from_str(requirement).ok())
+        .find_map(|requirement| {
+            let tools: &'static [&'static str] = match requirement.name.as_ref() {
+                "meson-python" => &["meson", "ninja"],
+                // `scikit-build-core` only rebuilds on import if `editable.rebuild` is enabled.
+                "scikit-build-core"
+                    if pyproject
+                        .get("tool")
+                        .and_then(|tool| tool.get("scikit-build"))
+                        .and_then(|tool| tool.get("editable"))
+                        .and_then(|editable| editable.get("rebuild"))
+                        .and_then(toml::Value::as_bool)
+                        .unwrap_or(false) =>
+                {
+                    &["cmake", "ninja"]
+                }
+                _ => return None,
+            };
+            Some((requirement.name, tools))
+        })
+}
+
+/// Check that the paths added by an editable distribution's `.pth` files contain its importable
+/// package.
+///
+/// Returns a diagnostic if the package isn't found on any of those paths, but is found elsewhere
+/// in the project, as when a `src` layout project is configured as a flat layout (or vice versa).
+/// Editable installs that rely on import hooks, rather than path entries, are skipped.
+fn editable_layout_mismatch(
+    package: &PackageName,
+    distribution: &InstalledDist,
+) -> Option<SitePackagesDiagnostic> {
+    let project = editable_project(distribution)?;
+    let path = distribution.install_path();
+    let site_packages = path.parent()?;
+
+    // Collect the directories added to `sys.path` by the distribution's `.pth` files.
+    let record = fs::File::open(path.join("RECORD")).ok()?;
+    let record = read_record_file(&mut BufReader::new(record)).ok()?;
+    let search_paths = record
+        .iter()
+        .filter(|entry| {
+            Path::new(&entry.path)
+                .extension()
+                .is_some_and(|ext| ext == "pth")
+        })
+        .filter_map(|entry| fs::read_to_string(site_packages.join(&entry.path)).ok())
+        .flat_map(|contents| {
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .filter(|line| !line.starts_with("import ") && !line.starts_with("import\t"))
+                .map(|line| site_packages.join(line))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    if search_paths.is_empty() {
+        return None;
+    }
+
+    // Determine the importable top-level names, defaulting to the normalized package name.
+    let top_level = fs::read_to_string(path.join("top_level.txt"))
+        .ok()
+        .map(|contents| {
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        })
+        .filter(|top_level| !top_level.is_empty())
+        .unwrap_or_else(|| vec![package.as_dist_info_name().to_string()]);
+    let is_importable = |directory: &Path| {
+        top_level.iter().any(|module| {
+            directory.join(module).is_dir() || directory.join(format!("{module}.py")).is_file()
+        })
+    };
+
+    if search_paths
+        .iter()
+        .any(|directory| is_importable(directory))
+    {
+        return None;
+    }
+
+    let found = [project.join("src"), project]
+        .into_iter()
+        .find(|directory| is_importable(directory))?;
+
+    Some(SitePackagesDiagnostic::EditableLayoutMismatch {
+        package: package.clone(),
+        search_paths,
+        found,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fmt::Write;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use fs_err as fs;
+    use indoc::formatdoc;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use uv_distribution_types::{
+        ConfigSettings, Diagnostic, ExtraBuildRequires, ExtraBuildVariables, PackageConfigSettings,
+        SuggestedFix,
+    };
+    use uv_install_wheel::LibKind;
+    use uv_normalize::PackageName;
+    use uv_pep440::{Version, VersionSpecifiers};
+    use uv_platform_tags::Tags;
+    use uv_pypi_types::VerbatimParsedUrl;
+
+    use super::{
+        DiagnosticSeverity, GroupedDiagnostic, HealthStatus, SitePackagesDiagnostic,
+        group_diagnostics, merge_diagnostics,
+    };
+    use crate::site_packages::tests::{
+        MockEnvironment, install, install_editable, mock_interpreter_with_gil, name, requirement,
+    };
+    use crate::{InstallationStrategy, SatisfiesResult, SitePackages};
+
+    #[test]
+    fn diagnostics_truncated_metadata() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+        env.install("idna", "3.6", &[])?;
+        fs::write(
+            env.site_packages()
+                .join("idna-3.6.dist-info")
+                .join("METADATA"),
+            "",
+        )?;
+
+        let diagnostics = env.diagnostics()?;
+        let truncated = diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                matches!(diagnostic, SitePackagesDiagnostic::TruncatedMetadata { .. })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(truncated.len(), 1);
+        assert!(truncated[0].includes(&name("idna")));
+        assert!(truncated[0].message().contains("--reinstall"));
+        assert!(!diagnostics.iter().any(|diagnostic| matches!(
+            diagnostic,
+            SitePackagesDiagnostic::MetadataUnavailable { .. }
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_invalid_version() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+        env.install("idna", "not_a_version", &[])?;
+
+        // The package with the invalid version isn't indexed as a distribution.
+        let site_packages = env.index()?;
+        assert!(site_packages.get_packages(&name("idna")).is_empty());
+        assert_eq!(site_packages.get_packages(&name("anyio")).len(), 1);
+
+        let diagnostics = env.diagnostics()?;
+        let invalid = diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                matches!(diagnostic, SitePackagesDiagnostic::InvalidVersion { .. })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            invalid,
+            [&SitePackagesDiagnostic::InvalidVersion {
+                package: name("idna"),
+                raw: "not_a_version".to_string(),
+            }]
+        );
+        assert_eq!(invalid[0].code(), "invalid-version");
+
+        // A valid distribution installed alongside the broken one doesn't satisfy a requirement,
+        // since the broken one would be left behind by a reinstall.
+        env.install("idna", "3.6", &[])?;
+        let interpreter = env.interpreter()?;
+        let satisfies = |site_packages: &SitePackages| {
+            site_packages.satisfies_requirements(
+                [requirement("idna")].iter(),
+                std::iter::empty(),
+                std::iter::empty(),
+                InstallationStrategy::Permissive,
+                &interpreter.resolver_marker_environment(),
+                interpreter.tags()?,
+                &ConfigSettings::default(),
+                &PackageConfigSettings::default(),
+                &ExtraBuildRequires::default(),
+                &ExtraBuildVariables::default(),
+            )
+        };
+        let mut site_packages = SitePackages::from_interpreter(&interpreter)?;
+        assert!(matches!(
+            satisfies(&site_packages)?,
+            SatisfiesResult::Unsatisfied(_)
+        ));
+
+        // Once the broken distribution is removed, the requirement is satisfied.
+        fs::remove_dir_all(env.site_packages().join("idna-not_a_version.dist-info"))?;
+        site_packages.reconcile()?;
+        assert!(matches!(
+            satisfies(&site_packages)?,
+            SatisfiesResult::Fresh { .. }
+        ));
+        assert!(!env.diagnostics()?.iter().any(|diagnostic| {
+            matches!(diagnostic, SitePackagesDiagnostic::InvalidVersion { .. })
+        }));
+        Ok(())
+    }
+
+    #[test]
+    fn group_missing_dependencies() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("httpx", "0.27.0", &["idna", "certifi"])?;
+        env.install("requests", "2.32.3", &["idna<4,>=2.5"])?;
+
+        let diagnostics = env
+            .diagnostics()?
+            .into_iter()
+            .filter(|diagnostic| {
+                matches!(diagnostic, SitePackagesDiagnostic::MissingDependency { .. })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(diagnostics.len(), 5);
+
+        let grouped = group_diagnostics(diagnostics);
+        assert_eq!(grouped.len(), 3);
+
+        let idna = grouped
+            .iter()
+            .find_map(|diagnostic| match diagnostic {
+                GroupedDiagnostic::MissingDependency {
+                    dependency,
+                    requirements,
+                } if *dependency == name("idna") => Some(requirements),
+                _ => None,
+            })
+            .expect("`idna` should be grouped");
+        let mut packages = idna
+            .iter()
+            .map(|(package, _)| package.to_string())
+            .collect::<Vec<_>>();
+        packages.sort();
+        assert_eq!(packages, ["anyio", "httpx", "requests"]);
+
+        // Dependencies missing from a single package are left as-is.
+        assert_eq!(
+            grouped
+                .iter()
+                .filter(|diagnostic| matches!(
+                    diagnostic,
+                    GroupedDiagnostic::Single(SitePackagesDiagnostic::MissingDependency { .. })
+                ))
+                .count(),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn health_summary() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        env.install("idna", "3.6", &[])?;
+        let interpreter = env.interpreter()?;
+        let markers = interpreter.resolver_marker_environment();
+        let tags = interpreter.tags()?;
+
+        // A healthy environment.
+        let summary = env
+            .index()?
+            .with_file_checks(true)
+            .health_summary(&markers, tags)?;
+        assert_eq!(summary.status, HealthStatus::Ok);
+        assert!(summary.counts.is_empty());
+
+        // A warning, but no errors.
+        env.install("foo", "1.0.0", &[])?;
+        fs::create_dir_all(site_packages.join("foo"))?;
+        fs::write(site_packages.join("foo").join("__init__.py"), "")?;
+        fs::write(
+            site_packages.join("foo-1.0.0.dist-info").join("RECORD"),
+            "foo/__init__.py,,\nfoo/__init__.py,,\nfoo-1.0.0.dist-info/METADATA,,\n",
+        )?;
+        let summary = env
+            .index()?
+            .with_file_checks(true)
+            .health_summary(&markers, tags)?;
+        assert_eq!(summary.status, HealthStatus::Warning);
+        assert_eq!(
+            summary.counts,
+            BTreeMap::from([("duplicate-record-entry", 1)])
+        );
+
+        // Errors take precedence.
+        env.install("anyio", "4.3.0", &["sniffio>=1.1", "trio>=0.23"])?;
+        env.install("httpx", "0.27.0", &["idna<3"])?;
+        let summary = env
+            .index()?
+            .with_file_checks(true)
+            .health_summary(&markers, tags)?;
+        assert_eq!(summary.status, HealthStatus::Error);
+        assert_eq!(
+            summary.counts,
+            BTreeMap::from([
+                ("duplicate-record-entry", 1),
+                ("incompatible-dependency", 1),
+                ("missing-dependency", 2),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_with_min_severity() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+
+        // Warnings: a duplicate `RECORD` entry, a self-dependency, and a peer conflict (along with
+        // an incompatible dependency, which is an error).
+        env.install("foo", "1.0.0", &["foo", "shared>=2"])?;
+        fs::create_dir_all(site_packages.join("foo"))?;
+        fs::write(site_packages.join("foo").join("__init__.py"), "")?;
+        fs::write(
+            site_packages.join("foo-1.0.0.dist-info").join("RECORD"),
+            "foo/__init__.py,,\nfoo/__init__.py,,\nfoo-1.0.0.dist-info/METADATA,,\n",
+        )?;
+        env.install("bar", "1.0.0", &["shared<2"])?;
+        env.install("shared", "2.1.0", &[])?;
+
+        // Errors: a missing dependency and an incompatible dependency.
+        env.install("httpx", "0.27.0", &["idna<3", "sniffio"])?;
+        env.install("idna", "3.6", &[])?;
+
+        let interpreter = env.interpreter()?;
+        let markers = interpreter.resolver_marker_environment();
+        let tags = interpreter.tags()?;
+        let site_packages = env.index()?.with_file_checks(true);
+
+        let all = site_packages.diagnostics(&markers, tags)?;
+        assert_eq!(
+            site_packages.diagnostics_with_min_severity(
+                &markers,
+                tags,
+                DiagnosticSeverity::Warning
+            )?,
+            all
+        );
+        let codes = |diagnostics: &[SitePackagesDiagnostic]| {
+            diagnostics
+                .iter()
+                .map(SitePackagesDiagnostic::code)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            codes(&all),
+            vec![
+                "incompatible-dependency",
+                "duplicate-record-entry",
+                "self-dependency",
+                "incompatible-dependency",
+                "missing-dependency",
+                "peer-conflict",
+            ]
+        );
+
+        // With an errors-only threshold, the warning-level checks are skipped, and the errors
+        // are reported in the same order.
+        let errors = site_packages.diagnostics_with_min_severity(
+            &markers,
+            tags,
+            DiagnosticSeverity::Error,
+        )?;
+        assert_eq!(
+            codes(&errors),
+            vec![
+                "incompatible-dependency",
+                "incompatible-dependency",
+                "missing-dependency"
+            ]
+        );
+        assert_eq!(
+            errors,
+            all.into_iter()
+                .filter(|diagnostic| diagnostic.severity() == DiagnosticSeverity::Error)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_peer_conflict() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("foo", "1.0.0", &["shared>=2"])?;
+        env.install("bar", "1.0.0", &["shared<2"])?;
+        env.install("baz", "1.0.0", &["shared"])?;
+        env.install("shared", "2.1.0", &[])?;
+
+        // Packages that agree on a shared dependency aren't flagged.
+        env.install("qux", "1.0.0", &["other>=1"])?;
+        env.install("quux", "1.0.0", &["other<3"])?;
+        env.install("other", "2.0.0", &[])?;
+
+        // Nor are packages whose requirements overlap, even if the installed version doesn't
+        // satisfy all of them.
+        env.install("corge", "1.0.0", &["overlap>=1"])?;
+        env.install("grault", "1.0.0", &["overlap>=2"])?;
+        env.install("overlap", "1.5.0", &[])?;
+
+        // But packages whose requirements are disjoint are, even if the installed version doesn't
+        // satisfy any of them.
+        env.install("garply", "1.0.0", &["pinned==1.0"])?;
+        env.install("waldo", "1.0.0", &["pinned==2.0"])?;
+        env.install("pinned", "3.0.0", &[])?;
+
+        let conflicts = env
+            .diagnostics()?
+            .into_iter()
+            .filter_map(|diagnostic| match diagnostic {
+                SitePackagesDiagnostic::PeerConflict {
+                    dependency,
+                    requiring,
+                    installed,
+                } => Some((dependency, requiring, installed)),
+                _ => None,
+            })
+            .map(|(dependency, requiring, installed)| {
+                format!(
+                    "{dependency}=={installed}: {}",
+                    requiring
+                        .iter()
+                        .map(|(package, specifiers)| format!("{package}{specifiers}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+            .collect::<BTreeSet<_>>();
+        assert_eq!(
+            conflicts.into_iter().collect::<Vec<_>>(),
+            vec![
+                "pinned==3.0.0: garply==1.0, waldo==2.0",
+                "shared==2.1.0: bar<2, foo>=2",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_missing_installed_file() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        env.install("foo", "1.0.0", &[])?;
+        fs::create_dir_all(site_packages.join("foo").join("locale"))?;
+        fs::write(site_packages.join("foo").join("__init__.py"), "")?;
+        fs::write(
+            site_packages.join("foo-1.0.0.dist-info").join("RECORD"),
+            indoc::indoc! {"
+                foo/__init__.py,,
+                foo/__pycache__/__init__.cpython-312.pyc,,
+                foo/__init__.pyo,,
+                foo/locale/en.mo,,
+                foo-1.0.0.dist-info/METADATA,,
+                foo-1.0.0.dist-info/RECORD,,
+            "},
+        )?;
+
+        // The missing bytecode is exempt, but the missing data file is reported.
+        let missing = env
+            .diagnostics()?
+            .into_iter()
+            .filter_map(|diagnostic| match diagnostic {
+                SitePackagesDiagnostic::MissingInstalledFile { package, path } => {
+                    Some((package, path))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            missing,
+            vec![(
+                name("foo"),
+                site_packages.join("foo").join("locale").join("en.mo")
+            )]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_duplicate_record_entry() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        env.install("foo", "1.0.0", &[])?;
+        fs::create_dir_all(site_packages.join("foo"))?;
+        fs::write(site_packages.join("foo").join("__init__.py"), "")?;
+        fs::write(site_packages.join("foo").join("core.py"), "")?;
+        fs::write(
+            site_packages.join("foo-1.0.0.dist-info").join("RECORD"),
+            indoc::indoc! {"
+                foo/__init__.py,sha256=47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU,0
+                foo/core.py,sha256=47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU,0
+                foo/core.py,sha256=frcCV1k9oG9oKj3dpUqdJg1PxRT2RSN_XKdLCPjaYaY,8
+                foo-1.0.0.dist-info/METADATA,,
+                foo-1.0.0.dist-info/RECORD,,
+            "},
+        )?;
+
+        let duplicates = env
+            .diagnostics()?
+            .into_iter()
+            .filter(|diagnostic| {
+                matches!(
+                    diagnostic,
+                    SitePackagesDiagnostic::DuplicateRecordEntry { .. }
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            duplicates,
+            vec![SitePackagesDiagnostic::DuplicateRecordEntry {
+                package: name("foo"),
+                path: site_packages.join("foo").join("core.py"),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_record_name_mismatch() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        for package in ["foo", "bar"] {
+            env.install(package, "1.0.0", &[])?;
+            fs::create_dir_all(site_packages.join(package))?;
+            fs::write(site_packages.join(package).join("__init__.py"), "")?;
+        }
+
+        // The `RECORD` refers to a non-normalized `.dist-info` directory.
+        fs::write(
+            site_packages.join("foo-1.0.0.dist-info").join("RECORD"),
+            indoc::indoc! {"
+                foo/__init__.py,,
+                Foo-1.0.0.dist-info/METADATA,,
+                Foo-1.0.0.dist-info/RECORD,,
+            "},
+        )?;
+        fs::write(
+            site_packages.join("bar-1.0.0.dist-info").join("RECORD"),
+            indoc::indoc! {"
+                bar/__init__.py,,
+                bar-1.0.0.dist-info/METADATA,,
+                bar-1.0.0.dist-info/RECORD,,
+            "},
+        )?;
+
+        // The stale entries aren't reported as missing files.
+        let diagnostics = env
+            .diagnostics()?
+            .into_iter()
+            .filter(|diagnostic| {
+                matches!(
+                    diagnostic,
+                    SitePackagesDiagnostic::RecordNameMismatch { .. }
+                        | SitePackagesDiagnostic::MissingInstalledFile { .. }
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![SitePackagesDiagnostic::RecordNameMismatch {
+                package: name("foo"),
+                record_name: "Foo-1.0.0.dist-info".to_string(),
+                disk_name: "foo-1.0.0.dist-info".to_string(),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_incomplete_uninstall() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        for package in ["foo", "bar"] {
+            env.install(package, "1.0.0", &[])?;
+            fs::create_dir_all(site_packages.join(package))?;
+            for module in ["__init__.py", "core.py", "util.py", "data.json"] {
+                fs::write(site_packages.join(package).join(module), "")?;
+            }
+            fs::write(
+                site_packages
+                    .join(format!("{package}-1.0.0.dist-info"))
+                    .join("RECORD"),
+                formatdoc! {"
+                    {package}/__init__.py,,
+                    {package}/core.py,,
+                    {package}/util.py,,
+                    {package}/data.json,,
+                    {package}-1.0.0.dist-info/METADATA,,
+                    {package}-1.0.0.dist-info/RECORD,,
+                "},
+            )?;
+        }
+
+        // Most of `foo`'s files were removed, but its `.dist-info` directory was left behind.
+        for module in ["__init__.py", "core.py", "util.py"] {
+            fs::remove_file(site_packages.join("foo").join(module))?;
+        }
+
+        // Only one of `bar`'s files is missing, which is reported individually.
+        fs::remove_file(site_packages.join("bar").join("data.json"))?;
+
+        let diagnostics = env
+            .diagnostics()?
+            .into_iter()
+            .filter(|diagnostic| {
+                matches!(
+                    diagnostic,
+                    SitePackagesDiagnostic::IncompleteUninstall { .. }
+                        | SitePackagesDiagnostic::MissingInstalledFile { .. }
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![
+                SitePackagesDiagnostic::MissingInstalledFile {
+                    package: name("bar"),
+                    path: site_packages.join("bar").join("data.json"),
+                },
+                SitePackagesDiagnostic::IncompleteUninstall {
+                    package: name("foo"),
+                    missing: 3,
+                    total: 4,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_relocated_environment() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        let bin = env.root.path().join("bin");
+        fs::create_dir_all(&bin)?;
+        let script = |package: &str, shebang: &str| -> Result<()> {
+            env.install(package, "1.0.0", &[])?;
+            fs::write(
+                site_packages
+                    .join(format!("{package}-1.0.0.dist-info"))
+                    .join("entry_points.txt"),
+                format!("[console_scripts]\n{package} = {package}:main\n"),
+            )?;
+            fs::write(
+                bin.join(package),
+                format!("{shebang}\nimport sys\nfrom {package} import main\nsys.exit(main())\n"),
+            )?;
+            Ok(())
+        };
+
+        let interpreter = env.root.path().join("python");
+        script("fresh", &format!("#!{}", interpreter.display()))?;
+        script(
+            "wrapped",
+            &format!(
+                r#"#!/bin/sh
+'''exec' '{}' "$0" "$@"
+' '''"#,
+                interpreter.display()
+            ),
+        )?;
+        script(
+            "relocatable",
+            r#"#!/bin/sh
+'''exec' "$(dirname -- "$(realpath -- "$0")")"/'python' "$0" "$@"
+' '''"#,
+        )?;
+        script("stale", "#!/old/venv/bin/python")?;
+        script("flagged", &format!("#!{} -sP", interpreter.display()))?;
+        script("pinned", "#!/old/venv/bin/python -sP")?;
+        script("env", "#!/usr/bin/env python3")?;
+        script("envpinned", "#!/usr/bin/env -S /old/venv/bin/python -sP")?;
+        script(
+            "spaced",
+            r#"#!/bin/sh
+'''exec' '/old/my venv/bin/python' "$0" "$@"
+' '''"#,
+        )?;
+
+        let relocated = env
+            .diagnostics()?
+            .into_iter()
+            .filter(|diagnostic| {
+                matches!(
+                    diagnostic,
+                    SitePackagesDiagnostic::RelocatedEnvironment { .. }
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            relocated,
+            vec![
+                SitePackagesDiagnostic::RelocatedEnvironment {
+                    package: name("envpinned"),
+                    script: "envpinned".to_string(),
+                    expected: interpreter.clone(),
+                    found: PathBuf::from("/old/venv/bin/python"),
+                },
+                SitePackagesDiagnostic::RelocatedEnvironment {
+                    package: name("pinned"),
+                    script: "pinned".to_string(),
+                    expected: interpreter.clone(),
+                    found: PathBuf::from("/old/venv/bin/python"),
+                },
+                SitePackagesDiagnostic::RelocatedEnvironment {
+                    package: name("spaced"),
+                    script: "spaced".to_string(),
+                    expected: interpreter.clone(),
+                    found: PathBuf::from("/old/my venv/bin/python"),
+                },
+                SitePackagesDiagnostic::RelocatedEnvironment {
+                    package: name("stale"),
+                    script: "stale".to_string(),
+                    expected: interpreter.clone(),
+                    found: PathBuf::from("/old/venv/bin/python"),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_suggested_fix() {
+        let package = name("foo");
+        let path = PathBuf::from("/site-packages/foo-1.0.0.dist-info");
+        let requirement =
+            uv_pep508::Requirement::<VerbatimParsedUrl>::from_str("bar>=1.0").unwrap();
+        let requires_python = VersionSpecifiers::from_str(">=3.13").unwrap();
+        let reinstall = Some(SuggestedFix::Reinstall(package.clone()));
+
+        let cases = [
+            (
+                SitePackagesDiagnostic::MetadataUnavailable {
+                    package: package.clone(),
+                    path: path.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::TruncatedMetadata {
+                    package: package.clone(),
+                    path: path.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::EditableLayoutMismatch {
+                    package: package.clone(),
+                    search_paths: vec![PathBuf::from("/project")],
+                    found: PathBuf::from("/project/src/foo"),
+                },
+                None,
+            ),
+            (
+                SitePackagesDiagnostic::SplitDistribution {
+                    package: package.clone(),
+                    path: path.clone(),
+                    files: vec![],
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::MissingInstalledFile {
+                    package: package.clone(),
+                    path: path.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::DuplicateRecordEntry {
+                    package: package.clone(),
+                    path: path.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::IncompleteUninstall {
+                    package: package.clone(),
+                    missing: 2,
+                    total: 3,
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::RecordNameMismatch {
+                    package: package.clone(),
+                    record_name: "Foo-1.0.0.dist-info".to_string(),
+                    disk_name: "foo-1.0.0.dist-info".to_string(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::RelocatedEnvironment {
+                    package: package.clone(),
+                    script: "foo".to_string(),
+                    expected: PathBuf::from("/new/bin/python"),
+                    found: PathBuf::from("/old/bin/python"),
+                },
+                Some(SuggestedFix::RecreateEnvironment),
+            ),
+            (
+                SitePackagesDiagnostic::StaleBytecode {
+                    package: package.clone(),
+                    path: path.clone(),
+                },
+                Some(SuggestedFix::RemovePath(path.clone())),
+            ),
+            (
+                SitePackagesDiagnostic::UnverifiedRecordSignature {
+                    package: package.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::InvalidVersion {
+                    package: package.clone(),
+                    raw: "not_a_version".to_string(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::MissingBuildTool {
+                    package: package.clone(),
+                    backend: name("meson-python"),
+                    tool: name("ninja"),
+                },
+                Some(SuggestedFix::Install(Box::new(
+                    uv_pep508::Requirement::from_str("ninja").unwrap(),
+                ))),
+            ),
+            (
+                SitePackagesDiagnostic::MalformedWheelMetadata {
+                    package: package.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::UnresolvedDynamicMetadata {
+                    package: package.clone(),
+                    fields: vec!["Requires-Dist".to_string()],
+                },
+                None,
+            ),
+            (
+                SitePackagesDiagnostic::TagsUnavailable {
+                    package: package.clone(),
+                    path: path.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::IncompatiblePythonVersion {
+                    package: package.clone(),
+                    version: Version::new([3, 12]),
+                    requires_python: requires_python.clone(),
+                },
+                Some(SuggestedFix::UpgradePython(requires_python)),
+            ),
+            (
+                SitePackagesDiagnostic::IncompatiblePlatform {
+                    package: package.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::IncompatibleInterpreterBuild {
+                    package: package.clone(),
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::MissingDependency {
+                    package: package.clone(),
+                    requirement: requirement.clone(),
+                },
+                Some(SuggestedFix::Install(Box::new(requirement.clone()))),
+            ),
+            (
+                SitePackagesDiagnostic::IncompatibleDependency {
+                    package: package.clone(),
+                    version: Version::new([1, 0]),
+                    requirement: requirement.clone(),
+                },
+                Some(SuggestedFix::Install(Box::new(requirement.clone()))),
+            ),
+            (
+                SitePackagesDiagnostic::SelfDependency {
+                    package: package.clone(),
+                },
+                None,
+            ),
+            (
+                SitePackagesDiagnostic::PeerConflict {
+                    dependency: name("bar"),
+                    requiring: vec![],
+                    installed: Version::new([1, 0]),
+                },
+                None,
+            ),
+            (
+                SitePackagesDiagnostic::DuplicatePackage {
+                    package: package.clone(),
+                    paths: vec![path.clone()],
+                },
+                reinstall.clone(),
+            ),
+            (
+                SitePackagesDiagnostic::DuplicateSameVersion {
+                    package: package.clone(),
+                    version: Version::new([1, 0]),
+                    paths: vec![path.clone()],
+                },
+                reinstall.clone(),
+            ),
+        ];
+        for (diagnostic, expected) in cases {
+            assert_eq!(diagnostic.suggested_fix(), expected, "{diagnostic:?}");
+            assert_eq!(
+                GroupedDiagnostic::Single(diagnostic).suggested_fix(),
+                expected
+            );
+        }
+
+        // Grouped missing dependencies may be required at different versions.
+        let grouped = GroupedDiagnostic::MissingDependency {
+            dependency: name("bar"),
+            requirements: vec![(package, requirement)],
+        };
+        assert_eq!(grouped.suggested_fix(), None);
+    }
+
+    #[test]
+    fn merge_environment_diagnostics() -> Result<()> {
+        let base = MockEnvironment::new()?;
+        base.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        base.install("httpx", "0.27.0", &["certifi"])?;
+
+        let overlay = MockEnvironment::new()?;
+        overlay.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        overlay.install("idna", "2.0", &[])?;
+
+        // Ignore the platform diagnostics, which are an artifact of the mocked tags.
+        let dependencies = |env: &MockEnvironment| -> Result<Vec<_>> {
+            Ok(env
+                .diagnostics()?
+                .into_iter()
+                .filter(|diagnostic| {
+                    !matches!(
+                        diagnostic,
+                        SitePackagesDiagnostic::IncompatiblePlatform { .. }
+                    )
+                })
+                .collect())
+        };
+        let base = dependencies(&base)?;
+        let overlay = dependencies(&overlay)?;
+        let messages = |diagnostics: &[SitePackagesDiagnostic]| {
+            diagnostics
+                .iter()
+                .map(Diagnostic::message)
+                .collect::<Vec<_>>()
+        };
+
+        // Overlapping diagnostics (the missing `sniffio`) are reported once.
+        let merged = merge_diagnostics(&[base.clone(), overlay.clone()]);
+        assert_eq!(
+            messages(&merged),
+            vec![
+                "The package `anyio` requires `idna>=2.8`, but `2.0` is installed",
+                "The package `anyio` requires `idna>=2.8`, but it's not installed",
+                "The package `anyio` requires `sniffio>=1.1`, but it's not installed",
+                "The package `httpx` requires `certifi`, but it's not installed",
+            ]
+        );
+
+        // The order of the environments doesn't affect the output.
+        assert_eq!(merge_diagnostics(&[overlay, base.clone()]), merged);
+
+        // Disjoint diagnostics are preserved.
+        assert_eq!(
+            merge_diagnostics(&[base.clone(), Vec::new()]).len(),
+            base.len()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_self_dependency() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("foo", "1.0.0", &["foo>=1"])?;
+        env.install("bar", "1.0.0", &["bar[cli]; extra == 'all'"])?;
+        let diagnostics = env
+            .diagnostics()?
+            .into_iter()
+            .filter(|diagnostic| {
+                !matches!(
+                    diagnostic,
+                    SitePackagesDiagnostic::IncompatiblePlatform { .. }
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![SitePackagesDiagnostic::SelfDependency {
+                package: name("foo")
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_native() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        let record = |name: &str, files: &[&str]| -> Result<()> {
+            let mut contents = String::new();
+            for file in files {
+                writeln!(contents, "{file},,")?;
+            }
+            fs::write(
+                site_packages
+                    .join(format!("{name}-1.0.0.dist-info"))
+                    .join("RECORD"),
+                contents,
+            )?;
+            Ok(())
+        };
+
+        env.install("pure", "1.0.0", &["missing"])?;
+        record("pure", &["pure/__init__.py"])?;
+        env.install("linux", "1.0.0", &["missing"])?;
+        record(
+            "linux",
+            &[
+                "linux/__init__.py",
+                "linux/_speedups.cpython-312-x86_64-linux-gnu.so",
+            ],
+        )?;
+        env.install("windows", "1.0.0", &[])?;
+        record("windows", &["windows/__init__.py", "windows/_speedups.PYD"])?;
+        fs::write(
+            site_packages.join("windows-1.0.0.dist-info").join("WHEEL"),
+            "Wheel-Version: 1.0\nTag: invalid\n",
+        )?;
+
+        // Only the compiled packages are checked, and only for compatibility.
+        let markers = env.interpreter()?.resolver_marker_environment();
+        let diagnostics = env
+            .index()?
+            .diagnostics_native(&markers, &Tags::new(vec![]));
+        assert_eq!(
+            diagnostics,
+            vec![
+                SitePackagesDiagnostic::IncompatiblePlatform {
+                    package: name("linux"),
+                },
+                SitePackagesDiagnostic::TagsUnavailable {
+                    package: name("windows"),
+                    path: site_packages.join("windows-1.0.0.dist-info"),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_malformed_wheel_metadata() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        env.install("valid", "1.0.0", &[])?;
+        env.install("missing", "1.0.0", &[])?;
+        fs::remove_file(site_packages.join("missing-1.0.0.dist-info").join("WHEEL"))?;
+        env.install("unversioned", "1.0.0", &[])?;
+        fs::write(
+            site_packages
+                .join("unversioned-1.0.0.dist-info")
+                .join("WHEEL"),
+            "Generator: hand-rolled\nRoot-Is-Purelib: true\nTag: py3-none-any\n",
+        )?;
+
+        let interpreter = env.interpreter()?;
+        let diagnostics = env.index()?.diagnostics(
+            &interpreter.resolver_marker_environment(),
+            interpreter.tags()?,
+        )?;
+        assert_eq!(
+            diagnostics,
+            vec![
+                SitePackagesDiagnostic::MalformedWheelMetadata {
+                    package: name("missing"),
+                },
+                SitePackagesDiagnostic::MalformedWheelMetadata {
+                    package: name("unversioned"),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_unresolved_dynamic_metadata() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("resolved", "1.0.0", &[])?;
+        env.install("dynamic", "1.0.0", &[])?;
+        fs::write(
+            env.site_packages()
+                .join("dynamic-1.0.0.dist-info")
+                .join("METADATA"),
+            "Metadata-Version: 2.2\nName: dynamic\nVersion: 1.0.0\nDynamic: Requires-Dist\nDynamic: Provides-Extra\n",
+        )?;
+
+        let interpreter = env.interpreter()?;
+        let markers = interpreter.resolver_marker_environment();
+
+        // File-level checks are opt-in.
+        assert_eq!(
+            env.index()?.diagnostics(&markers, interpreter.tags()?)?,
+            vec![]
+        );
+
+        let diagnostics = env
+            .index()?
+            .with_file_checks(true)
+            .diagnostics(&markers, interpreter.tags()?)?;
+        assert_eq!(
+            diagnostics,
+            vec![SitePackagesDiagnostic::UnresolvedDynamicMetadata {
+                package: name("dynamic"),
+                fields: vec!["Requires-Dist".to_string(), "Provides-Extra".to_string()],
+            }]
+        );
+        assert_eq!(
+            diagnostics[0].message(),
+            "The package `dynamic` was installed from a wheel whose metadata marks fields as dynamic (`Requires-Dist`, `Provides-Extra`), which is only valid for source distributions. Its dependencies may be incomplete."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_interpreter_build() -> Result<()> {
+        let wheel = |env: &MockEnvironment, package: &str, tag: &str| -> Result<()> {
+            env.install(package, "1.0.0", &[])?;
+            fs::write(
+                env.site_packages()
+                    .join(format!("{package}-1.0.0.dist-info"))
+                    .join("WHEEL"),
+                format!("Wheel-Version: 1.0\nTag: {tag}\n"),
+            )?;
+            Ok(())
+        };
+        let diagnostics = |env: &MockEnvironment, gil_disabled: bool| -> Result<Vec<String>> {
+            let interpreter = mock_interpreter_with_gil(
+                env.root.path(),
+                &env.site_packages(),
+                &env.platlib(),
+                gil_disabled,
+            )?;
+            let site_packages = SitePackages::from_interpreter(&interpreter)?;
+            Ok(site_packages
+                .diagnostics(
+                    &interpreter.resolver_marker_environment(),
+                    interpreter.tags()?,
+                )?
+                .iter()
+                .map(Diagnostic::message)
+                .collect())
+        };
+
+        let env = MockEnvironment::new()?;
+        wheel(&env, "pure", "py3-none-any")?;
+        wheel(&env, "gil", "cp312-cp312-manylinux_2_17_x86_64")?;
+        wheel(&env, "stable", "cp312-abi3-manylinux_2_17_x86_64")?;
+        wheel(&env, "nogil", "cp312-cp312t-manylinux_2_17_x86_64")?;
+        wheel(&env, "windows", "cp312-cp312t-win_amd64")?;
+
+        // On a free-threaded build, GIL-enabled and stable ABI wheels are flagged.
+        assert_eq!(
+            diagnostics(&env, true)?,
+            vec![
+                "The package `gil` was built for a different interpreter build (e.g., free-threaded or GIL-enabled)",
+                "The package `stable` was built for a different interpreter build (e.g., free-threaded or GIL-enabled)",
+                "The package `windows` was built for a different platform",
+            ]
+        );
+
+        // On a GIL-enabled build, free-threaded wheels are flagged.
+        let env = MockEnvironment::new()?;
+        wheel(&env, "gil", "cp312-cp312-manylinux_2_17_x86_64")?;
+        wheel(&env, "nogil", "cp312-cp312t-manylinux_2_17_x86_64")?;
+        assert_eq!(
+            diagnostics(&env, false)?,
+            vec![
+                "The package `nogil` was built for a different interpreter build (e.g., free-threaded or GIL-enabled)",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_stream() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("zeta", "1.0.0", &["missing-z"])?;
+        env.install("alpha", "1.0.0", &["missing-a", "zeta>=2"])?;
+        env.install("mid", "1.0.0", &["missing-m"])?;
+        let site_packages = env.index()?;
+        let markers = env.interpreter()?.resolver_marker_environment();
+        let tags = Tags::new(vec![]);
+
+        let mut streamed = Vec::new();
+        site_packages.diagnostics_stream(&markers, &tags, |diagnostic| {
+            if !matches!(
+                diagnostic,
+                SitePackagesDiagnostic::IncompatiblePlatform { .. }
+            ) {
+                streamed.push(diagnostic.message());
+            }
+        })?;
+        assert_eq!(
+            streamed,
+            vec![
+                "The package `alpha` requires `missing-a`, but it's not installed".to_string(),
+                "The package `alpha` requires `zeta>=2`, but `1.0.0` is installed".to_string(),
+                "The package `mid` requires `missing-m`, but it's not installed".to_string(),
+                "The package `zeta` requires `missing-z`, but it's not installed".to_string(),
+            ]
+        );
+
+        // The collected diagnostics match the streamed diagnostics, in the same order.
+        let collected = site_packages
+            .diagnostics(&markers, &tags)?
+            .into_iter()
+            .filter(|diagnostic| {
+                !matches!(
+                    diagnostic,
+                    SitePackagesDiagnostic::IncompatiblePlatform { .. }
+                )
+            })
+            .map(|diagnostic| diagnostic.message())
+            .collect::<Vec<_>>();
+        assert_eq!(collected, streamed);
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_stale_bytecode() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        env.install("foo", "1.0.0", &[])?;
+        let package = site_packages.join("foo");
+        let pycache = package.join("__pycache__");
+        fs::create_dir_all(&pycache)?;
+        fs::write(
+            site_packages.join("foo-1.0.0.dist-info").join("RECORD"),
+            indoc::indoc! {"
+                foo/__init__.py,,
+                foo/fresh.py,,
+                foo/edited.py,,
+                foo/other.py,,
+                foo/truncated.py,,
+                foo-1.0.0.dist-info/METADATA,,
+            "},
+        )?;
+
+        // Write a timestamp-based `.pyc` header for the given source.
+        let compile = |module: &str, magic: u16, source: &str| -> Result<()> {
+            let metadata = fs::metadata(package.join(format!("{module}.py")))?;
+            let mtime = metadata
+                .modified()?
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs();
+            let mut header = Vec::new();
+            header.extend_from_slice(&magic.to_le_bytes());
+            header.extend_from_slice(b"\r\n");
+            header.extend_from_slice(&0u32.to_le_bytes());
+            header.extend_from_slice(&u32::try_from(mtime)?.to_le_bytes());
+            header.extend_from_slice(&u32::try_from(source.len())?.to_le_bytes());
+            fs::write(pycache.join(format!("{module}.cpython-312.pyc")), header)?;
+            Ok(())
+        };
+        for module in ["__init__", "fresh", "edited", "other", "truncated"] {
+            fs::write(package.join(format!("{module}.py")), "x = 1\n")?;
+        }
+        compile("__init__", 3531, "x = 1\n")?;
+        compile("fresh", 3531, "x = 1\n")?;
+        compile("other", 3495, "x = 1\n")?;
+
+        // Compile `edited`, and then edit its source.
+        compile("edited", 3531, "x = 1\n")?;
+        fs::write(package.join("edited.py"), "x = 12345\n")?;
+
+        // A `.pyc` that ends before its header does is stale, too.
+        fs::write(
+            pycache.join("truncated.cpython-312.pyc"),
+            3531u16.to_le_bytes(),
+        )?;
+
+        let stale = env
+            .index()?
+            .bytecode_diagnostics()
+            .into_iter()
+            .filter_map(|diagnostic| match diagnostic {
+                SitePackagesDiagnostic::StaleBytecode { package, path } => Some((package, path)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stale,
+            vec![
+                (name("foo"), pycache.join("edited.cpython-312.pyc")),
+                (name("foo"), pycache.join("other.cpython-312.pyc")),
+                (name("foo"), pycache.join("truncated.cpython-312.pyc")),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn record_signature_diagnostics() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        let trusted = Ed25519KeyPair::from_seed_unchecked(&[1; 32]).unwrap();
+        let untrusted = Ed25519KeyPair::from_seed_unchecked(&[2; 32]).unwrap();
+
+        for package in ["pkcs", "signed", "tampered", "unsigned", "untrusted"] {
+            env.install(package, "1.0.0", &[])?;
+            fs::write(
+                site_packages.join(format!("{package}-1.0.0.dist-info/RECORD")),
+                format!("{package}-1.0.0.dist-info/METADATA,,\n"),
+            )?;
+        }
+
+        // Write a `RECORD.jws` that signs the current `RECORD` of the given package.
+        let sign = |package: &str, key: &Ed25519KeyPair| -> Result<()> {
+            let dist_info = site_packages.join(format!("{package}-1.0.0.dist-info"));
+            let digest =
+                ring::digest::digest(&ring::digest::SHA256, &fs::read(dist_info.join("RECORD"))?);
+            let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"EdDSA"}"#);
+            let payload = URL_SAFE_NO_PAD.encode(format!(
+                r#"{{"hash":"sha256={}"}}"#,
+                URL_SAFE_NO_PAD.encode(digest)
+            ));
+            let signature = key.sign(format!("{header}.{payload}").as_bytes());
+            fs::write(
+                dist_info.join("RECORD.jws"),
+                format!("{header}.{payload}.{}", URL_SAFE_NO_PAD.encode(signature)),
+            )?;
+            Ok(())
+        };
+        sign("signed", &trusted)?;
+        sign("tampered", &trusted)?;
+        sign("untrusted", &untrusted)?;
+
+        // Modify the `RECORD` after signing.
+        fs::write(
+            site_packages.join("tampered-1.0.0.dist-info/RECORD"),
+            "tampered-1.0.0.dist-info/METADATA,,\ntampered/__init__.py,,\n",
+        )?;
+
+        // PKCS #7 signatures can't be verified.
+        fs::write(
+            site_packages.join("pkcs-1.0.0.dist-info/RECORD.p7s"),
+            "-----BEGIN PKCS7-----\n-----END PKCS7-----\n",
+        )?;
+
+        let unverified = |trusted_keys: &[[u8; 32]]| -> Result<Vec<PackageName>> {
+            Ok(env
+                .index()?
+                .record_signature_diagnostics(trusted_keys)
+                .into_iter()
+                .filter_map(|diagnostic| match diagnostic {
+                    SitePackagesDiagnostic::UnverifiedRecordSignature { package } => Some(package),
+                    _ => None,
+                })
+                .collect())
+        };
+        let key = <[u8; 32]>::try_from(trusted.public_key().as_ref())?;
+        assert_eq!(
+            unverified(&[key])?,
+            vec![name("pkcs"), name("tampered"), name("untrusted")]
+        );
+
+        // Without any trusted keys, every signature is unverifiable.
+        assert_eq!(
+            unverified(&[])?,
+            vec![
+                name("pkcs"),
+                name("signed"),
+                name("tampered"),
+                name("untrusted")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn build_tool_diagnostics() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+
+        // A `meson-python` project, which is rebuilt on import.
+        let native = env.root.path().join("native");
+        fs::create_dir_all(&native)?;
+        fs::write(
+            native.join("pyproject.toml"),
+            indoc::indoc! {r#"
+                [build-system]
+                requires = ["meson-python>=0.13", "meson>=1.1", "ninja"]
+                build-backend = "mesonpy"
+            "#},
+        )?;
+        install_editable(&site_packages, "native", "0.1.0", &native, &native)?;
+
+        // A `scikit-build-core` project, which isn't rebuilt on import by default.
+        let cmake = env.root.path().join("cmake");
+        fs::create_dir_all(&cmake)?;
+        fs::write(
+            cmake.join("pyproject.toml"),
+            indoc::indoc! {r#"
+                [build-system]
+                requires = ["scikit-build-core"]
+                build-backend = "scikit_build_core.build"
+            "#},
+        )?;
+        install_editable(&site_packages, "cmake_project", "0.1.0", &cmake, &cmake)?;
+
+        let missing = |site_packages: &SitePackages| {
+            site_packages
+                .build_tool_diagnostics()
+                .into_iter()
+                .filter_map(|diagnostic| match diagnostic {
+                    SitePackagesDiagnostic::MissingBuildTool {
+                        package,
+                        backend,
+                        tool,
+                    } => Some((package, backend, tool)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            missing(&env.index()?),
+            vec![
+                (name("native"), name("meson-python"), name("meson")),
+                (name("native"), name("meson-python"), name("ninja")),
+            ]
+        );
+
+        // Once the build tools are installed, nothing is reported.
+        env.install("meson", "1.4.0", &[])?;
+        env.install("ninja", "1.11.1", &[])?;
+        assert!(missing(&env.index()?).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_duplicate_same_version() -> Result<()> {
+        let env = MockEnvironment::split()?;
+
+        // The same version installed twice, as from a corrupt install.
+        env.install("foo", "1.0.0", &[])?;
+        install(&env.platlib(), "foo", "1.0.0", &[])?;
+
+        // Different versions, as when one shadows the other.
+        env.install("bar", "1.0.0", &[])?;
+        install(&env.platlib(), "bar", "2.0.0", &[])?;
+
+        let duplicates = env
+            .diagnostics()?
+            .into_iter()
+            .filter(|diagnostic| {
+                matches!(
+                    diagnostic,
+                    SitePackagesDiagnostic::DuplicatePackage { .. }
+                        | SitePackagesDiagnostic::DuplicateSameVersion { .. }
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            duplicates,
+            vec![
+                SitePackagesDiagnostic::DuplicatePackage {
+                    package: name("bar"),
+                    paths: vec![
+                        env.site_packages().join("bar-1.0.0.dist-info"),
+                        env.platlib().join("bar-2.0.0.dist-info"),
+                    ],
+                },
+                SitePackagesDiagnostic::DuplicateSameVersion {
+                    package: name("foo"),
+                    version: Version::new([1, 0, 0]),
+                    paths: vec![
+                        env.site_packages().join("foo-1.0.0.dist-info"),
+                        env.platlib().join("foo-1.0.0.dist-info"),
+                    ],
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_split_distribution() -> Result<()> {
+        let env = MockEnvironment::split()?;
+
+        // A pure-Python distribution whose modules ended up in `platlib`, rather than alongside
+        // its `.dist-info` directory in `purelib`.
+        env.install("foo", "0.1.0", &[])?;
+        fs::create_dir_all(env.platlib().join("foo"))?;
+        fs::write(env.platlib().join("foo").join("__init__.py"), "")?;
+        fs::write(
+            env.site_packages()
+                .join("foo-0.1.0.dist-info")
+                .join("RECORD"),
+            "foo/__init__.py,,\nfoo-0.1.0.dist-info/METADATA,,\n",
+        )?;
+
+        // A distribution that was installed entirely into `platlib`.
+        install(&env.platlib(), "bar", "0.1.0", &[])?;
+        fs::create_dir_all(env.platlib().join("bar"))?;
+        fs::write(env.platlib().join("bar").join("__init__.py"), "")?;
+        fs::write(
+            env.platlib().join("bar-0.1.0.dist-info").join("RECORD"),
+            "bar/__init__.py,,\nbar-0.1.0.dist-info/METADATA,,\n",
+        )?;
+
+        let site_packages = env.index()?;
+        let library = |package: &str| {
+            site_packages
+                .get_packages(&name(package))
+                .first()
+                .and_then(|distribution| site_packages.library(distribution))
+        };
+        assert_eq!(library("foo"), Some(LibKind::Pure));
+        assert_eq!(library("bar"), Some(LibKind::Plat));
+
+        let split = env
+            .diagnostics()?
+            .into_iter()
+            .filter_map(|diagnostic| match diagnostic {
+                SitePackagesDiagnostic::SplitDistribution { package, files, .. } => {
+                    Some((package, files))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].0, name("foo"));
+        assert_eq!(
+            split[0].1,
+            vec![env.platlib().join("foo").join("__init__.py")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diagnostics_editable_layout() -> Result<()> {
+        let env = MockEnvironment::new()?;
+
+        // A `src` layout project, with a `.pth` file that points to the project root.
+        let project = env.root.path().join("project");
+        fs::create_dir_all(project.join("src").join("foo"))?;
+        fs::write(project.join("src").join("foo").join("__init__.py"), "")?;
+        install_editable(&env.site_packages(), "foo", "0.1.0", &project, &project)?;
+
+        // A `src` layout project, with a `.pth` file that points to `src`.
+        let other = env.root.path().join("other");
+        fs::create_dir_all(other.join("src").join("bar"))?;
+        fs::write(other.join("src").join("bar").join("__init__.py"), "")?;
+        install_editable(
+            &env.site_packages(),
+            "bar",
+            "0.1.0",
+            &other,
+            &other.join("src"),
+        )?;
+
+        let mismatched = env
+            .diagnostics()?
+            .into_iter()
+            .filter_map(|diagnostic| match diagnostic {
+                SitePackagesDiagnostic::EditableLayoutMismatch { package, found, .. } => {
+                    Some((package, found))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(mismatched.len(), 1);
+        assert_eq!(mismatched[0].0, name("foo"));
+        assert_eq!(mismatched[0].1, project.join("src"));
+        Ok(())
+    }
+}
@@ -1,13 +1,25 @@
 pub use compile::{CompileError, compile_tree};
+pub use dependency_graph::{DependencyEdge, DependencyGraph};
+pub use diagnostics::{
+    DiagnosticSeverity, GroupedDiagnostic, HealthStatus, HealthSummary, SitePackagesDiagnostic,
+    group_diagnostics, merge_diagnostics,
+};
 pub use installer::{Installer, Reporter as InstallReporter};
 pub use plan::{Plan, Planner};
 pub use preparer::{Error as PrepareError, Preparer, Reporter as PrepareReporter};
+pub use satisfies::{
+    CompatibilityPolicy, DefaultCompatibilityPolicy, SatisfiesOptions, SatisfiesOutcome,
+    SatisfiesReport, SatisfiesReportEntry, SatisfiesResult, SatisfiesStatus, SatisfiesTrace,
+    SatisfiesTraceNode, SatisfiesVerdict,
+};
 pub use site_packages::{
-    InstallationStrategy, SatisfiesResult, SitePackages, SitePackagesDiagnostic,
+    Cancelled, EnvironmentDiff, InstallationStrategy, LockValidation, ReconcileReport, SitePackages,
 };
 pub use uninstall::{UninstallError, uninstall};
 
 mod compile;
+mod dependency_graph;
+mod diagnostics;
 mod preparer;
 
 mod installer;
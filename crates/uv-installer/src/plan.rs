@@ -21,7 +21,7 @@ use uv_normalize::PackageName;
 use uv_platform_tags::{IncompatibleTag, TagCompatibility, Tags};
 use uv_pypi_types::VerbatimParsedUrl;
 use uv_python::PythonEnvironment;
-use uv_types::HashStrategy;
+use uv_types::{BuildIsolation, HashStrategy};
 
 use crate::satisfies::RequirementSatisfaction;
 use crate::{InstallationStrategy, SitePackages};
@@ -61,6 +61,7 @@ impl<'a> Planner<'a> {
         config_settings_package: &PackageConfigSettings,
         extra_build_requires: &ExtraBuildRequires,
         extra_build_variables: &ExtraBuildVariables,
+        build_isolation: BuildIsolation<'_>,
         cache: &Cache,
         venv: &PythonEnvironment,
         tags: &Tags,
@@ -76,14 +77,17 @@ impl<'a> Planner<'a> {
             extra_build_requires,
             extra_build_variables,
         );
+        let markers = venv.interpreter().resolver_marker_environment();
         let built_index = BuiltWheelIndex::new(
             cache,
             tags,
+            &markers,
             hasher,
             config_settings,
             config_settings_package,
             extra_build_requires,
             extra_build_variables,
+            build_isolation,
         );
 
         let mut cached = vec![];
@@ -127,6 +131,8 @@ impl<'a> Planner<'a> {
                             installed,
                             &source,
                             installation,
+                            site_packages.compatibility_policy(),
+                            &markers,
                             tags,
                             config_settings,
                             config_settings_package,
@@ -1,24 +1,53 @@
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fmt::Debug;
+use std::path::Path;
 
+use anyhow::{Context, Result};
+use rustc_hash::{FxBuildHasher, FxHashMap, FxHashSet};
 use same_file::is_same_file;
 use tracing::{debug, trace};
 use url::Url;
 
-use uv_cache_info::CacheInfo;
+use uv_cache_info::{CacheInfo, CacheInfoError, CacheMarkers};
 use uv_cache_key::{CanonicalUrl, RepositoryUrl};
 use uv_distribution_filename::ExpandedTags;
 use uv_distribution_types::{
     BuildInfo, BuildVariables, ConfigSettings, ExtraBuildRequirement, ExtraBuildRequires,
-    ExtraBuildVariables, InstalledDirectUrlDist, InstalledDist, InstalledDistKind,
-    PackageConfigSettings, RequirementSource,
+    ExtraBuildVariables, InstalledDirectUrlDist, InstalledDist, InstalledDistKind, Name,
+    NameRequirementSpecification, PackageConfigSettings, Requirement, RequirementSource,
+    UnresolvedRequirement, UnresolvedRequirementSpecification,
 };
-use uv_git_types::GitOid;
+use uv_git_types::GitUrl;
 use uv_normalize::PackageName;
+use uv_pep440::{Version, VersionSpecifiers};
+use uv_pep508::VersionOrUrl;
 use uv_platform_tags::{IncompatibleTag, TagCompatibility, Tags};
-use uv_pypi_types::{DirInfo, DirectUrl, VcsInfo, VcsKind};
+use uv_pypi_types::{DirInfo, DirectUrl, ResolverMarkerEnvironment, VcsInfo, VcsKind};
 
 use crate::InstallationStrategy;
+use crate::site_packages::SitePackages;
+
+/// A policy that decides whether an installed version is compatible with the version specifiers
+/// of a requirement (e.g., to only accept exact pins).
+///
+/// The policy is only consulted for requirements on registry packages; URL requirements are
+/// always checked against the installed source.
+pub trait CompatibilityPolicy: Debug + Send + Sync {
+    /// Returns `true` if the installed version satisfies the given specifiers.
+    fn is_compatible(&self, installed: &Version, specifiers: &VersionSpecifiers) -> bool;
+}
+
+/// The default [`CompatibilityPolicy`], under which an installed version is compatible if it's
+/// contained in the specifiers.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DefaultCompatibilityPolicy;
+
+impl CompatibilityPolicy for DefaultCompatibilityPolicy {
+    fn is_compatible(&self, installed: &Version, specifiers: &VersionSpecifiers) -> bool {
+        specifiers.contains(installed)
+    }
+}
 
 #[derive(Debug, Copy, Clone)]
 pub(crate) enum RequirementSatisfaction {
@@ -31,12 +60,17 @@ pub(crate) enum RequirementSatisfaction {
 impl RequirementSatisfaction {
     /// Returns true if a requirement is satisfied by an installed distribution.
     ///
+    /// The installed version of a registry requirement is checked against its specifiers via the
+    /// given [`CompatibilityPolicy`].
+    ///
     /// Returns an error if IO fails during a freshness check for a local path.
     pub(crate) fn check(
         name: &PackageName,
         distribution: &InstalledDist,
         source: &RequirementSource,
         installation: InstallationStrategy,
+        policy: &dyn CompatibilityPolicy,
+        markers: &ResolverMarkerEnvironment,
         tags: &Tags,
         config_settings: &ConfigSettings,
         config_settings_package: &PackageConfigSettings,
@@ -89,7 +123,7 @@ impl RequirementSatisfaction {
                     }
                 }
 
-                if !specifier.contains(distribution.version()) {
+                if !policy.is_compatible(distribution.version(), specifier) {
                     return Self::Mismatch;
                 }
             }
@@ -140,7 +174,7 @@ impl RequirementSatisfaction {
                         let Some(cache_info) = cache_info.as_ref() else {
                             return Self::OutOfDate;
                         };
-                        match CacheInfo::from_path(&archive) {
+                        match read_cache_info(&archive, markers, cache_info) {
                             Ok(read_cache_info) => {
                                 if *cache_info != read_cache_info {
                                     return Self::OutOfDate;
@@ -199,15 +233,12 @@ impl RequirementSatisfaction {
                     return Self::Mismatch;
                 }
 
-                // TODO(charlie): It would be more consistent for us to compare the requested
-                // revisions here.
-                if installed_precise.as_deref()
-                    != requested_git.precise().as_ref().map(GitOid::as_str)
-                {
+                if !git_commit_matches(requested_git, installed_precise.as_deref()) {
                     debug!(
-                        "Precise mismatch: {:?} vs. {:?}",
+                        "Precise mismatch: {:?} vs. {:?} (`{}`)",
                         installed_precise,
-                        requested_git.precise()
+                        requested_git.precise(),
+                        requested_git.reference()
                     );
                     return Self::OutOfDate;
                 }
@@ -254,7 +285,7 @@ impl RequirementSatisfaction {
                 let Some(cache_info) = cache_info.as_ref() else {
                     return Self::OutOfDate;
                 };
-                match CacheInfo::from_path(requested_path) {
+                match read_cache_info(requested_path, markers, cache_info) {
                     Ok(read_cache_info) => {
                         if *cache_info != read_cache_info {
                             return Self::OutOfDate;
@@ -321,7 +352,7 @@ impl RequirementSatisfaction {
                 let Some(cache_info) = cache_info.as_ref() else {
                     return Self::OutOfDate;
                 };
-                match CacheInfo::from_path(requested_path) {
+                match read_cache_info(requested_path, markers, cache_info) {
                     Ok(read_cache_info) => {
                         if *cache_info != read_cache_info {
                             return Self::OutOfDate;
@@ -350,6 +381,781 @@ impl RequirementSatisfaction {
         // Otherwise, assume the requirement is up-to-date.
         Self::Satisfied
     }
+
+    /// If `offline` is set, treat an out-of-date distribution as satisfied, since reinstalling it
+    /// may require network access (e.g., to resolve a Git reference, or to fetch build
+    /// dependencies).
+    pub(crate) fn offline(self, offline: bool) -> Self {
+        match self {
+            Self::OutOfDate if offline => Self::Satisfied,
+            satisfaction => satisfaction,
+        }
+    }
+}
+
+/// We check if all requirements are already satisfied, recursing through the requirements tree.
+#[derive(Debug)]
+pub enum SatisfiesResult {
+    /// All requirements are recursively satisfied.
+    Fresh {
+        /// The flattened set (transitive closure) of all requirements checked.
+        recursive_requirements: FxHashSet<Requirement>,
+    },
+    /// We found an unsatisfied requirement. Since we exit early, we only know about the first
+    /// unsatisfied requirement.
+    Unsatisfied(String),
+    /// The traversal exceeded [`SatisfiesOptions::max_depth`] before reaching a verdict. Contains
+    /// the first requirement beyond the maximum depth.
+    Indeterminate(String),
+}
+
+impl SatisfiesResult {
+    /// Returns the given constraints whose package never appears in the transitive closure of the
+    /// requirements, which are likely stale entries (e.g., in a `constraints.txt`).
+    ///
+    /// Unused constraints can only be determined if the requirements are satisfied; otherwise,
+    /// the closure is incomplete, and no constraints are returned.
+    pub fn unused_constraints<'a>(
+        &self,
+        constraints: impl IntoIterator<Item = &'a Requirement>,
+    ) -> Vec<&'a Requirement> {
+        let Self::Fresh {
+            recursive_requirements,
+        } = self
+        else {
+            return Vec::new();
+        };
+        let names = recursive_requirements
+            .iter()
+            .map(|requirement| &requirement.name)
+            .collect::<FxHashSet<_>>();
+        constraints
+            .into_iter()
+            .filter(|constraint| !names.contains(&constraint.name))
+            .collect()
+    }
+}
+
+/// Options for [`SitePackages::satisfies_requirements_with_options`] and
+/// [`SitePackages::satisfies_spec_with_options`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SatisfiesOptions<'env> {
+    /// The marker environment against which to evaluate the requirements. Defaults to that of the
+    /// environment's own interpreter.
+    pub markers: Option<&'env ResolverMarkerEnvironment>,
+    /// The tags against which to check installed wheels. Defaults to those of the environment's
+    /// own interpreter.
+    pub tags: Option<&'env Tags>,
+    /// Give up once the traversal descends more than this many levels below the direct
+    /// requirements (which are at depth zero), returning [`SatisfiesResult::Indeterminate`].
+    ///
+    /// This bounds the work performed for pathologically deep dependency graphs.
+    pub max_depth: Option<usize>,
+    /// First check the names of the direct requirements against the installed packages,
+    /// returning the first requirement that isn't installed at all without walking the
+    /// dependency tree.
+    ///
+    /// This front-loads the common case of a missing package in large requirement sets. If the
+    /// requirements are satisfied, the result is unchanged; if not, the reported requirement may
+    /// differ.
+    pub prefilter: bool,
+    /// Record a [`SatisfiesTrace`] of every requirement visited and its verdict. If the
+    /// requirements are unsatisfied, the trace ends at the first requirement that failed.
+    pub trace: bool,
+    /// Continue past unsatisfied requirements, rather than stopping at the first, recording every
+    /// visited requirement in the trace (e.g., for `uv pip install --dry-run --format json`).
+    ///
+    /// The dependencies of unsatisfied requirements aren't visited.
+    pub exhaustive: bool,
+}
+
+/// The outcome of [`SitePackages::satisfies_requirements_with_options`] and
+/// [`SitePackages::satisfies_spec_with_options`].
+#[derive(Debug)]
+pub struct SatisfiesOutcome {
+    /// Whether the installed packages satisfy the requirements.
+    pub result: SatisfiesResult,
+    /// The requirements visited, if [`SatisfiesOptions::trace`] or
+    /// [`SatisfiesOptions::exhaustive`] was set.
+    pub trace: Option<SatisfiesTrace>,
+    /// The hashes declared for each package in the satisfied closure, via
+    /// [`SitePackages::satisfies_spec_with_options`]. Empty if the requirements are unsatisfied.
+    pub hashes: BTreeMap<PackageName, BTreeSet<String>>,
+}
+
+impl SatisfiesOutcome {
+    /// Returns a serializable report of every visited requirement and whether it's satisfied, if
+    /// the requirements were traced.
+    pub fn report(&self) -> Option<SatisfiesReport> {
+        self.trace
+            .as_ref()
+            .map(|trace| SatisfiesReport::from_trace(&self.result, trace))
+    }
+}
+
+impl From<SatisfiesResult> for SatisfiesOutcome {
+    fn from(result: SatisfiesResult) -> Self {
+        Self {
+            result,
+            trace: None,
+            hashes: BTreeMap::new(),
+        }
+    }
+}
+
+/// A trace of the requirements visited by [`SitePackages::satisfies_requirements_with_options`],
+/// if [`SatisfiesOptions::trace`] is set.
+///
+/// The trace forms a tree: direct requirements are roots, and each transitive requirement points
+/// to the requirement whose distribution declared it.
+#[derive(Debug, Default)]
+pub struct SatisfiesTrace {
+    nodes: Vec<SatisfiesTraceNode>,
+}
+
+impl SatisfiesTrace {
+    /// Record a visited requirement, returning its index in the trace.
+    fn push(
+        &mut self,
+        requirement: Requirement,
+        parent: Option<usize>,
+        verdict: SatisfiesVerdict,
+    ) -> usize {
+        self.nodes.push(SatisfiesTraceNode {
+            requirement,
+            parent,
+            verdict,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Returns all visited requirements, in the order in which they were visited.
+    pub fn nodes(&self) -> &[SatisfiesTraceNode] {
+        &self.nodes
+    }
+
+    /// Returns the direct requirements, along with their indexes in the trace.
+    pub fn roots(&self) -> impl Iterator<Item = (usize, &SatisfiesTraceNode)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.parent.is_none())
+    }
+
+    /// Returns the requirements introduced by the node at the given index, along with their
+    /// indexes in the trace.
+    pub fn children(&self, index: usize) -> impl Iterator<Item = (usize, &SatisfiesTraceNode)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.parent == Some(index))
+    }
+
+    /// Returns the first requirement that wasn't satisfied, if any.
+    pub fn failure(&self) -> Option<&SatisfiesTraceNode> {
+        self.nodes
+            .iter()
+            .find(|node| node.verdict != SatisfiesVerdict::Satisfied)
+    }
+}
+
+/// A serializable report of whether an environment satisfies a set of requirements, as returned
+/// by [`SatisfiesOutcome::report`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SatisfiesReport {
+    /// Whether every requirement is satisfied, such that nothing would be installed.
+    pub satisfied: bool,
+    /// Each visited requirement, in the order in which it was visited.
+    pub requirements: Vec<SatisfiesReportEntry>,
+}
+
+impl SatisfiesReport {
+    fn from_trace(result: &SatisfiesResult, trace: &SatisfiesTrace) -> Self {
+        let requirements = trace
+            .nodes()
+            .iter()
+            .map(|node| SatisfiesReportEntry {
+                name: node.requirement.name.clone(),
+                requirement: node.requirement.to_string(),
+                required_by: node
+                    .parent
+                    .map(|parent| trace.nodes[parent].requirement.name.clone()),
+                status: SatisfiesStatus::from(&node.verdict),
+                constraint: match &node.verdict {
+                    SatisfiesVerdict::Constraint { constraint } => Some(constraint.to_string()),
+                    _ => None,
+                },
+            })
+            .collect();
+        Self {
+            satisfied: matches!(result, SatisfiesResult::Fresh { .. }),
+            requirements,
+        }
+    }
+}
+
+/// A single requirement in a [`SatisfiesReport`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SatisfiesReportEntry {
+    /// The name of the required package.
+    pub name: PackageName,
+    /// The requirement, after applying any overrides.
+    pub requirement: String,
+    /// The package that introduced the requirement, or `None` for direct requirements.
+    pub required_by: Option<PackageName>,
+    /// Whether the installed environment satisfies the requirement.
+    pub status: SatisfiesStatus,
+    /// The unsatisfied constraint, if the status is [`SatisfiesStatus::Constraint`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<String>,
+}
+
+/// The status of a requirement in a [`SatisfiesReport`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SatisfiesStatus {
+    /// The requirement is satisfied by the installed distribution.
+    Satisfied,
+    /// The package isn't installed.
+    Missing,
+    /// There are multiple installed distributions for the package.
+    Duplicate,
+    /// The installed distribution doesn't match the requirement.
+    Mismatch,
+    /// The installed distribution matches the requirement, but is out-of-date.
+    OutOfDate,
+    /// The installed distribution's cached build information couldn't be read.
+    CacheInvalid,
+    /// The installed distribution doesn't satisfy a constraint on the package.
+    Constraint,
+}
+
+impl From<&SatisfiesVerdict> for SatisfiesStatus {
+    fn from(verdict: &SatisfiesVerdict) -> Self {
+        match verdict {
+            SatisfiesVerdict::Satisfied => Self::Satisfied,
+            SatisfiesVerdict::Missing => Self::Missing,
+            SatisfiesVerdict::Duplicate => Self::Duplicate,
+            SatisfiesVerdict::Mismatch => Self::Mismatch,
+            SatisfiesVerdict::OutOfDate => Self::OutOfDate,
+            SatisfiesVerdict::CacheInvalid => Self::CacheInvalid,
+            SatisfiesVerdict::Constraint { .. } => Self::Constraint,
+        }
+    }
+}
+
+/// A requirement visited while checking whether an environment satisfies a set of requirements.
+#[derive(Debug, Clone)]
+pub struct SatisfiesTraceNode {
+    /// The requirement, after applying any overrides.
+    pub requirement: Requirement,
+    /// The index of the requirement that introduced this one, or `None` for direct requirements.
+    pub parent: Option<usize>,
+    /// Whether the installed environment satisfies the requirement.
+    pub verdict: SatisfiesVerdict,
+}
+
+/// The verdict for a single requirement in a [`SatisfiesTrace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SatisfiesVerdict {
+    /// The requirement is satisfied by the installed distribution.
+    Satisfied,
+    /// The package isn't installed.
+    Missing,
+    /// There are multiple installed distributions for the package.
+    Duplicate,
+    /// The installed distribution doesn't match the requirement.
+    Mismatch,
+    /// The installed distribution matches the requirement, but is out-of-date.
+    OutOfDate,
+    /// The installed distribution's cached build information couldn't be read.
+    CacheInvalid,
+    /// The installed distribution doesn't satisfy a constraint on the package.
+    Constraint {
+        /// The unsatisfied constraint.
+        constraint: Box<Requirement>,
+    },
+}
+
+impl SatisfiesVerdict {
+    fn from_satisfaction(satisfaction: RequirementSatisfaction) -> Self {
+        match satisfaction {
+            RequirementSatisfaction::Satisfied => Self::Satisfied,
+            RequirementSatisfaction::Mismatch => Self::Mismatch,
+            RequirementSatisfaction::OutOfDate => Self::OutOfDate,
+            RequirementSatisfaction::CacheInvalid => Self::CacheInvalid,
+        }
+    }
+}
+
+impl SitePackages {
+    /// Returns if the installed packages satisfy the given requirements.
+    pub fn satisfies_spec(
+        &self,
+        requirements: &[UnresolvedRequirementSpecification],
+        constraints: &[NameRequirementSpecification],
+        overrides: &[UnresolvedRequirementSpecification],
+        installation: InstallationStrategy,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+        config_settings: &ConfigSettings,
+        config_settings_package: &PackageConfigSettings,
+        extra_build_requires: &ExtraBuildRequires,
+        extra_build_variables: &ExtraBuildVariables,
+    ) -> Result<SatisfiesResult> {
+        let options = SatisfiesOptions {
+            markers: Some(markers),
+            tags: Some(tags),
+            ..SatisfiesOptions::default()
+        };
+        Ok(self
+            .satisfies_spec_with_options(
+                requirements,
+                constraints,
+                overrides,
+                installation,
+                config_settings,
+                config_settings_package,
+                extra_build_requires,
+                extra_build_variables,
+                &options,
+            )?
+            .result)
+    }
+
+    /// Like [`SitePackages::satisfies_spec`], but with the given [`SatisfiesOptions`].
+    ///
+    /// The returned [`SatisfiesOutcome::hashes`] contains the hashes declared for each package in
+    /// the satisfied closure, as the union of the hashes across all requirements, constraints, and
+    /// overrides for that package. Packages reached through recursion pick up the hashes of any
+    /// constraints or overrides that name them.
+    pub fn satisfies_spec_with_options(
+        &self,
+        requirements: &[UnresolvedRequirementSpecification],
+        constraints: &[NameRequirementSpecification],
+        overrides: &[UnresolvedRequirementSpecification],
+        installation: InstallationStrategy,
+        config_settings: &ConfigSettings,
+        config_settings_package: &PackageConfigSettings,
+        extra_build_requires: &ExtraBuildRequires,
+        extra_build_variables: &ExtraBuildVariables,
+        options: &SatisfiesOptions,
+    ) -> Result<SatisfiesOutcome> {
+        // First, map all unnamed requirements to named requirements.
+        let named_requirements = match self.named_requirements(requirements) {
+            Ok(named) => named,
+            Err(url) => return Ok(SatisfiesOutcome::from(SatisfiesResult::Unsatisfied(url))),
+        };
+
+        // Second, map all overrides to named requirements. We assume that all overrides are
+        // relevant.
+        let named_overrides = match self.named_requirements(overrides) {
+            Ok(named) => named,
+            Err(url) => return Ok(SatisfiesOutcome::from(SatisfiesResult::Unsatisfied(url))),
+        };
+
+        let mut outcome = self.satisfies_requirements_with_options(
+            named_requirements.iter().map(Cow::as_ref),
+            constraints.iter().map(|constraint| &constraint.requirement),
+            named_overrides.iter().map(Cow::as_ref),
+            installation,
+            config_settings,
+            config_settings_package,
+            extra_build_requires,
+            extra_build_variables,
+            options,
+        )?;
+        let SatisfiesResult::Fresh {
+            recursive_requirements,
+        } = &outcome.result
+        else {
+            return Ok(outcome);
+        };
+
+        // Collect the hashes for every package in the closure.
+        let closure = recursive_requirements
+            .iter()
+            .map(|requirement| &requirement.name)
+            .collect::<FxHashSet<_>>();
+        let mut hashes: BTreeMap<PackageName, BTreeSet<String>> = BTreeMap::new();
+        let specifications = requirements
+            .iter()
+            .chain(overrides)
+            .filter_map(|specification| {
+                let name = match &specification.requirement {
+                    UnresolvedRequirement::Named(requirement) => requirement.name.clone(),
+                    UnresolvedRequirement::Unnamed(requirement) => {
+                        let installed = self.get_urls(requirement.url.verbatim.raw());
+                        let [distribution] = installed.as_slice() else {
+                            return None;
+                        };
+                        distribution.name().clone()
+                    }
+                };
+                Some((name, &specification.hashes))
+            })
+            .chain(constraints.iter().map(|specification| {
+                (
+                    specification.requirement.name.clone(),
+                    &specification.hashes,
+                )
+            }));
+        for (name, digests) in specifications {
+            if digests.is_empty() || !closure.contains(&name) {
+                continue;
+            }
+            hashes
+                .entry(name)
+                .or_default()
+                .extend(digests.iter().cloned());
+        }
+        outcome.hashes = hashes;
+
+        Ok(outcome)
+    }
+
+    /// Map the given requirements to named requirements, resolving the names of unnamed
+    /// requirements from the installed distributions.
+    ///
+    /// Returns the URL of the first unnamed requirement that doesn't correspond to exactly one
+    /// installed distribution.
+    fn named_requirements<'r>(
+        &self,
+        requirements: &'r [UnresolvedRequirementSpecification],
+    ) -> Result<Vec<Cow<'r, Requirement>>, String> {
+        let mut named = Vec::with_capacity(requirements.len());
+        for requirement in requirements {
+            match &requirement.requirement {
+                UnresolvedRequirement::Named(requirement) => {
+                    named.push(Cow::Borrowed(requirement));
+                }
+                UnresolvedRequirement::Unnamed(requirement) => {
+                    match self.get_urls(requirement.url.verbatim.raw()).as_slice() {
+                        [distribution] => {
+                            let requirement = uv_pep508::Requirement {
+                                name: distribution.name().clone(),
+                                version_or_url: Some(VersionOrUrl::Url(requirement.url.clone())),
+                                marker: requirement.marker,
+                                extras: requirement.extras.clone(),
+                                origin: requirement.origin.clone(),
+                            };
+                            named.push(Cow::Owned(Requirement::from(requirement)));
+                        }
+                        _ => return Err(requirement.url.verbatim.raw().to_string()),
+                    }
+                }
+            }
+        }
+        Ok(named)
+    }
+
+    /// Like [`SitePackages::satisfies_spec`], but with resolved names for all requirements.
+    pub fn satisfies_requirements<'a>(
+        &self,
+        requirements: impl ExactSizeIterator<Item = &'a Requirement>,
+        constraints: impl Iterator<Item = &'a Requirement>,
+        overrides: impl Iterator<Item = &'a Requirement>,
+        installation: InstallationStrategy,
+        markers: &ResolverMarkerEnvironment,
+        tags: &Tags,
+        config_settings: &ConfigSettings,
+        config_settings_package: &PackageConfigSettings,
+        extra_build_requires: &ExtraBuildRequires,
+        extra_build_variables: &ExtraBuildVariables,
+    ) -> Result<SatisfiesResult> {
+        let options = SatisfiesOptions {
+            markers: Some(markers),
+            tags: Some(tags),
+            ..SatisfiesOptions::default()
+        };
+        Ok(self
+            .satisfies_requirements_with_options(
+                requirements,
+                constraints,
+                overrides,
+                installation,
+                config_settings,
+                config_settings_package,
+                extra_build_requires,
+                extra_build_variables,
+                &options,
+            )?
+            .result)
+    }
+
+    /// Like [`SitePackages::satisfies_requirements`], but with the given [`SatisfiesOptions`].
+    pub fn satisfies_requirements_with_options<'a>(
+        &self,
+        requirements: impl ExactSizeIterator<Item = &'a Requirement>,
+        constraints: impl Iterator<Item = &'a Requirement>,
+        overrides: impl Iterator<Item = &'a Requirement>,
+        installation: InstallationStrategy,
+        config_settings: &ConfigSettings,
+        config_settings_package: &PackageConfigSettings,
+        extra_build_requires: &ExtraBuildRequires,
+        extra_build_variables: &ExtraBuildVariables,
+        options: &SatisfiesOptions,
+    ) -> Result<SatisfiesOutcome> {
+        // Default to the marker environment and tags of the environment's own interpreter.
+        let markers = match options.markers {
+            Some(markers) => Cow::Borrowed(markers),
+            None => Cow::Owned(self.interpreter().resolver_marker_environment()),
+        };
+        let markers = markers.as_ref();
+        let tags = match options.tags {
+            Some(tags) => tags,
+            None => self.interpreter().tags()?,
+        };
+
+        // Collect the constraints and overrides by package name.
+        let constraints: FxHashMap<&PackageName, Vec<&Requirement>> =
+            constraints.fold(FxHashMap::default(), |mut constraints, constraint| {
+                constraints
+                    .entry(&constraint.name)
+                    .or_default()
+                    .push(constraint);
+                constraints
+            });
+        let overrides: FxHashMap<&PackageName, Vec<&Requirement>> =
+            overrides.fold(FxHashMap::default(), |mut overrides, r#override| {
+                overrides
+                    .entry(&r#override.name)
+                    .or_default()
+                    .push(r#override);
+                overrides
+            });
+
+        let mut trace = (options.trace || options.exhaustive).then(SatisfiesTrace::default);
+
+        // Replace any overridden direct requirements by their overrides, and drop any that don't
+        // apply to the current environment.
+        let requirements = requirements
+            .flat_map(|requirement| match overrides.get(&requirement.name) {
+                Some(r#overrides) => r#overrides.clone(),
+                None => vec![requirement],
+            })
+            .filter(|requirement| requirement.evaluate_markers(Some(markers), &[]))
+            .collect::<Vec<_>>();
+
+        // If requested, check that each direct requirement is installed at all before walking the
+        // dependency tree.
+        if options.prefilter {
+            if let Some(missing) = requirements
+                .iter()
+                .find(|requirement| !self.is_installed(&requirement.name))
+            {
+                return Ok(SatisfiesOutcome {
+                    result: SatisfiesResult::Unsatisfied(missing.to_string()),
+                    trace,
+                    hashes: BTreeMap::new(),
+                });
+            }
+        }
+
+        // Each entry in the queue tracks the trace node that introduced it (if tracing), along
+        // with its depth below the direct requirements. Since the queue is processed breadth-first,
+        // each requirement is first reached at its minimum depth.
+        let mut queue = VecDeque::with_capacity(requirements.len());
+        let mut seen = FxHashSet::with_capacity_and_hasher(requirements.len(), FxBuildHasher);
+
+        // If exhaustive, the first unsatisfied requirement.
+        let mut unsatisfied = None;
+
+        // Add the direct requirements to the queue.
+        for requirement in requirements {
+            if seen.insert(requirement.clone()) {
+                queue.push_back((Cow::Borrowed(requirement), None, 0));
+            }
+        }
+
+        // Verify that all non-editable requirements are met.
+        while let Some((requirement, parent, depth)) = queue.pop_front() {
+            if options.max_depth.is_some_and(|max_depth| depth > max_depth) {
+                return Ok(SatisfiesOutcome {
+                    result: SatisfiesResult::Indeterminate(requirement.to_string()),
+                    trace,
+                    hashes: BTreeMap::new(),
+                });
+            }
+
+            let name = &requirement.name;
+            let installed = self.get_packages(name);
+            let (verdict, distribution) = match installed.as_slice() {
+                [] => {
+                    // The package isn't installed.
+                    (SatisfiesVerdict::Missing, None)
+                }
+                [_] if self.has_invalid_version(name) => {
+                    // There's another distribution for the same package, with an invalid version.
+                    (SatisfiesVerdict::Duplicate, None)
+                }
+                [distribution] => {
+                    // Validate that the requirement is satisfied.
+                    let mut verdict = if requirement.evaluate_markers(Some(markers), &[]) {
+                        SatisfiesVerdict::from_satisfaction(
+                            RequirementSatisfaction::check(
+                                name,
+                                distribution,
+                                &requirement.source,
+                                installation,
+                                self.compatibility_policy(),
+                                markers,
+                                tags,
+                                config_settings,
+                                config_settings_package,
+                                extra_build_requires,
+                                extra_build_variables,
+                            )
+                            .offline(self.offline),
+                        )
+                    } else {
+                        SatisfiesVerdict::Satisfied
+                    };
+
+                    // Validate that the installed version satisfies the constraints.
+                    if verdict == SatisfiesVerdict::Satisfied {
+                        for constraint in constraints.get(name).into_iter().flatten() {
+                            if constraint.evaluate_markers(Some(markers), &[]) {
+                                match RequirementSatisfaction::check(
+                                    name,
+                                    distribution,
+                                    &constraint.source,
+                                    installation,
+                                    self.compatibility_policy(),
+                                    markers,
+                                    tags,
+                                    config_settings,
+                                    config_settings_package,
+                                    extra_build_requires,
+                                    extra_build_variables,
+                                )
+                                .offline(self.offline)
+                                {
+                                    RequirementSatisfaction::Mismatch
+                                    | RequirementSatisfaction::OutOfDate
+                                    | RequirementSatisfaction::CacheInvalid => {
+                                        verdict = SatisfiesVerdict::Constraint {
+                                            constraint: Box::new((*constraint).clone()),
+                                        };
+                                        break;
+                                    }
+                                    RequirementSatisfaction::Satisfied => {}
+                                }
+                            }
+                        }
+                    }
+
+                    (verdict, Some(*distribution))
+                }
+                _ => {
+                    // There are multiple installed distributions for the same package.
+                    (SatisfiesVerdict::Duplicate, None)
+                }
+            };
+
+            let node = trace
+                .as_mut()
+                .map(|trace| trace.push(requirement.as_ref().clone(), parent, verdict.clone()));
+
+            let (SatisfiesVerdict::Satisfied, Some(distribution)) = (verdict, distribution) else {
+                if options.exhaustive {
+                    unsatisfied.get_or_insert_with(|| requirement.to_string());
+                    continue;
+                }
+                return Ok(SatisfiesOutcome {
+                    result: SatisfiesResult::Unsatisfied(requirement.to_string()),
+                    trace,
+                    hashes: BTreeMap::new(),
+                });
+            };
+
+            // Recurse into the dependencies.
+            let metadata = distribution
+                .read_metadata()
+                .with_context(|| format!("Failed to read metadata for: {distribution}"))?;
+
+            // Add the dependencies to the queue.
+            for dependency in &metadata.requires_dist {
+                let dependency = Requirement::from(dependency.clone());
+                if let Some(r#overrides) = overrides.get(&dependency.name) {
+                    for dependency in r#overrides {
+                        if dependency.evaluate_markers(Some(markers), &requirement.extras) {
+                            if seen.insert((*dependency).clone()) {
+                                queue.push_back((Cow::Borrowed(*dependency), node, depth + 1));
+                            }
+                        }
+                    }
+                } else {
+                    if dependency.evaluate_markers(Some(markers), &requirement.extras) {
+                        if seen.insert(dependency.clone()) {
+                            queue.push_back((Cow::Owned(dependency), node, depth + 1));
+                        }
+                    }
+                }
+            }
+        }
+
+        let result = if let Some(unsatisfied) = unsatisfied {
+            SatisfiesResult::Unsatisfied(unsatisfied)
+        } else {
+            SatisfiesResult::Fresh {
+                recursive_requirements: seen,
+            }
+        };
+        Ok(SatisfiesOutcome {
+            result,
+            trace,
+            hashes: BTreeMap::new(),
+        })
+    }
+}
+
+/// Compute the [`CacheInfo`] for a local path, including the marker environment if the project
+/// requests it via `cache-keys`.
+///
+/// Whether the distribution was built in isolation is carried over from the `installed` cache
+/// info, since it describes the build, rather than the source.
+fn read_cache_info(
+    path: &Path,
+    markers: &ResolverMarkerEnvironment,
+    installed: &CacheInfo,
+) -> Result<CacheInfo, CacheInfoError> {
+    let markers = CacheMarkers::new(
+        markers.sys_platform(),
+        markers.platform_machine(),
+        markers.python_full_version().to_string(),
+    );
+    let cache_info = CacheInfo::from_path_with_markers(path, &markers)?;
+    Ok(match installed.build_isolation() {
+        Some(build_isolation) => cache_info.with_build_isolation(build_isolation),
+        None => cache_info,
+    })
+}
+
+/// Returns `true` if the installed commit matches the commit requested by a Git URL.
+///
+/// If the URL was resolved to a precise commit, the installed commit must match it exactly.
+/// Otherwise, a reference that looks like a full or abbreviated commit (e.g., `@a1b2c3d`) is
+/// resolved against the installed commit by prefix. Branches and tags (including those that don't
+/// look like a commit, but weren't disambiguated) can't be resolved without fetching the
+/// repository, so they're only considered up-to-date if the installed distribution doesn't record a
+/// commit either.
+fn git_commit_matches(requested: &GitUrl, installed_commit: Option<&str>) -> bool {
+    if let Some(precise) = requested.precise() {
+        return installed_commit == Some(precise.as_str());
+    }
+
+    let Some(installed_commit) = installed_commit else {
+        return true;
+    };
+
+    requested.reference().as_commit().is_some_and(|rev| {
+        installed_commit
+            .get(..rev.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(rev))
+    })
 }
 
 /// Determine the [`ConfigSettings`] for the given package name.
@@ -504,3 +1310,968 @@ fn generate_dist_compatibility_hint(wheel_tags: &ExpandedTags, tags: &Tags) -> O
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use fs_err as fs;
+    use tempfile::TempDir;
+
+    use uv_cache_info::CacheInfo;
+    use uv_distribution_types::{
+        ConfigSettings, ExtraBuildRequires, ExtraBuildVariables, InstalledDist,
+        PackageConfigSettings, RequirementSource,
+    };
+    use uv_git_types::{GitOid, GitReference, GitUrl};
+    use uv_normalize::PackageName;
+    use uv_pep440::VersionSpecifiers;
+    use uv_pep508::{MarkerEnvironment, MarkerEnvironmentBuilder, VerbatimUrl};
+    use uv_platform_tags::Tags;
+    use uv_pypi_types::ResolverMarkerEnvironment;
+    use uv_redacted::DisplaySafeUrl;
+
+    use super::{DefaultCompatibilityPolicy, RequirementSatisfaction};
+    use crate::InstallationStrategy;
+
+    const REPOSITORY: &str = "https://github.com/astral-test/uv-public-pypackage";
+    const COMMIT: &str = "b270df1a2fb5d012294e9aaf05e7e0bab1e6a389";
+    const OTHER_COMMIT: &str = "0dacfd662c64cb4ceb16e6cf65a157a8b715b979";
+
+    /// Install a Git distribution at the given commit, returning the [`InstalledDist`].
+    fn installed(root: &TempDir, commit: &str) -> Result<InstalledDist> {
+        let dist_info = root.path().join("uv_public_pypackage-0.1.0.dist-info");
+        fs::create_dir_all(&dist_info)?;
+        fs::write(
+            dist_info.join("direct_url.json"),
+            format!(
+                r#"{{"url": "{REPOSITORY}", "vcs_info": {{"vcs": "git", "commit_id": "{commit}"}}}}"#
+            ),
+        )?;
+        Ok(InstalledDist::try_from_path(&dist_info)?.expect("valid `.dist-info` directory"))
+    }
+
+    /// Return a representative marker environment for a Linux machine.
+    fn markers() -> Result<ResolverMarkerEnvironment> {
+        Ok(ResolverMarkerEnvironment::from(
+            MarkerEnvironment::try_from(MarkerEnvironmentBuilder {
+                implementation_name: "cpython",
+                implementation_version: "3.12.2",
+                os_name: "posix",
+                platform_machine: "x86_64",
+                platform_python_implementation: "CPython",
+                platform_release: "6.5.0-1016-azure",
+                platform_system: "Linux",
+                platform_version: "#16~22.04.1-Ubuntu SMP Fri Feb 16 15:42:02 UTC 2024",
+                python_full_version: "3.12.2",
+                python_version: "3.12",
+                sys_platform: "linux",
+            })?,
+        ))
+    }
+
+    /// Check whether a Git requirement is satisfied by the installed distribution.
+    fn check(distribution: &InstalledDist, git: GitUrl) -> Result<RequirementSatisfaction> {
+        let source = RequirementSource::Git {
+            url: VerbatimUrl::from_url(DisplaySafeUrl::parse(&format!(
+                "git+{REPOSITORY}@{}",
+                git.reference()
+            ))?),
+            git,
+            subdirectory: None,
+        };
+        Ok(RequirementSatisfaction::check(
+            &PackageName::from_str("uv-public-pypackage")?,
+            distribution,
+            &source,
+            InstallationStrategy::Permissive,
+            &DefaultCompatibilityPolicy,
+            &markers()?,
+            &Tags::new(vec![]),
+            &ConfigSettings::default(),
+            &PackageConfigSettings::default(),
+            &ExtraBuildRequires::default(),
+            &ExtraBuildVariables::default(),
+        ))
+    }
+
+    #[test]
+    fn local_directory_drift() -> Result<()> {
+        let root = TempDir::new()?;
+        let project = root.path().join("project");
+        fs::create_dir_all(&project)?;
+        fs::write(
+            project.join("pyproject.toml"),
+            "[project]\nname = \"foo\"\n",
+        )?;
+
+        // Install the project from its directory, recording the given cache info.
+        let install = |cache_info: &CacheInfo| -> Result<InstalledDist> {
+            let dist_info = root.path().join("foo-0.1.0.dist-info");
+            fs::create_dir_all(&dist_info)?;
+            let url = url::Url::from_directory_path(&project).unwrap();
+            fs::write(
+                dist_info.join("direct_url.json"),
+                format!(r#"{{"url": "{url}", "dir_info": {{}}}}"#),
+            )?;
+            fs::write(
+                dist_info.join("uv_cache.json"),
+                serde_json::to_string(cache_info)?,
+            )?;
+            Ok(InstalledDist::try_from_path(&dist_info)?.expect("valid `.dist-info` directory"))
+        };
+        let registry = RequirementSource::Registry {
+            specifier: VersionSpecifiers::from_str(">=0.1")?,
+            index: None,
+            conflict: None,
+        };
+        let directory = RequirementSource::Directory {
+            install_path: project.clone().into_boxed_path(),
+            editable: None,
+            r#virtual: None,
+            url: VerbatimUrl::from_absolute_path(&project)?,
+        };
+        let check = |distribution: &InstalledDist,
+                     source: &RequirementSource,
+                     installation: InstallationStrategy|
+         -> Result<RequirementSatisfaction> {
+            Ok(RequirementSatisfaction::check(
+                &PackageName::from_str("foo")?,
+                distribution,
+                source,
+                installation,
+                &DefaultCompatibilityPolicy,
+                &markers()?,
+                &Tags::new(vec![]),
+                &ConfigSettings::default(),
+                &PackageConfigSettings::default(),
+                &ExtraBuildRequires::default(),
+                &ExtraBuildVariables::default(),
+            ))
+        };
+
+        // If the source is unchanged, the installed distribution satisfies both a directory
+        // requirement and (in a permissive install) a registry requirement.
+        let cache_info = CacheInfo::from_directory(&project)?;
+        let fresh = install(&cache_info)?;
+        for source in [&directory, &registry] {
+            assert!(matches!(
+                check(&fresh, source, InstallationStrategy::Permissive)?,
+                RequirementSatisfaction::Satisfied
+            ));
+        }
+
+        // If the source changed after the installation, it's out-of-date for the directory
+        // requirement, but a registry requirement is still satisfied, rather than replacing the
+        // local installation with a build from the index.
+        fs::create_dir(project.join("src"))?;
+        let stale = install(&cache_info)?;
+        assert!(matches!(
+            check(&stale, &directory, InstallationStrategy::Permissive)?,
+            RequirementSatisfaction::OutOfDate
+        ));
+        assert!(matches!(
+            check(&stale, &registry, InstallationStrategy::Permissive)?,
+            RequirementSatisfaction::Satisfied
+        ));
+
+        // In a strict install, a directory install never satisfies a registry requirement.
+        let fresh = install(&CacheInfo::from_directory(&project)?)?;
+        assert!(matches!(
+            check(&fresh, &registry, InstallationStrategy::Strict)?,
+            RequirementSatisfaction::Mismatch
+        ));
+
+        // The build isolation recorded at install time doesn't make the source out-of-date.
+        let non_isolated =
+            install(&CacheInfo::from_directory(&project)?.with_build_isolation(false))?;
+        assert!(matches!(
+            check(&non_isolated, &directory, InstallationStrategy::Permissive)?,
+            RequirementSatisfaction::Satisfied
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn git_precise_commit() -> Result<()> {
+        let root = TempDir::new()?;
+        let distribution = installed(&root, COMMIT)?;
+        let repository = DisplaySafeUrl::parse(REPOSITORY)?;
+
+        let matching = GitUrl::from_commit(
+            repository.clone(),
+            GitReference::DefaultBranch,
+            GitOid::from_str(COMMIT)?,
+        )?;
+        assert!(matches!(
+            check(&distribution, matching)?,
+            RequirementSatisfaction::Satisfied
+        ));
+
+        let mismatched = GitUrl::from_commit(
+            repository,
+            GitReference::DefaultBranch,
+            GitOid::from_str(OTHER_COMMIT)?,
+        )?;
+        assert!(matches!(
+            check(&distribution, mismatched)?,
+            RequirementSatisfaction::OutOfDate
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn git_commit_reference() -> Result<()> {
+        let root = TempDir::new()?;
+        let distribution = installed(&root, COMMIT)?;
+        let repository = DisplaySafeUrl::parse(REPOSITORY)?;
+
+        // A full commit in the URL, without a resolved commit.
+        let full = GitUrl::from_reference(
+            repository.clone(),
+            GitReference::from_rev(COMMIT.to_string()),
+        )?;
+        assert!(matches!(
+            check(&distribution, full)?,
+            RequirementSatisfaction::Satisfied
+        ));
+
+        // An abbreviated commit in the URL.
+        let short = GitUrl::from_reference(
+            repository.clone(),
+            GitReference::from_rev(COMMIT[..8].to_string()),
+        )?;
+        assert!(matches!(
+            check(&distribution, short)?,
+            RequirementSatisfaction::Satisfied
+        ));
+
+        // A different commit in the URL.
+        let mismatched = GitUrl::from_reference(
+            repository.clone(),
+            GitReference::from_rev(OTHER_COMMIT[..8].to_string()),
+        )?;
+        assert!(matches!(
+            check(&distribution, mismatched)?,
+            RequirementSatisfaction::OutOfDate
+        ));
+
+        // A branch can't be resolved without fetching, so it's out-of-date.
+        let branch =
+            GitUrl::from_reference(repository.clone(), GitReference::Branch("main".to_string()))?;
+        assert!(matches!(
+            check(&distribution, branch)?,
+            RequirementSatisfaction::OutOfDate
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn git_ambiguous_reference() -> Result<()> {
+        let root = TempDir::new()?;
+        let repository = DisplaySafeUrl::parse(REPOSITORY)?;
+
+        // A reference that's too short to be an abbreviated commit is treated as a branch or tag,
+        // even if the installed commit starts with it.
+        let distribution = installed(&root, COMMIT)?;
+        let short = GitUrl::from_reference(
+            repository.clone(),
+            GitReference::BranchOrTagOrCommit(COMMIT[..4].to_string()),
+        )?;
+        assert!(matches!(
+            check(&distribution, short)?,
+            RequirementSatisfaction::OutOfDate
+        ));
+
+        // As is a reference that isn't hexadecimal.
+        let branch = GitUrl::from_reference(
+            repository.clone(),
+            GitReference::BranchOrTagOrCommit("release".to_string()),
+        )?;
+        assert!(matches!(
+            check(&distribution, branch)?,
+            RequirementSatisfaction::OutOfDate
+        ));
+
+        // A malformed installed commit is compared without slicing through a character.
+        let distribution = installed(&root, "b270dfé")?;
+        let commit =
+            GitUrl::from_reference(repository, GitReference::from_rev(COMMIT[..7].to_string()))?;
+        assert!(matches!(
+            check(&distribution, commit)?,
+            RequirementSatisfaction::OutOfDate
+        ));
+
+        Ok(())
+    }
+}
+
+/// Tests for the [`SitePackages`] methods, which rely on a mocked (Unix) interpreter.
+#[cfg(all(test, unix))]
+mod site_packages_tests {
+    use anyhow::Result;
+    use fs_err as fs;
+
+    use uv_distribution_types::{
+        ConfigSettings, ExtraBuildRequires, ExtraBuildVariables, NameRequirementSpecification,
+        PackageConfigSettings, Requirement, UnresolvedRequirement,
+        UnresolvedRequirementSpecification,
+    };
+    use uv_pep440::{Operator, Version, VersionSpecifiers};
+    use uv_pypi_types::ResolverMarkerEnvironment;
+
+    use super::{CompatibilityPolicy, SatisfiesOptions, SatisfiesResult, SatisfiesVerdict};
+    use crate::site_packages::tests::{MockEnvironment, name, requirement};
+    use crate::{InstallationStrategy, SitePackages};
+
+    #[test]
+    fn satisfies_for_interpreter() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+
+        let satisfied = [requirement("anyio>=4")];
+        let unsatisfied = [requirement("anyio>=4"), requirement("httpx")];
+        for requirements in [&satisfied[..], &unsatisfied[..]] {
+            let derived = site_packages
+                .satisfies_requirements_with_options(
+                    requirements.iter(),
+                    std::iter::empty(),
+                    std::iter::empty(),
+                    InstallationStrategy::Permissive,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                    &SatisfiesOptions::default(),
+                )?
+                .result;
+            let manual = site_packages.satisfies_requirements(
+                requirements.iter(),
+                std::iter::empty(),
+                std::iter::empty(),
+                InstallationStrategy::Permissive,
+                &interpreter.resolver_marker_environment(),
+                interpreter.tags()?,
+                &ConfigSettings::default(),
+                &PackageConfigSettings::default(),
+                &ExtraBuildRequires::default(),
+                &ExtraBuildVariables::default(),
+            )?;
+            match (derived, manual) {
+                (
+                    SatisfiesResult::Fresh {
+                        recursive_requirements: derived,
+                    },
+                    SatisfiesResult::Fresh {
+                        recursive_requirements: manual,
+                    },
+                ) => assert_eq!(derived, manual),
+                (SatisfiesResult::Unsatisfied(derived), SatisfiesResult::Unsatisfied(manual)) => {
+                    assert_eq!(derived, manual);
+                }
+                (derived, manual) => panic!("Expected {manual:?}, found {derived:?}"),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_compatibility_policy() -> Result<()> {
+        /// A policy under which only exact pins (e.g., `==1.0.0`) are satisfying.
+        #[derive(Debug)]
+        struct ExactPinPolicy;
+
+        impl CompatibilityPolicy for ExactPinPolicy {
+            fn is_compatible(&self, installed: &Version, specifiers: &VersionSpecifiers) -> bool {
+                !specifiers.is_empty()
+                    && specifiers
+                        .iter()
+                        .all(|specifier| *specifier.operator() == Operator::Equal)
+                    && specifiers.contains(installed)
+            }
+        }
+
+        let env = MockEnvironment::new()?;
+        env.install("foo", "1.0.0", &[])?;
+        let interpreter = env.interpreter()?;
+
+        let satisfied = |site_packages: &SitePackages, requirement: &str| -> Result<bool> {
+            Ok(matches!(
+                site_packages.satisfies_requirements(
+                    [self::requirement(requirement)].iter(),
+                    std::iter::empty(),
+                    std::iter::empty(),
+                    InstallationStrategy::Permissive,
+                    &interpreter.resolver_marker_environment(),
+                    interpreter.tags()?,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                )?,
+                SatisfiesResult::Fresh { .. }
+            ))
+        };
+
+        // By default, any version contained in the specifiers is satisfying.
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        assert!(satisfied(&site_packages, "foo>=0.9")?);
+        assert!(satisfied(&site_packages, "foo==1.0.0")?);
+        assert!(!satisfied(&site_packages, "foo>=2")?);
+
+        // Under a strict policy, only exact pins are satisfying.
+        let site_packages = site_packages.with_compatibility_policy(ExactPinPolicy);
+        assert!(!satisfied(&site_packages, "foo>=0.9")?);
+        assert!(!satisfied(&site_packages, "foo")?);
+        assert!(satisfied(&site_packages, "foo==1.0.0")?);
+        assert!(!satisfied(&site_packages, "foo==2.0.0")?);
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_offline() -> Result<()> {
+        // A package installed from a Git repository, at a known commit.
+        let env = MockEnvironment::new()?;
+        env.install("foo", "1.0.0", &[])?;
+        fs::write(
+            env.site_packages()
+                .join("foo-1.0.0.dist-info")
+                .join("direct_url.json"),
+            r#"{"url": "https://github.com/astral-test/foo", "vcs_info": {"vcs": "git", "commit_id": "b270df1a2fb5d012294e9aaf05e7e0bab1e6a389"}}"#,
+        )?;
+
+        let interpreter = env.interpreter()?;
+        let satisfies = |requirement: &str, offline: bool| {
+            SitePackages::from_interpreter(&interpreter)?
+                .with_offline(offline)
+                .satisfies_requirements(
+                    [self::requirement(requirement)].iter(),
+                    std::iter::empty(),
+                    std::iter::empty(),
+                    InstallationStrategy::Permissive,
+                    &interpreter.resolver_marker_environment(),
+                    interpreter.tags()?,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                )
+        };
+
+        // A branch can't be resolved to a commit without fetching, so the package is out-of-date.
+        // Online, it must be reinstalled; offline, it's accepted as-is.
+        let branch = "foo @ git+https://github.com/astral-test/foo@main";
+        assert!(matches!(
+            satisfies(branch, false)?,
+            SatisfiesResult::Unsatisfied(_)
+        ));
+        assert!(matches!(
+            satisfies(branch, true)?,
+            SatisfiesResult::Fresh { .. }
+        ));
+
+        // A package installed from a different repository is unsatisfied either way.
+        let moved = "foo @ git+https://github.com/astral-test/bar@main";
+        for offline in [false, true] {
+            assert!(matches!(
+                satisfies(moved, offline)?,
+                SatisfiesResult::Unsatisfied(_)
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_max_depth() -> Result<()> {
+        // A chain of five packages, four levels deep.
+        let env = MockEnvironment::new()?;
+        env.install("a", "1.0.0", &["b"])?;
+        env.install("b", "1.0.0", &["c"])?;
+        env.install("c", "1.0.0", &["d"])?;
+        env.install("d", "1.0.0", &["e"])?;
+        env.install("e", "1.0.0", &[])?;
+
+        // A package that reaches `d` through both `x` and the chain.
+        env.install("f", "1.0.0", &["x", "b"])?;
+        env.install("x", "1.0.0", &["d"])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let satisfies = |requirement: &str, max_depth: usize| {
+            site_packages
+                .satisfies_requirements_with_options(
+                    [self::requirement(requirement)].iter(),
+                    std::iter::empty(),
+                    std::iter::empty(),
+                    InstallationStrategy::Permissive,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                    &SatisfiesOptions {
+                        max_depth: Some(max_depth),
+                        ..SatisfiesOptions::default()
+                    },
+                )
+                .map(|outcome| outcome.result)
+        };
+        match satisfies("a", 2)? {
+            SatisfiesResult::Indeterminate(requirement) => assert_eq!(requirement, "d"),
+            result => panic!("Expected an indeterminate result, found {result:?}"),
+        }
+        assert!(matches!(satisfies("a", 4)?, SatisfiesResult::Fresh { .. }));
+
+        // A package that's reachable through both a deep and a shallow path is checked at its
+        // minimum depth, regardless of the order in which the paths are visited: here, `d` is at
+        // depth two (via `x`) rather than three (via `b` and `c`).
+        assert!(matches!(satisfies("f", 3)?, SatisfiesResult::Fresh { .. }));
+        match satisfies("f", 2)? {
+            SatisfiesResult::Indeterminate(requirement) => assert_eq!(requirement, "e"),
+            result => panic!("Expected an indeterminate result, found {result:?}"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_unused_constraints() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        env.install("requests", "2.31.0", &[])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let check = |requirements: &[Requirement], constraints: &[Requirement]| {
+            site_packages
+                .satisfies_requirements(
+                    requirements.iter(),
+                    constraints.iter(),
+                    std::iter::empty(),
+                    InstallationStrategy::Permissive,
+                    &interpreter.resolver_marker_environment(),
+                    interpreter.tags()?,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                )
+                .map(|result| {
+                    (
+                        matches!(result, SatisfiesResult::Fresh { .. }),
+                        result
+                            .unused_constraints(constraints)
+                            .into_iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>(),
+                    )
+                })
+        };
+
+        // Constraints on transitive dependencies are used, even if the package is installed but
+        // outside the closure.
+        let constraints = [
+            requirement("idna>=3"),
+            requirement("requests<3"),
+            requirement("urllib3<2"),
+        ];
+        assert_eq!(
+            check(&[requirement("anyio")], &constraints)?,
+            (
+                true,
+                vec!["requests<3".to_string(), "urllib3<2".to_string()]
+            )
+        );
+
+        // If the requirements aren't satisfied, the closure is incomplete.
+        assert_eq!(
+            check(&[requirement("anyio"), requirement("trio")], &constraints)?,
+            (false, vec![])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_multi() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install(
+            "anyio",
+            "4.3.0",
+            &["idna>=2.8", "colorama ; sys_platform == 'win32'"],
+        )?;
+        env.install("idna", "3.6", &[])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let linux = ResolverMarkerEnvironment::from(
+            interpreter.markers().clone().with_sys_platform("linux"),
+        );
+        let windows = ResolverMarkerEnvironment::from(
+            interpreter.markers().clone().with_sys_platform("win32"),
+        );
+        let check = |requirements: &[Requirement]| {
+            [&linux, &windows]
+                .into_iter()
+                .map(|markers| {
+                    let outcome = site_packages.satisfies_requirements_with_options(
+                        requirements.iter(),
+                        std::iter::empty(),
+                        std::iter::empty(),
+                        InstallationStrategy::Permissive,
+                        &ConfigSettings::default(),
+                        &PackageConfigSettings::default(),
+                        &ExtraBuildRequires::default(),
+                        &ExtraBuildVariables::default(),
+                        &SatisfiesOptions {
+                            markers: Some(markers),
+                            ..SatisfiesOptions::default()
+                        },
+                    )?;
+                    Ok((
+                        markers.sys_platform().to_string(),
+                        matches!(outcome.result, SatisfiesResult::Fresh { .. }),
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+
+        // `colorama` is only required on Windows, where it isn't installed.
+        assert_eq!(
+            check(&[requirement("anyio")])?,
+            vec![("linux".to_string(), true), ("win32".to_string(), false)]
+        );
+
+        // Direct requirements are evaluated against each environment, too.
+        assert_eq!(
+            check(&[
+                requirement("idna"),
+                requirement("trio ; sys_platform == 'linux'")
+            ])?,
+            vec![("linux".to_string(), false), ("win32".to_string(), true)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_prefilter() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        env.install("httpx", "0.27.0", &["anyio", "certifi"])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let check = |requirements: &[Requirement], overrides: &[Requirement], prefilter: bool| {
+            site_packages
+                .satisfies_requirements_with_options(
+                    requirements.iter(),
+                    std::iter::empty(),
+                    overrides.iter(),
+                    InstallationStrategy::Permissive,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                    &SatisfiesOptions {
+                        prefilter,
+                        ..SatisfiesOptions::default()
+                    },
+                )
+                .map(|outcome| outcome.result)
+        };
+        let is_fresh = |result: &SatisfiesResult| matches!(result, SatisfiesResult::Fresh { .. });
+
+        let cases = [
+            // Satisfied, including transitive dependencies.
+            (vec![requirement("anyio"), requirement("idna>=3")], vec![]),
+            // A direct requirement that isn't installed.
+            (vec![requirement("anyio"), requirement("trio")], vec![]),
+            // A transitive dependency that isn't installed, which the pre-pass can't detect.
+            (vec![requirement("httpx")], vec![]),
+            // An installed package that doesn't satisfy the requirement.
+            (vec![requirement("idna<3")], vec![]),
+            // A requirement that doesn't apply to the current environment.
+            (
+                vec![
+                    requirement("anyio"),
+                    requirement("trio ; sys_platform == 'never'"),
+                ],
+                vec![],
+            ),
+            // A missing requirement that's overridden by an installed package, and vice versa.
+            (
+                vec![requirement("anyio"), requirement("idna")],
+                vec![requirement("idna==3.6")],
+            ),
+            (
+                vec![requirement("anyio")],
+                vec![
+                    requirement("anyio ; sys_platform == 'never'"),
+                    requirement("trio"),
+                ],
+            ),
+        ];
+        for (requirements, overrides) in &cases {
+            let full = check(requirements, overrides, false)?;
+            let prefiltered = check(requirements, overrides, true)?;
+            assert_eq!(is_fresh(&full), is_fresh(&prefiltered), "{requirements:?}");
+            if let (
+                SatisfiesResult::Fresh {
+                    recursive_requirements: full,
+                },
+                SatisfiesResult::Fresh {
+                    recursive_requirements: prefiltered,
+                },
+            ) = (&full, &prefiltered)
+            {
+                assert_eq!(full, prefiltered);
+            }
+        }
+
+        // The missing direct requirement is reported without walking the tree.
+        let SatisfiesResult::Unsatisfied(missing) = check(
+            &[
+                requirement("trio"),
+                requirement("anyio"),
+                requirement("httpx"),
+            ],
+            &[],
+            true,
+        )?
+        else {
+            panic!("expected the requirements to be unsatisfied");
+        };
+        assert_eq!(missing, "trio");
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_report() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("idna", "2.0", &[])?;
+        env.install("httpx", "0.27.0", &[])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let report = |requirements: &[Requirement], constraints: &[Requirement]| {
+            site_packages
+                .satisfies_requirements_with_options(
+                    requirements.iter(),
+                    constraints.iter(),
+                    std::iter::empty(),
+                    InstallationStrategy::Permissive,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                    &SatisfiesOptions {
+                        exhaustive: true,
+                        ..SatisfiesOptions::default()
+                    },
+                )
+                .map(|outcome| outcome.report().expect("exhaustive outcomes are traced"))
+        };
+
+        // Every unsatisfied requirement is reported, not just the first.
+        let unsatisfied = report(
+            &[requirement("anyio"), requirement("httpx")],
+            &[requirement("httpx<0.27")],
+        )?;
+        assert_eq!(
+            serde_json::to_value(&unsatisfied)?,
+            serde_json::json!({
+                "satisfied": false,
+                "requirements": [
+                    {
+                        "name": "anyio",
+                        "requirement": "anyio",
+                        "required-by": null,
+                        "status": "satisfied",
+                    },
+                    {
+                        "name": "httpx",
+                        "requirement": "httpx",
+                        "required-by": null,
+                        "status": "constraint",
+                        "constraint": "httpx<0.27",
+                    },
+                    {
+                        "name": "idna",
+                        "requirement": "idna>=2.8",
+                        "required-by": "anyio",
+                        "status": "mismatch",
+                    },
+                    {
+                        "name": "sniffio",
+                        "requirement": "sniffio>=1.1",
+                        "required-by": "anyio",
+                        "status": "missing",
+                    },
+                ],
+            })
+        );
+
+        let satisfied = report(&[requirement("httpx")], &[])?;
+        assert_eq!(
+            serde_json::to_value(&satisfied)?,
+            serde_json::json!({
+                "satisfied": true,
+                "requirements": [
+                    {
+                        "name": "httpx",
+                        "requirement": "httpx",
+                        "required-by": null,
+                        "status": "satisfied",
+                    },
+                ],
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_with_trace() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        env.install("httpx", "0.27.0", &["certifi"])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let trace = |requirements: &[Requirement], constraints: &[Requirement]| {
+            site_packages
+                .satisfies_requirements_with_options(
+                    requirements.iter(),
+                    constraints.iter(),
+                    std::iter::empty(),
+                    InstallationStrategy::Permissive,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                    &SatisfiesOptions {
+                        trace: true,
+                        ..SatisfiesOptions::default()
+                    },
+                )
+                .map(|outcome| (outcome.result, outcome.trace.expect("traced")))
+        };
+
+        // On success, the trace covers every requirement visited.
+        let (result, trace_satisfied) = trace(&[requirement("anyio>=4")], &[])?;
+        assert!(matches!(result, SatisfiesResult::Fresh { .. }));
+        assert_eq!(trace_satisfied.nodes().len(), 3);
+        assert!(trace_satisfied.failure().is_none());
+        let roots = trace_satisfied.roots().collect::<Vec<_>>();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].1.requirement.name, name("anyio"));
+        let mut children = trace_satisfied
+            .children(roots[0].0)
+            .map(|(_, node)| node.requirement.name.clone())
+            .collect::<Vec<_>>();
+        children.sort();
+        assert_eq!(children, vec![name("idna"), name("sniffio")]);
+
+        // On failure, the trace ends at the first unsatisfied requirement.
+        let (result, trace_missing) = trace(&[requirement("httpx")], &[])?;
+        assert!(matches!(result, SatisfiesResult::Unsatisfied(_)));
+        let failure = trace_missing.failure().unwrap();
+        assert_eq!(failure.requirement.name, name("certifi"));
+        assert_eq!(failure.verdict, SatisfiesVerdict::Missing);
+        let parent = &trace_missing.nodes()[failure.parent.unwrap()];
+        assert_eq!(parent.requirement.name, name("httpx"));
+        assert_eq!(parent.verdict, SatisfiesVerdict::Satisfied);
+
+        // Constraint violations are attributed to the constrained requirement.
+        let (result, trace_constrained) =
+            trace(&[requirement("anyio>=4")], &[requirement("idna<3")])?;
+        assert!(matches!(result, SatisfiesResult::Unsatisfied(_)));
+        let failure = trace_constrained.failure().unwrap();
+        assert_eq!(failure.requirement.name, name("idna"));
+        assert_eq!(
+            failure.verdict,
+            SatisfiesVerdict::Constraint {
+                constraint: Box::new(requirement("idna<3"))
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn satisfies_spec_with_options() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let specification =
+            |requirement: &str, hashes: &[&str]| UnresolvedRequirementSpecification {
+                requirement: UnresolvedRequirement::Named(self::requirement(requirement)),
+                hashes: hashes.iter().map(ToString::to_string).collect(),
+            };
+        let constraint = |requirement: &str, hashes: &[&str]| NameRequirementSpecification {
+            requirement: self::requirement(requirement),
+            hashes: hashes.iter().map(ToString::to_string).collect(),
+        };
+        let satisfies = |requirements: &[UnresolvedRequirementSpecification]| {
+            site_packages
+                .satisfies_spec_with_options(
+                    requirements,
+                    &[
+                        constraint("idna==3.6", &["sha256:bbb"]),
+                        constraint("httpx", &["sha256:ccc"]),
+                    ],
+                    &[specification("sniffio>=1", &["sha256:ddd"])],
+                    InstallationStrategy::Permissive,
+                    &ConfigSettings::default(),
+                    &PackageConfigSettings::default(),
+                    &ExtraBuildRequires::default(),
+                    &ExtraBuildVariables::default(),
+                    &SatisfiesOptions::default(),
+                )
+                .map(|outcome| (outcome.result, outcome.hashes))
+        };
+
+        // Hashes propagate to transitive dependencies via constraints and overrides, but not to
+        // packages outside the closure.
+        let (result, hashes) = satisfies(&[
+            specification("anyio>=4", &["sha256:aaa"]),
+            specification("anyio", &["sha256:eee"]),
+        ])?;
+        assert!(matches!(result, SatisfiesResult::Fresh { .. }));
+        assert_eq!(
+            hashes
+                .iter()
+                .map(|(name, hashes)| format!(
+                    "{name}: {}",
+                    hashes.iter().cloned().collect::<Vec<_>>().join(", ")
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                "anyio: sha256:aaa, sha256:eee",
+                "idna: sha256:bbb",
+                "sniffio: sha256:ddd",
+            ]
+        );
+
+        // If the requirements are unsatisfied, no hashes are returned.
+        let (result, hashes) = satisfies(&[specification("httpx", &["sha256:ccc"])])?;
+        assert!(matches!(result, SatisfiesResult::Unsatisfied(_)));
+        assert!(hashes.is_empty());
+        Ok(())
+    }
+}
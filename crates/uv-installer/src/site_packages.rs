@@ -1,29 +1,40 @@
 use std::borrow::Cow;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufReader;
 use std::iter::Flatten;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{Context, Result};
 use fs_err as fs;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use rustc_hash::{FxBuildHasher, FxHashMap, FxHashSet};
+use tracing::{debug, warn};
+use url::Url;
 
+use uv_cache_info::Timestamp;
 use uv_distribution_types::{
-    ConfigSettings, Diagnostic, ExtraBuildRequires, ExtraBuildVariables, InstalledDist,
-    InstalledDistKind, Name, NameRequirementSpecification, PackageConfigSettings, Requirement,
-    UnresolvedRequirement, UnresolvedRequirementSpecification,
+    ConfigSettings, ExtraBuildRequires, ExtraBuildVariables, InstalledDirectUrlDist, InstalledDist,
+    InstalledDistKind, Name, PackageConfigSettings, Requirement, RequirementSource, Resolution,
+    ResolvedDist,
+};
+use uv_fs::{Simplified, normalize_path};
+use uv_install_wheel::{LibKind, read_record_file, script_names, script_path};
+use uv_normalize::{ExtraName, PackageName};
+use uv_pep440::{Version, VersionSpecifier, VersionSpecifiers};
+use uv_pep508::{MarkerTree, VerbatimUrl, VersionOrUrl};
+use uv_pypi_types::{
+    DirectUrl, Metadata23, ParsedUrl, ResolverMarkerEnvironment, VerbatimParsedUrl,
 };
-use uv_fs::Simplified;
-use uv_normalize::PackageName;
-use uv_pep440::{Version, VersionSpecifiers};
-use uv_pep508::VersionOrUrl;
-use uv_platform_tags::Tags;
-use uv_pypi_types::{ResolverMarkerEnvironment, VerbatimParsedUrl};
 use uv_python::{Interpreter, PythonEnvironment};
 use uv_redacted::DisplaySafeUrl;
-use uv_types::InstalledPackagesProvider;
+use uv_types::{FilteredProvider, InstalledPackagesProvider};
 use uv_warnings::warn_user;
 
-use crate::satisfies::RequirementSatisfaction;
+use crate::dependency_graph::DependencyGraph;
+use crate::satisfies::{CompatibilityPolicy, DefaultCompatibilityPolicy, RequirementSatisfaction};
 
 /// An index over the packages installed in an environment.
 ///
@@ -34,13 +45,62 @@ pub struct SitePackages {
     /// The vector of all installed distributions. The `by_name` and `by_url` indices index into
     /// this vector. The vector may contain `None` values, which represent distributions that were
     /// removed from the virtual environment.
-    distributions: Vec<Option<InstalledDist>>,
+    pub(crate) distributions: Vec<Option<InstalledDist>>,
     /// The installed distributions, keyed by name. Although the Python runtime does not support it,
     /// it is possible to have multiple distributions with the same name to be present in the
     /// virtual environment, which we handle gracefully.
-    by_name: FxHashMap<PackageName, Vec<usize>>,
+    pub(crate) by_name: FxHashMap<PackageName, Vec<usize>>,
     /// The installed editable distributions, keyed by URL.
     by_url: FxHashMap<DisplaySafeUrl, Vec<usize>>,
+    /// The `site-packages` directories that were scanned, in order, along with their timestamps
+    /// at the time of the scan (or `None`, if the directory didn't exist).
+    sites: Vec<(PathBuf, Option<Timestamp>)>,
+    /// The `site-packages` directories that were intentionally skipped, rather than scanned, as
+    /// via [`SitePackages::from_interpreter_filtered`].
+    skipped: Vec<PathBuf>,
+    /// The policy used to decide whether an installed version satisfies a requirement.
+    compatibility_policy: Arc<dyn CompatibilityPolicy>,
+    /// Whether [`SitePackages::diagnostics`] verifies the files installed by each distribution
+    /// (e.g., those listed in its `RECORD`, and its scripts).
+    pub(crate) file_checks: bool,
+    /// Whether out-of-date distributions are considered satisfied, as when operating offline.
+    pub(crate) offline: bool,
+    /// The `.dist-info` directories whose version couldn't be parsed.
+    ///
+    /// These can't be indexed (or uninstalled), but any requirement on a package that also has a
+    /// valid installed distribution is considered unsatisfied, and they're reported by
+    /// [`SitePackages::diagnostics`].
+    pub(crate) invalid_versions: Vec<PathBuf>,
+}
+
+/// The changes applied to a [`SitePackages`] index by [`SitePackages::reconcile`].
+#[derive(Debug, Default, Clone)]
+pub struct ReconcileReport {
+    /// The distributions that appeared on disk since the last scan.
+    pub added: Vec<InstalledDist>,
+    /// The distributions that disappeared from disk since the last scan.
+    pub removed: Vec<InstalledDist>,
+}
+
+impl ReconcileReport {
+    /// Returns `true` if the index was unchanged.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The error returned when a scan of the environment is cancelled via its cancellation token.
+#[derive(Debug, thiserror::Error)]
+#[error("The scan of the environment was cancelled")]
+pub struct Cancelled;
+
+/// Return a [`Cancelled`] error if the (optional) cancellation token has been set.
+pub(crate) fn check_cancelled(cancel: Option<&AtomicBool>) -> Result<(), Cancelled> {
+    if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+        Err(Cancelled)
+    } else {
+        Ok(())
+    }
 }
 
 impl SitePackages {
@@ -50,97 +110,332 @@ impl SitePackages {
     }
 
     /// Build an index of installed packages from the given Python executable.
+    ///
+    /// Any of the interpreter's `site-packages` directories that don't exist (e.g., a `purelib`
+    /// directory in an environment into which only extension modules were installed) are
+    /// skipped, and the remaining directories are still indexed. Any other failure to read a
+    /// directory is an error.
     pub fn from_interpreter(interpreter: &Interpreter) -> Result<Self> {
+        Self::from_site_packages(interpreter, interpreter.site_packages(), None)
+    }
+
+    /// Like [`SitePackages::from_interpreter`], but checks the cancellation token between
+    /// directories and distributions, returning a [`Cancelled`] error once it's set.
+    ///
+    /// This allows interactive tools to abort a slow scan of a large environment.
+    pub fn from_interpreter_cancellable(
+        interpreter: &Interpreter,
+        cancel: &AtomicBool,
+    ) -> Result<Self> {
+        Self::from_site_packages(interpreter, interpreter.site_packages(), Some(cancel))
+    }
+
+    /// Build an index of installed packages from the given Python executable, skipping any of the
+    /// interpreter's `site-packages` directories that appear in `skip`.
+    ///
+    /// This is useful for auditing a virtual environment in isolation, e.g., omitting a shared,
+    /// read-only system `site-packages` directory. Paths are compared after lexical normalization.
+    /// The skipped directories are reported by [`SitePackages::skipped_sites`].
+    pub fn from_interpreter_filtered(interpreter: &Interpreter, skip: &[PathBuf]) -> Result<Self> {
+        let skip = skip
+            .iter()
+            .map(|path| normalize_path(path))
+            .collect::<FxHashSet<_>>();
+        let (skipped, scanned): (Vec<_>, Vec<_>) = interpreter
+            .site_packages()
+            .partition(|site_packages| skip.contains(&normalize_path(site_packages)));
+        let mut site_packages = Self::from_site_packages(interpreter, scanned, None)?;
+        site_packages.skipped = skipped.into_iter().map(Cow::into_owned).collect();
+        Ok(site_packages)
+    }
+
+    /// Build an index of the packages installed in the given directories, rather than in the
+    /// interpreter's own `site-packages` directories.
+    ///
+    /// The directories are scanned in order, such that earlier directories take precedence, as on
+    /// `sys.path`. The interpreter is retained for diagnostics and satisfaction checks.
+    pub fn from_paths(interpreter: &Interpreter, paths: &[PathBuf]) -> Result<Self> {
+        Self::from_site_packages(interpreter, paths, None)
+    }
+
+    /// Build an index of the packages importable with the given `PYTHONPATH`, i.e., those in the
+    /// directories listed in `pythonpath` (separated as on the current platform), followed by
+    /// those in the interpreter's own `site-packages` directories.
+    ///
+    /// As at runtime, earlier directories take precedence. Empty entries (which Python treats as
+    /// the current directory) and repeated entries are skipped, as are directories that don't
+    /// exist.
+    pub fn from_pythonpath(interpreter: &Interpreter, pythonpath: &str) -> Result<Self> {
+        let mut seen = FxHashSet::default();
+        let paths = std::env::split_paths(pythonpath)
+            .filter(|path| !path.as_os_str().is_empty())
+            .chain(interpreter.site_packages().map(Cow::into_owned))
+            .filter(|path| seen.insert(normalize_path(path).into_owned()))
+            .collect::<Vec<_>>();
+        Self::from_site_packages(interpreter, paths, None)
+    }
+
+    /// Build an index of the packages installed in the given `site-packages` directories.
+    ///
+    /// Directories that don't exist are skipped; any other failure to read a directory is an
+    /// error. If a cancellation token is provided, it's checked between directories and
+    /// distributions.
+    fn from_site_packages(
+        interpreter: &Interpreter,
+        site_packages: impl IntoIterator<Item = impl AsRef<Path>>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<Self> {
         let mut distributions: Vec<Option<InstalledDist>> = Vec::new();
         let mut by_name = FxHashMap::default();
         let mut by_url = FxHashMap::default();
+        let mut sites = Vec::new();
+        let mut invalid_versions = Vec::new();
+
+        for site_packages in site_packages {
+            check_cancelled(cancel)?;
+
+            // Record the directory's timestamp before reading it, such that any concurrent changes
+            // are picked up by a subsequent reconcile.
+            let site_packages = site_packages.as_ref();
+            sites.push((
+                site_packages.to_path_buf(),
+                Timestamp::from_path(site_packages).ok(),
+            ));
 
-        for site_packages in interpreter.site_packages() {
             // Read the site-packages directory.
-            let site_packages = match fs::read_dir(site_packages.as_ref()) {
-                Ok(read_dir) => {
-                    // Collect sorted directory paths; `read_dir` is not stable across platforms
-                    let dist_likes: BTreeSet<_> = read_dir
-                        .filter_map(|read_dir| match read_dir {
-                            Ok(entry) => match entry.file_type() {
-                                Ok(file_type) => (file_type.is_dir()
-                                    || entry
-                                        .path()
-                                        .extension()
-                                        .is_some_and(|ext| ext == "egg-link" || ext == "egg-info"))
-                                .then_some(Ok(entry.path())),
-                                Err(err) => Some(Err(err)),
-                            },
-                            Err(err) => Some(Err(err)),
-                        })
-                        .collect::<Result<_, std::io::Error>>()
-                        .with_context(|| {
-                            format!(
-                                "Failed to read site-packages directory contents: {}",
-                                site_packages.user_display()
-                            )
-                        })?;
-                    dist_likes
-                }
-                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                    return Ok(Self {
-                        interpreter: interpreter.clone(),
-                        distributions,
-                        by_name,
-                        by_url,
-                    });
-                }
-                Err(err) => return Err(err).context("Failed to read site-packages directory"),
+            let Some(dist_likes) = read_dist_likes(site_packages)? else {
+                continue;
             };
 
             // Index all installed packages by name.
-            for path in site_packages {
-                let dist_info = match InstalledDist::try_from_path(&path) {
-                    Ok(Some(dist_info)) => dist_info,
-                    Ok(None) => continue,
-                    Err(_)
-                        if path.file_name().is_some_and(|name| {
-                            name.to_str().is_some_and(|name| name.starts_with('~'))
-                        }) =>
-                    {
-                        warn_user!(
-                            "Ignoring dangling temporary directory: `{}`",
-                            path.simplified_display().cyan()
-                        );
-                        continue;
-                    }
-                    Err(err) => {
-                        return Err(err).context(format!(
-                            "Failed to read metadata from: `{}`",
-                            path.simplified_display()
-                        ));
-                    }
+            for path in dist_likes {
+                check_cancelled(cancel)?;
+
+                if is_invalid_version(&path) {
+                    invalid_versions.push(path);
+                    continue;
+                }
+                let Some(dist_info) = read_distribution(&path)? else {
+                    continue;
+                };
+                let dist_info = resolve_relative_editable(dist_info, interpreter.sys_prefix());
+                index_distribution(&mut distributions, &mut by_name, &mut by_url, dist_info);
+            }
+        }
+
+        Ok(Self {
+            interpreter: interpreter.clone(),
+            distributions,
+            by_name,
+            by_url,
+            sites,
+            skipped: Vec::new(),
+            compatibility_policy: Arc::new(DefaultCompatibilityPolicy),
+            file_checks: false,
+            offline: false,
+            invalid_versions,
+        })
+    }
+
+    /// Bring the index up-to-date with any changes made to the environment since it was built
+    /// (e.g., by an external `pip install`), returning the distributions that were added and
+    /// removed.
+    ///
+    /// Only the `site-packages` directories whose timestamps have changed are re-read, and only
+    /// the metadata of newly-appeared distributions is read, which is far cheaper than rebuilding
+    /// the index. Since the timestamp of a directory only changes when an entry is added to or
+    /// removed from it, in-place modifications to an installed distribution aren't detected. Any
+    /// shadowing applied by [`SitePackages::overlay`] isn't re-applied to added distributions.
+    pub fn reconcile(&mut self) -> Result<ReconcileReport> {
+        let mut report = ReconcileReport::default();
+        let mut added = Vec::new();
+
+        for (site, timestamp) in &mut self.sites {
+            let current = Timestamp::from_path(&*site).ok();
+            if current == *timestamp {
+                continue;
+            }
+            *timestamp = current;
+
+            let dist_likes = read_dist_likes(site)?.unwrap_or_default();
+
+            // Forget any distributions with invalid versions that no longer exist.
+            self.invalid_versions
+                .retain(|path| path.parent() != Some(site.as_path()) || dist_likes.contains(path));
+
+            // Remove any distributions that no longer exist.
+            for index in 0..self.distributions.len() {
+                let Some(dist) = &self.distributions[index] else {
+                    continue;
                 };
+                let path = metadata_path(dist);
+                if path.parent() != Some(site.as_path()) || dist_likes.contains(path) {
+                    continue;
+                }
+                if let Some(dist) = self.distributions[index].take() {
+                    unindex_distribution(&mut self.by_name, &mut self.by_url, &dist, index);
+                    report.removed.push(dist);
+                }
+            }
 
-                let idx = distributions.len();
+            // Add any distributions that appeared.
+            let existing = self
+                .distributions
+                .iter()
+                .flatten()
+                .map(|dist| metadata_path(dist).to_path_buf())
+                .collect::<FxHashSet<_>>();
+            for path in dist_likes {
+                if existing.contains(&path) || self.invalid_versions.contains(&path) {
+                    continue;
+                }
+                if is_invalid_version(&path) {
+                    self.invalid_versions.push(path);
+                    continue;
+                }
+                if let Some(dist_info) = read_distribution(&path)? {
+                    added.push(resolve_relative_editable(
+                        dist_info,
+                        self.interpreter.sys_prefix(),
+                    ));
+                }
+            }
+        }
 
-                // Index the distribution by name.
-                by_name
-                    .entry(dist_info.name().clone())
-                    .or_default()
-                    .push(idx);
+        for dist_info in added {
+            report.added.push(dist_info.clone());
+            index_distribution(
+                &mut self.distributions,
+                &mut self.by_name,
+                &mut self.by_url,
+                dist_info,
+            );
+        }
 
-                // Index the distribution by URL.
-                if let InstalledDistKind::Url(dist) = &dist_info.kind {
-                    by_url.entry(dist.url.clone()).or_default().push(idx);
+        // Restore the `sys.path` precedence of any packages with multiple distributions, since
+        // the added distributions were appended to the index.
+        if !report.added.is_empty() {
+            let position = |index: &usize| {
+                self.distributions[*index].as_ref().map(|dist| {
+                    let path = metadata_path(dist);
+                    let site = self
+                        .sites
+                        .iter()
+                        .position(|(site, _)| path.parent() == Some(site.as_path()));
+                    (site, path.to_path_buf())
+                })
+            };
+            for indexes in self.by_name.values_mut() {
+                if indexes.len() > 1 {
+                    indexes.sort_by_cached_key(position);
                 }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Layer the packages installed in an `overlay` environment on top of those in a `base`
+    /// environment, as in a virtual environment created with `--system-site-packages`.
+    ///
+    /// Distributions in the overlay shadow any distributions of the same name in the base.
+    /// Multiple distributions of the same name within a single layer are retained, such that
+    /// they're still reported as duplicates. The merged index uses the overlay's interpreter.
+    pub fn overlay(mut base: Self, overlay: Self) -> Self {
+        let Self {
+            interpreter,
+            mut distributions,
+            mut by_name,
+            mut by_url,
+            mut sites,
+            mut skipped,
+            compatibility_policy,
+            file_checks,
+            offline,
+            mut invalid_versions,
+        } = overlay;
+        sites.extend(base.sites.iter().cloned());
+        skipped.extend(std::mem::take(&mut base.skipped));
+
+        // Determine the packages that are shadowed by the overlay.
+        let shadowed: FxHashSet<PackageName> = distributions
+            .iter()
+            .flatten()
+            .map(|dist| dist.name().clone())
+            .collect();
+
+        invalid_versions.extend(
+            std::mem::take(&mut base.invalid_versions)
+                .into_iter()
+                .filter(|path| {
+                    invalid_version(path).is_none_or(|(package, _)| !shadowed.contains(&package))
+                }),
+        );
 
-                // Add the distribution to the database.
-                distributions.push(Some(dist_info));
+        for dist_info in base {
+            if shadowed.contains(dist_info.name()) {
+                continue;
             }
+            index_distribution(&mut distributions, &mut by_name, &mut by_url, dist_info);
         }
 
-        Ok(Self {
-            interpreter: interpreter.clone(),
+        Self {
+            interpreter,
             distributions,
             by_name,
             by_url,
-        })
+            sites,
+            skipped,
+            compatibility_policy,
+            file_checks,
+            offline,
+            invalid_versions,
+        }
+    }
+
+    /// Enable (or disable) the checks in [`SitePackages::diagnostics`] that verify the files
+    /// installed by each distribution: that the files listed in its `RECORD` exist (and aren't
+    /// split across `purelib` and `platlib`), that its scripts exist and refer to the
+    /// environment's interpreter, and that editable installs point to the importable package.
+    ///
+    /// These checks read and stat every installed file, so they're disabled by default, and only
+    /// metadata-level checks (e.g., missing dependencies) are performed.
+    #[must_use]
+    pub fn with_file_checks(mut self, file_checks: bool) -> Self {
+        self.file_checks = file_checks;
+        self
+    }
+
+    /// Treat installed distributions that are merely out-of-date as satisfied, as when operating
+    /// offline (e.g., with `--offline`).
+    ///
+    /// A distribution is out-of-date if it matches the requirement, but its source may have
+    /// changed since it was installed: a local source tree was modified, or a Git reference may
+    /// have moved. Reinstalling such a distribution may require network access (e.g., to fetch
+    /// the Git repository, or the build dependencies), so, offline, the installed distribution is
+    /// retained instead. Distributions that don't match the requirement at all are still
+    /// reported as unsatisfied.
+    #[must_use]
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Set the [`CompatibilityPolicy`] used to decide whether an installed version satisfies the
+    /// version specifiers of a requirement (e.g., in [`SitePackages::satisfies_requirements`]).
+    ///
+    /// Defaults to [`DefaultCompatibilityPolicy`], under which any version contained in the
+    /// specifiers is satisfying.
+    #[must_use]
+    pub fn with_compatibility_policy(mut self, policy: impl CompatibilityPolicy + 'static) -> Self {
+        self.compatibility_policy = Arc::new(policy);
+        self
+    }
+
+    /// Returns the [`CompatibilityPolicy`] used to decide whether an installed version satisfies a
+    /// requirement.
+    pub fn compatibility_policy(&self) -> &dyn CompatibilityPolicy {
+        self.compatibility_policy.as_ref()
     }
 
     /// Returns the [`Interpreter`] used to install the packages.
@@ -153,6 +448,63 @@ impl SitePackages {
         self.distributions.iter().flatten()
     }
 
+    /// Returns an iterator over the installed distributions, sorted by package name and version.
+    ///
+    /// Unlike [`SitePackages::iter`], the order doesn't depend on the layout of the environment,
+    /// so it's stable across machines. Distributions with the same name and version are ordered
+    /// by their install path.
+    pub fn iter_sorted(&self) -> impl Iterator<Item = &InstalledDist> {
+        let mut distributions = self.iter().collect::<Vec<_>>();
+        distributions.sort_by(|a, b| {
+            (a.name(), a.version(), a.install_path()).cmp(&(
+                b.name(),
+                b.version(),
+                b.install_path(),
+            ))
+        });
+        distributions.into_iter()
+    }
+
+    /// Returns the on-disk metadata path of each installed distribution: the `.dist-info` or
+    /// `.egg-info` directory (or `.egg-info` file), or, for legacy editable installs, the
+    /// `.egg-link` file.
+    pub fn dist_info_paths(&self) -> Vec<&Path> {
+        self.iter().map(metadata_path).collect()
+    }
+
+    /// Returns an iterator over the installed distributions, grouped by the `site-packages`
+    /// directory (i.e., the `sys.path` entry) in which each was found.
+    ///
+    /// Directories are returned in the order in which they were scanned, which follows `sys.path`
+    /// (e.g., a virtual environment's `site-packages` precedes the system `site-packages` in an
+    /// [`SitePackages::overlay`]). Directories without any installed distributions are omitted.
+    pub fn iter_by_site(&self) -> impl Iterator<Item = (&Path, Vec<&InstalledDist>)> {
+        let mut sites: Vec<(&Path, Vec<&InstalledDist>)> = Vec::new();
+        let mut index: FxHashMap<&Path, usize> = FxHashMap::default();
+        for (dist, path) in self.iter().zip(self.dist_info_paths()) {
+            let Some(site) = path.parent() else {
+                continue;
+            };
+            let position = *index.entry(site).or_insert_with(|| {
+                sites.push((site, Vec::new()));
+                sites.len() - 1
+            });
+            sites[position].1.push(dist);
+        }
+        sites.into_iter()
+    }
+
+    /// Returns the `site-packages` directories that were intentionally skipped, rather than
+    /// scanned, in the order in which they appear on `sys.path`.
+    pub fn skipped_sites(&self) -> &[PathBuf] {
+        &self.skipped
+    }
+
+    /// Returns the installed distributions that match the given predicate.
+    pub fn filter(&self, predicate: impl Fn(&InstalledDist) -> bool) -> Vec<&InstalledDist> {
+        self.iter().filter(|dist| predicate(dist)).collect()
+    }
+
     /// Returns the installed distributions for a given package.
     pub fn get_packages(&self, name: &PackageName) -> Vec<&InstalledDist> {
         let Some(indexes) = self.by_name.get(name) else {
@@ -164,20 +516,98 @@ impl SitePackages {
             .collect()
     }
 
+    /// Returns the installed version of a given package.
+    ///
+    /// If multiple distributions are installed for the package, returns the version of the one
+    /// that's imported, i.e., the first on `sys.path`.
+    pub fn version_of(&self, name: &PackageName) -> Option<&Version> {
+        self.get_packages(name)
+            .first()
+            .map(|distribution| distribution.version())
+    }
+
+    /// Returns an [`InstalledPackagesProvider`] over the index that excludes the given packages,
+    /// e.g., to resolve as if they weren't installed.
+    ///
+    /// The index is shared with the provider, rather than cloned.
+    pub fn provider_excluding(
+        self: &Arc<Self>,
+        names: impl IntoIterator<Item = PackageName>,
+    ) -> FilteredProvider<Arc<Self>> {
+        FilteredProvider::new(Arc::clone(self), names)
+    }
+
+    /// Returns `true` if any of the given packages are installed.
+    ///
+    /// Short-circuits on the first installed package, e.g., for checks like "is `torch` or
+    /// `tensorflow` installed?".
+    pub fn any_installed(&self, names: &[PackageName]) -> bool {
+        names.iter().any(|name| self.is_installed(name))
+    }
+
+    /// Returns `true` if the given package is installed.
+    pub(crate) fn is_installed(&self, name: &PackageName) -> bool {
+        self.by_name.get(name).is_some_and(|indexes| {
+            indexes
+                .iter()
+                .any(|index| self.distributions[*index].is_some())
+        })
+    }
+
+    /// Returns `true` if a `.dist-info` directory for the given package was excluded from the
+    /// index, since its version couldn't be parsed.
+    pub(crate) fn has_invalid_version(&self, name: &PackageName) -> bool {
+        self.invalid_versions
+            .iter()
+            .any(|path| invalid_version(path).is_some_and(|(package, _)| package == *name))
+    }
+
+    /// Returns the installed distributions for a package, given its name as written by a user.
+    ///
+    /// The name is normalized before lookup, such that, e.g., `Foo.Bar`, `foo_bar`, and `foo-bar`
+    /// all refer to the same package. Returns an empty list if the name is invalid.
+    pub fn get_normalized(&self, raw: &str) -> Vec<&InstalledDist> {
+        let Ok(name) = raw.parse::<PackageName>() else {
+            return Vec::new();
+        };
+        self.get_packages(&name)
+    }
+
     /// Remove the given packages from the index, returning all installed versions, if any.
+    ///
+    /// The removed distributions are also dropped from the name and URL indexes.
     pub fn remove_packages(&mut self, name: &PackageName) -> Vec<InstalledDist> {
-        let Some(indexes) = self.by_name.get(name) else {
+        let Some(indexes) = self.by_name.remove(name) else {
             return Vec::new();
         };
-        indexes
+        let removed = indexes
             .iter()
             .filter_map(|index| std::mem::take(&mut self.distributions[*index]))
-            .collect()
+            .collect::<Vec<_>>();
+
+        // Prune any URL index entries that pointed to the removed distributions.
+        for dist in &removed {
+            let InstalledDistKind::Url(dist) = &dist.kind else {
+                continue;
+            };
+            let url = canonicalize_file_url(&dist.url);
+            if let Some(urls) = self.by_url.get_mut(&url) {
+                urls.retain(|index| !indexes.contains(index));
+                if urls.is_empty() {
+                    self.by_url.remove(&url);
+                }
+            }
+        }
+
+        removed
     }
 
     /// Returns the distributions installed from the given URL, if any.
+    ///
+    /// Local file URLs are compared by their normalized path, such that, e.g., an editable
+    /// recorded with a relative URL matches a requirement for the same directory.
     pub fn get_urls(&self, url: &DisplaySafeUrl) -> Vec<&InstalledDist> {
-        let Some(indexes) = self.by_url.get(url) else {
+        let Some(indexes) = self.by_url.get(&canonicalize_file_url(url)) else {
             return Vec::new();
         };
         indexes
@@ -186,382 +616,770 @@ impl SitePackages {
             .collect()
     }
 
-    /// Returns `true` if there are any installed packages.
-    pub fn any(&self) -> bool {
-        self.distributions.iter().any(Option::is_some)
+    /// Export the installed packages as a list of requirements, as in `uv pip freeze`.
+    ///
+    /// Registry distributions are pinned to their installed version (`name==version`), while
+    /// direct URL distributions are pinned to the URL recorded in their `direct_url.json`, with
+    /// editable installs represented as editable directory requirements (`-e URL`).
+    ///
+    /// If a package has multiple installed distributions, a warning is emitted and the first
+    /// distribution on `sys.path` (i.e., the one that Python would import) is used.
+    pub fn to_requirements(&self, include_editable: bool) -> Vec<Requirement> {
+        let mut names = self.by_name.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut requirements = Vec::with_capacity(names.len());
+        for name in names {
+            let installed = self.get_packages(name);
+            let Some(distribution) = installed.first() else {
+                continue;
+            };
+            if installed.len() > 1 {
+                warn_user!(
+                    "The package `{name}` has multiple installed distributions; using the distribution at: {}",
+                    distribution.install_path().user_display()
+                );
+            }
+            if !include_editable && distribution.is_editable() {
+                continue;
+            }
+            requirements.push(to_requirement(distribution));
+        }
+        requirements
     }
 
-    /// Validate the installed packages in the virtual environment.
-    pub fn diagnostics(
-        &self,
-        markers: &ResolverMarkerEnvironment,
-        tags: &Tags,
-    ) -> Result<Vec<SitePackagesDiagnostic>> {
-        let mut diagnostics = Vec::new();
+    /// Returns the files owned exclusively by the given package, i.e., those listed in its
+    /// `RECORD` but not in the `RECORD` of any other installed distribution.
+    ///
+    /// Files shared with other distributions (e.g., the `__init__.py` of a `pkgutil`-style
+    /// namespace package) are excluded, such that they can be left in place when the package
+    /// is uninstalled. Distributions without a `RECORD` (e.g., `.egg-info` distributions) are
+    /// assumed not to share any files.
+    pub fn exclusive_files(&self, name: &PackageName) -> Result<Vec<PathBuf>> {
+        let mut owned = BTreeSet::new();
+        let mut shared = FxHashSet::default();
+        for distribution in self.iter() {
+            let is_target = distribution.name() == name;
+            let Some(files) = record_files(distribution)
+                .with_context(|| format!("Failed to read `RECORD` for: {}", distribution.name()))?
+            else {
+                continue;
+            };
+            if is_target {
+                owned.extend(files);
+            } else {
+                shared.extend(files);
+            }
+        }
+        Ok(owned
+            .into_iter()
+            .filter(|path| !shared.contains(path))
+            .collect())
+    }
 
-        for (package, indexes) in &self.by_name {
-            let mut distributions = indexes.iter().flat_map(|index| &self.distributions[*index]);
+    /// Returns the groups of distributions that provide the same top-level module, keyed by the
+    /// module name, such that importing the module from one distribution shadows the other.
+    ///
+    /// Unlike [`SitePackagesDiagnostic::DuplicatePackage`](crate::SitePackagesDiagnostic::DuplicatePackage), which flags multiple distributions
+    /// with the same name, this catches distinct packages that ship the same module (e.g.,
+    /// `tests`). Modules are derived from each distribution's `RECORD`. Directories without an
+    /// `__init__.py` are treated as (PEP 420) namespace packages, which can be shared safely, so
+    /// the first regular package or module within them is used instead (e.g., `google.protobuf`).
+    /// Distributions without a `RECORD` are skipped.
+    pub fn group_by_top_level_conflict(&self) -> Vec<(String, Vec<&InstalledDist>)> {
+        let mut providers: BTreeMap<String, Vec<&InstalledDist>> = BTreeMap::new();
+        for distribution in self.iter() {
+            for module in top_level_modules(distribution) {
+                providers.entry(module).or_default().push(distribution);
+            }
+        }
+        providers
+            .into_iter()
+            .filter(|(_, distributions)| {
+                distributions
+                    .iter()
+                    .any(|distribution| distribution.name() != distributions[0].name())
+            })
+            .collect()
+    }
+
+    /// Returns `true` if any installed distribution provides a top-level module with the same name
+    /// as a module in the interpreter's standard library (e.g., a `typing` backport), which could
+    /// shadow it.
+    ///
+    /// Short-circuits on the first shadow found. Standard library modules are those in the
+    /// interpreter's `stdlib` directory, either as a `.py` file or as a regular package; built-in
+    /// and extension modules aren't considered. Modules are derived from each distribution's
+    /// `RECORD`.
+    pub fn has_stdlib_shadows(&self) -> bool {
+        let stdlib = self.interpreter.stdlib();
+        self.iter().any(|distribution| {
+            top_level_modules(distribution)
+                .iter()
+                .filter(|module| !module.contains('.'))
+                .any(|module| {
+                    stdlib.join(format!("{module}.py")).is_file()
+                        || stdlib.join(module).join("__init__.py").is_file()
+                })
+        })
+    }
+
+    /// Returns the distributions that provide the given (dotted) module, e.g., `a.b.c`.
+    ///
+    /// Modules are matched against the files in each distribution's `RECORD`, as a module file
+    /// (`a/b/c.py`, or an extension module like `a/b/c.cpython-312-x86_64-linux-gnu.so`) or a
+    /// regular package (`a/b/c/__init__.py`). Typically, a single distribution is returned. If no
+    /// distribution provides the module directly, it's treated as a (PEP 420) namespace package,
+    /// and every distribution with files beneath it is returned. Distributions without a `RECORD`
+    /// are skipped.
+    pub fn owners_of_module(&self, module: &str) -> Vec<&InstalledDist> {
+        let parts = module.split('.').collect::<Vec<_>>();
+        if parts.iter().any(|part| part.is_empty()) {
+            return Vec::new();
+        }
+        let (name, parents) = parts.split_last().expect("split yields at least one part");
 
-            // Find the installed distribution for the given package.
-            let Some(distribution) = distributions.next() else {
+        let mut owners = Vec::new();
+        let mut namespace_owners = Vec::new();
+        for distribution in self.iter() {
+            let Ok(Some(files)) = record_files(distribution) else {
                 continue;
             };
-
-            if let Some(conflict) = distributions.next() {
-                // There are multiple installed distributions for the same package.
-                diagnostics.push(SitePackagesDiagnostic::DuplicatePackage {
-                    package: package.clone(),
-                    paths: std::iter::once(distribution.install_path().to_owned())
-                        .chain(std::iter::once(conflict.install_path().to_owned()))
-                        .chain(distributions.map(|dist| dist.install_path().to_owned()))
-                        .collect(),
-                });
+            let Some(site_packages) = distribution.install_path().parent() else {
                 continue;
+            };
+            let parent = parents
+                .iter()
+                .fold(site_packages.to_path_buf(), |path, part| path.join(part));
+            let package = parent.join(name);
+            let provides = |path: &Path| {
+                if path == package.join("__init__.py") {
+                    return true;
+                }
+                if path.parent() != Some(parent.as_path()) {
+                    return false;
+                }
+                let Some(file_name) = path.file_name().and_then(|file_name| file_name.to_str())
+                else {
+                    return false;
+                };
+                let is_extension = path
+                    .extension()
+                    .is_some_and(|extension| extension == "so" || extension == "pyd");
+                file_name == format!("{name}.py")
+                    || (is_extension
+                        && file_name
+                            .strip_prefix(name)
+                            .is_some_and(|suffix| suffix.starts_with('.')))
+            };
+            if files.iter().any(|path| provides(path)) {
+                owners.push(distribution);
+            } else if files.iter().any(|path| path.starts_with(&package)) {
+                namespace_owners.push(distribution);
             }
+        }
+        if owners.is_empty() {
+            namespace_owners
+        } else {
+            owners
+        }
+    }
 
-            for index in indexes {
-                let Some(distribution) = &self.distributions[*index] else {
-                    continue;
-                };
+    /// Returns the time at which the given package was installed, derived from the `ctime` of
+    /// its `.dist-info` (or `.egg-info`) path.
+    ///
+    /// If multiple distributions are installed for the package, returns the most recent.
+    pub fn installed_at(&self, name: &PackageName) -> Option<Timestamp> {
+        self.installed_at_with(name, |path| Timestamp::from_path(path).ok())
+    }
 
-                // Determine the dependencies for the given package.
-                let Ok(metadata) = distribution.read_metadata() else {
-                    diagnostics.push(SitePackagesDiagnostic::MetadataUnavailable {
-                        package: package.clone(),
-                        path: distribution.install_path().to_owned(),
-                    });
-                    continue;
-                };
+    /// Returns the most recent of the [`Timestamp`]s that `timestamp` reports for the install
+    /// paths of the given package.
+    fn installed_at_with(
+        &self,
+        name: &PackageName,
+        timestamp: impl Fn(&Path) -> Option<Timestamp>,
+    ) -> Option<Timestamp> {
+        self.get_packages(name)
+            .into_iter()
+            .filter_map(|distribution| timestamp(distribution.install_path()))
+            .max()
+    }
 
-                // Verify that the package is compatible with the current Python version.
-                if let Some(requires_python) = metadata.requires_python.as_ref() {
-                    if !requires_python.contains(markers.python_full_version()) {
-                        diagnostics.push(SitePackagesDiagnostic::IncompatiblePythonVersion {
-                            package: package.clone(),
-                            version: self.interpreter.python_version().clone(),
-                            requires_python: requires_python.clone(),
-                        });
-                    }
-                }
+    /// Returns the license of the given package, as declared in its metadata.
+    ///
+    /// Prefers the SPDX `License-Expression` field (PEP 639), then the free-form `License` field,
+    /// and finally any `License ::` trove classifiers (e.g., `MIT License`). Returns `None` if the
+    /// package isn't installed, its metadata can't be read, or it doesn't declare a license. If
+    /// multiple distributions are installed for the package, the first is used.
+    pub fn license(&self, name: &PackageName) -> Option<String> {
+        let distribution = self.get_packages(name).into_iter().next()?;
+        let contents = fs::read(metadata_file(distribution)).ok()?;
+        let metadata = Metadata23::parse(&contents).ok()?;
 
-                // Verify that the package is compatible with the current tags.
-                match distribution.read_tags() {
-                    Ok(Some(wheel_tags)) => {
-                        if !wheel_tags.is_compatible(tags) {
-                            // TODO(charlie): Show the expanded tag hint, that explains _why_ it doesn't match.
-                            diagnostics.push(SitePackagesDiagnostic::IncompatiblePlatform {
-                                package: package.clone(),
-                            });
-                        }
-                    }
-                    Ok(None) => {}
-                    Err(_) => {
-                        diagnostics.push(SitePackagesDiagnostic::TagsUnavailable {
-                            package: package.clone(),
-                            path: distribution.install_path().to_owned(),
-                        });
-                    }
-                }
+        if let Some(expression) = metadata
+            .license_expression
+            .as_deref()
+            .map(str::trim)
+            .filter(|expression| !expression.is_empty())
+        {
+            return Some(expression.to_string());
+        }
 
-                // Verify that the dependencies are installed.
-                for dependency in &metadata.requires_dist {
-                    if !dependency.evaluate_markers(markers, &[]) {
-                        continue;
-                    }
+        // Legacy versions of `setuptools` write `UNKNOWN` for a missing license.
+        if let Some(license) = metadata
+            .license
+            .as_deref()
+            .map(str::trim)
+            .filter(|license| !license.is_empty() && *license != "UNKNOWN")
+        {
+            return Some(license.to_string());
+        }
 
-                    let installed = self.get_packages(&dependency.name);
-                    match installed.as_slice() {
-                        [] => {
-                            // No version installed.
-                            diagnostics.push(SitePackagesDiagnostic::MissingDependency {
-                                package: package.clone(),
-                                requirement: dependency.clone(),
-                            });
-                        }
-                        [installed] => {
-                            match &dependency.version_or_url {
-                                None | Some(VersionOrUrl::Url(_)) => {
-                                    // Nothing to do (accept any installed version).
-                                }
-                                Some(VersionOrUrl::VersionSpecifier(version_specifier)) => {
-                                    // The installed version doesn't satisfy the requirement.
-                                    if !version_specifier.contains(installed.version()) {
-                                        diagnostics.push(
-                                            SitePackagesDiagnostic::IncompatibleDependency {
-                                                package: package.clone(),
-                                                version: installed.version().clone(),
-                                                requirement: dependency.clone(),
-                                            },
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        _ => {
-                            // There are multiple installed distributions for the same package.
-                        }
-                    }
+        // Ex) `License :: OSI Approved :: MIT License`
+        let classifiers = metadata
+            .classifiers
+            .iter()
+            .filter_map(|classifier| {
+                let mut segments = classifier.split("::").map(str::trim);
+                if segments.next() != Some("License") {
+                    return None;
                 }
-            }
+                segments
+                    .last()
+                    .filter(|license| *license != "OSI Approved")
+                    .map(ToString::to_string)
+            })
+            .collect::<Vec<_>>();
+        if classifiers.is_empty() {
+            None
+        } else {
+            Some(classifiers.join(", "))
         }
+    }
 
-        Ok(diagnostics)
+    /// Returns the console and GUI scripts installed by each package, as declared in its
+    /// `entry_points.txt`, along with the path to the script in the scripts directory (e.g., `bin`
+    /// or `Scripts`) of the site into which the package was installed.
+    ///
+    /// Scripts are returned regardless of whether they exist on disk; missing scripts are
+    /// reported by [`SitePackages::diagnostics`].
+    pub fn scripts(&self) -> Vec<(PackageName, String, PathBuf)> {
+        let mut scripts = self
+            .iter()
+            .flat_map(|distribution| {
+                self.distribution_scripts(distribution)
+                    .into_iter()
+                    .map(|(script, path)| (distribution.name().clone(), script, path))
+            })
+            .collect::<Vec<_>>();
+        scripts.sort_unstable();
+        scripts
     }
 
-    /// Returns if the installed packages satisfy the given requirements.
-    pub fn satisfies_spec(
+    /// Returns the scripts declared in the distribution's `entry_points.txt`, along with their
+    /// paths in the scripts directory.
+    ///
+    /// Scripts are located via the distribution's `RECORD`, since the distribution may have been
+    /// installed into a site other than the environment's own (e.g., the user site, or a base
+    /// environment). Scripts missing from the `RECORD` are assumed to be in the environment's
+    /// scripts directory if the distribution is installed in the environment's `purelib` or
+    /// `platlib`, and are skipped otherwise.
+    pub(crate) fn distribution_scripts(
         &self,
-        requirements: &[UnresolvedRequirementSpecification],
-        constraints: &[NameRequirementSpecification],
-        overrides: &[UnresolvedRequirementSpecification],
-        installation: InstallationStrategy,
-        markers: &ResolverMarkerEnvironment,
-        tags: &Tags,
-        config_settings: &ConfigSettings,
-        config_settings_package: &PackageConfigSettings,
-        extra_build_requires: &ExtraBuildRequires,
-        extra_build_variables: &ExtraBuildVariables,
-    ) -> Result<SatisfiesResult> {
-        // First, map all unnamed requirements to named requirements.
-        let requirements = {
-            let mut named = Vec::with_capacity(requirements.len());
-            for requirement in requirements {
-                match &requirement.requirement {
-                    UnresolvedRequirement::Named(requirement) => {
-                        named.push(Cow::Borrowed(requirement));
-                    }
-                    UnresolvedRequirement::Unnamed(requirement) => {
-                        match self.get_urls(requirement.url.verbatim.raw()).as_slice() {
-                            [] => {
-                                return Ok(SatisfiesResult::Unsatisfied(
-                                    requirement.url.verbatim.raw().to_string(),
-                                ));
-                            }
-                            [distribution] => {
-                                let requirement = uv_pep508::Requirement {
-                                    name: distribution.name().clone(),
-                                    version_or_url: Some(VersionOrUrl::Url(
-                                        requirement.url.clone(),
-                                    )),
-                                    marker: requirement.marker,
-                                    extras: requirement.extras.clone(),
-                                    origin: requirement.origin.clone(),
-                                };
-                                named.push(Cow::Owned(Requirement::from(requirement)));
-                            }
-                            _ => {
-                                return Ok(SatisfiesResult::Unsatisfied(
-                                    requirement.url.verbatim.raw().to_string(),
-                                ));
-                            }
-                        }
-                    }
-                }
+        distribution: &InstalledDist,
+    ) -> Vec<(String, PathBuf)> {
+        let entry_points = match &distribution.kind {
+            InstalledDistKind::Registry(_)
+            | InstalledDistKind::Url(_)
+            | InstalledDistKind::EggInfoDirectory(_) => {
+                distribution.install_path().join("entry_points.txt")
             }
-            named
+            InstalledDistKind::LegacyEditable(dist) => dist.egg_info.join("entry_points.txt"),
+            InstalledDistKind::EggInfoFile(_) => return Vec::new(),
         };
-
-        // Second, map all overrides to named requirements. We assume that all overrides are
-        // relevant.
-        let overrides = {
-            let mut named = Vec::with_capacity(overrides.len());
-            for requirement in overrides {
-                match &requirement.requirement {
-                    UnresolvedRequirement::Named(requirement) => {
-                        named.push(Cow::Borrowed(requirement));
-                    }
-                    UnresolvedRequirement::Unnamed(requirement) => {
-                        match self.get_urls(requirement.url.verbatim.raw()).as_slice() {
-                            [] => {
-                                return Ok(SatisfiesResult::Unsatisfied(
-                                    requirement.url.verbatim.raw().to_string(),
-                                ));
-                            }
-                            [distribution] => {
-                                let requirement = uv_pep508::Requirement {
-                                    name: distribution.name().clone(),
-                                    version_or_url: Some(VersionOrUrl::Url(
-                                        requirement.url.clone(),
-                                    )),
-                                    marker: requirement.marker,
-                                    extras: requirement.extras.clone(),
-                                    origin: requirement.origin.clone(),
-                                };
-                                named.push(Cow::Owned(Requirement::from(requirement)));
-                            }
-                            _ => {
-                                return Ok(SatisfiesResult::Unsatisfied(
-                                    requirement.url.verbatim.raw().to_string(),
-                                ));
-                            }
-                        }
-                    }
-                }
+        let Ok(contents) = fs::read_to_string(&entry_points) else {
+            return Vec::new();
+        };
+        let names = match script_names(contents, self.interpreter.python_minor()) {
+            Ok(names) => names,
+            Err(err) => {
+                warn!(
+                    "Failed to parse entry points at `{}`: {err}",
+                    entry_points.user_display()
+                );
+                return Vec::new();
             }
-            named
         };
+        let site = distribution.install_path().parent();
+        let record = record_files(distribution)
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        let scripts = record
+            .into_iter()
+            .filter(|path| site.is_none_or(|site| !path.starts_with(site)))
+            .collect::<Vec<_>>();
+        let fallback = self
+            .library(distribution)
+            .map(|_| self.interpreter.scripts());
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let path = scripts
+                    .iter()
+                    .find(|path| {
+                        path.parent()
+                            .is_some_and(|parent| script_path(parent, &name) == **path)
+                    })
+                    .cloned()
+                    .or_else(|| fallback.map(|scripts| script_path(scripts, &name)))?;
+                Some((name, path))
+            })
+            .collect()
+    }
 
-        self.satisfies_requirements(
-            requirements.iter().map(Cow::as_ref),
-            constraints.iter().map(|constraint| &constraint.requirement),
-            overrides.iter().map(Cow::as_ref),
-            installation,
-            markers,
-            tags,
-            config_settings,
-            config_settings_package,
-            extra_build_requires,
-            extra_build_variables,
-        )
+    /// Returns the library directory (`purelib` or `platlib`) from which the distribution was
+    /// read, or `None` if it was found elsewhere (e.g., on an additional `sys.path` entry).
+    ///
+    /// If the interpreter's `purelib` and `platlib` directories are the same, returns
+    /// [`LibKind::Pure`].
+    pub fn library(&self, distribution: &InstalledDist) -> Option<LibKind> {
+        let parent = distribution.install_path().parent()?;
+        if parent == self.interpreter.purelib() {
+            Some(LibKind::Pure)
+        } else if parent == self.interpreter.platlib() {
+            Some(LibKind::Plat)
+        } else {
+            None
+        }
     }
 
-    /// Like [`SitePackages::satisfies_spec`], but with resolved names for all requirements.
-    pub fn satisfies_requirements<'a>(
-        &self,
-        requirements: impl ExactSizeIterator<Item = &'a Requirement>,
-        constraints: impl Iterator<Item = &'a Requirement>,
-        overrides: impl Iterator<Item = &'a Requirement>,
-        installation: InstallationStrategy,
-        markers: &ResolverMarkerEnvironment,
-        tags: &Tags,
-        config_settings: &ConfigSettings,
-        config_settings_package: &PackageConfigSettings,
-        extra_build_requires: &ExtraBuildRequires,
-        extra_build_variables: &ExtraBuildVariables,
-    ) -> Result<SatisfiesResult> {
-        // Collect the constraints and overrides by package name.
-        let constraints: FxHashMap<&PackageName, Vec<&Requirement>> =
-            constraints.fold(FxHashMap::default(), |mut constraints, constraint| {
-                constraints
-                    .entry(&constraint.name)
-                    .or_default()
-                    .push(constraint);
-                constraints
-            });
-        let overrides: FxHashMap<&PackageName, Vec<&Requirement>> =
-            overrides.fold(FxHashMap::default(), |mut overrides, r#override| {
-                overrides
-                    .entry(&r#override.name)
-                    .or_default()
-                    .push(r#override);
-                overrides
+    /// Returns `true` if there are any installed packages.
+    pub fn any(&self) -> bool {
+        self.distributions.iter().any(Option::is_some)
+    }
+
+    /// Read and parse the `METADATA` and `WHEEL` files of every installed distribution in parallel,
+    /// populating each distribution's cache.
+    ///
+    /// For operations that touch the metadata of every package (e.g., [`SitePackages::diagnostics`]
+    /// or [`SitePackages::satisfies_requirements`]), this avoids reading each file lazily and
+    /// serially. Any failure to read or parse a `METADATA` file is cached, and returned by
+    /// subsequent reads, rather than aborting the preload.
+    pub fn preload_metadata(&self) {
+        self.distributions
+            .par_iter()
+            .flatten()
+            .for_each(|distribution| {
+                let _ = distribution.read_metadata();
+                let _ = distribution.read_tags();
             });
+    }
+
+    /// Returns the number of files installed by each package, as listed in its `RECORD`, sorted by
+    /// package name.
+    ///
+    /// Packages without a readable `RECORD` (e.g., legacy `.egg-info` installs) are reported with
+    /// a count of `None`, since their files can't be enumerated.
+    pub fn file_counts(&self) -> Vec<(PackageName, Option<usize>)> {
+        let mut names = self.by_name.keys().collect::<Vec<_>>();
+        names.sort();
+        let mut counts = Vec::with_capacity(names.len());
+        for package in names {
+            for distribution in self.get_packages(package) {
+                let count = match record_files(distribution) {
+                    Ok(files) => files.map(|files| files.len()),
+                    Err(err) => {
+                        debug!("Failed to read `RECORD` for `{package}`: {err}");
+                        None
+                    }
+                };
+                counts.push((package.clone(), count));
+            }
+        }
+        counts
+    }
 
-        let mut stack = Vec::with_capacity(requirements.len());
-        let mut seen = FxHashSet::with_capacity_and_hasher(requirements.len(), FxBuildHasher);
+    /// Returns the total number of files installed by all packages with a readable `RECORD`.
+    ///
+    /// See [`SitePackages::file_counts`].
+    pub fn total_file_count(&self) -> usize {
+        self.file_counts()
+            .into_iter()
+            .filter_map(|(_, count)| count)
+            .sum()
+    }
 
-        // Add the direct requirements to the queue.
-        for requirement in requirements {
-            if let Some(r#overrides) = overrides.get(&requirement.name) {
-                for dependency in r#overrides {
-                    if dependency.evaluate_markers(Some(markers), &[]) {
-                        if seen.insert((*dependency).clone()) {
-                            stack.push(Cow::Borrowed(*dependency));
-                        }
+    /// Returns the declared dependencies (`Requires-Dist`) of the installed package that apply
+    /// under the given markers, excluding those that only apply under an extra.
+    ///
+    /// If multiple distributions are installed for the package, the dependencies of each are
+    /// returned. Distributions whose metadata can't be read are skipped.
+    pub fn requires(
+        &self,
+        name: &PackageName,
+        markers: &ResolverMarkerEnvironment,
+    ) -> Vec<Requirement> {
+        self.get_packages(name)
+            .into_iter()
+            .filter_map(|distribution| distribution.read_metadata().ok())
+            .flat_map(|metadata| &metadata.requires_dist)
+            .map(|dependency| Requirement::from(dependency.clone()))
+            .filter(|dependency| dependency.evaluate_markers(Some(markers), &[]))
+            .collect()
+    }
+
+    /// Returns every installed distribution that depends on the given package, along with the
+    /// requirement that introduces the dependency.
+    ///
+    /// Requirements that only apply under an extra (e.g., `idna; extra == "all"`) are included
+    /// whenever their non-extra markers apply to the given environment; the extra is preserved in
+    /// the returned requirement's markers. Distributions whose metadata can't be read are skipped.
+    pub fn reverse_dependencies(
+        &self,
+        name: &PackageName,
+        markers: &ResolverMarkerEnvironment,
+    ) -> Vec<(&InstalledDist, &uv_pep508::Requirement<VerbatimParsedUrl>)> {
+        let mut dependents = Vec::new();
+        for distribution in self.iter() {
+            let Ok(metadata) = distribution.read_metadata() else {
+                continue;
+            };
+            for dependency in &metadata.requires_dist {
+                if dependency.name != *name {
+                    continue;
+                }
+                if !dependency.marker.without_extras().evaluate(markers, &[]) {
+                    continue;
+                }
+                dependents.push((distribution, dependency));
+            }
+        }
+        dependents
+    }
+
+    /// Returns the extras that each installed package was apparently installed with, as inferred
+    /// from which of its optional dependencies are installed.
+    ///
+    /// An extra is included if every dependency that it adds (i.e., that applies under the given
+    /// markers with the extra enabled, but not without it) is installed at a compatible version.
+    /// Extras that don't add any dependencies in the given environment can't be inferred, and are
+    /// omitted, as are packages without any inferred extras. Extras are returned in sorted order.
+    pub fn installed_extras(
+        &self,
+        markers: &ResolverMarkerEnvironment,
+    ) -> FxHashMap<PackageName, Vec<ExtraName>> {
+        let is_installed = |dependency: &uv_pep508::Requirement<VerbatimParsedUrl>| {
+            self.get_packages(&dependency.name)
+                .into_iter()
+                .any(|installed| match &dependency.version_or_url {
+                    Some(VersionOrUrl::VersionSpecifier(specifiers)) => self
+                        .compatibility_policy
+                        .is_compatible(installed.version(), specifiers),
+                    Some(VersionOrUrl::Url(_)) | None => true,
+                })
+        };
+
+        let mut installed_extras = FxHashMap::default();
+        for (name, indexes) in &self.by_name {
+            let mut extras = BTreeSet::new();
+            for distribution in indexes.iter().flat_map(|index| &self.distributions[*index]) {
+                let Ok(metadata) = distribution.read_metadata() else {
+                    continue;
+                };
+                for extra in &metadata.provides_extra {
+                    let mut dependencies = metadata
+                        .requires_dist
+                        .iter()
+                        .filter(|dependency| {
+                            dependency.evaluate_markers(markers, std::slice::from_ref(extra))
+                                && !dependency.evaluate_markers(markers, &[])
+                        })
+                        .peekable();
+                    if dependencies.peek().is_none() {
+                        continue;
+                    }
+                    if dependencies.all(is_installed) {
+                        extras.insert(extra.clone());
                     }
                 }
-            } else {
-                if requirement.evaluate_markers(Some(markers), &[]) {
-                    if seen.insert(requirement.clone()) {
-                        stack.push(Cow::Borrowed(requirement));
+            }
+            if !extras.is_empty() {
+                installed_extras.insert(name.clone(), extras.into_iter().collect());
+            }
+        }
+        installed_extras
+    }
+
+    /// Returns the installed packages reachable from the given root packages by following the
+    /// dependencies that apply under the given markers, including any installed roots.
+    ///
+    /// Unlike [`SitePackages::satisfies_requirements`], the traversal doesn't verify that the
+    /// installed versions satisfy each dependency; a dependency on an installed package is followed
+    /// regardless. Dependencies that aren't installed (along with any packages whose metadata can't
+    /// be read) are skipped. Extras requested by a dependency (e.g., `anyio[trio]`) are followed.
+    pub fn closure(
+        &self,
+        roots: &[PackageName],
+        markers: &ResolverMarkerEnvironment,
+    ) -> FxHashSet<PackageName> {
+        let mut closure = FxHashSet::default();
+        let mut seen = FxHashSet::default();
+        let mut stack = roots
+            .iter()
+            .map(|name| (name.clone(), Vec::<ExtraName>::new()))
+            .collect::<Vec<_>>();
+        while let Some((name, extras)) = stack.pop() {
+            if !seen.insert((name.clone(), extras.clone())) {
+                continue;
+            }
+            let installed = self.get_packages(&name);
+            if installed.is_empty() {
+                continue;
+            }
+            for distribution in installed {
+                let Ok(metadata) = distribution.read_metadata() else {
+                    continue;
+                };
+                for dependency in &metadata.requires_dist {
+                    if dependency.evaluate_markers(markers, &extras) {
+                        let mut extras = dependency.extras.to_vec();
+                        extras.sort();
+                        stack.push((dependency.name.clone(), extras));
                     }
                 }
             }
+            closure.insert(name);
         }
+        closure
+    }
 
-        // Verify that all non-editable requirements are met.
-        while let Some(requirement) = stack.pop() {
-            let name = &requirement.name;
-            let installed = self.get_packages(name);
-            match installed.as_slice() {
-                [] => {
-                    // The package isn't installed.
-                    return Ok(SatisfiesResult::Unsatisfied(requirement.to_string()));
+    /// Returns the given packages in the order in which they should be uninstalled, such that each
+    /// package is removed before any of its dependencies (i.e., dependents first).
+    ///
+    /// Only dependencies between the given packages are considered, as evaluated against the
+    /// environment's markers; packages outside the set are ignored. Ties are broken by name. If
+    /// the packages form a dependency cycle, it's broken at the package with the lowest name, with
+    /// a warning.
+    pub fn uninstall_order(&self, names: &[PackageName]) -> Vec<PackageName> {
+        let markers = self.interpreter.resolver_marker_environment();
+        let requested = names.iter().collect::<BTreeSet<_>>();
+
+        // Collect the dependencies of each package within the requested set, and count the
+        // dependents of each package that have yet to be uninstalled.
+        let mut dependencies = BTreeMap::new();
+        let mut dependents = requested
+            .iter()
+            .map(|name| (*name, 0usize))
+            .collect::<BTreeMap<_, _>>();
+        for name in &requested {
+            let mut edges = BTreeSet::new();
+            for distribution in self.get_packages(name) {
+                let Ok(metadata) = distribution.read_metadata() else {
+                    continue;
+                };
+                for dependency in &metadata.requires_dist {
+                    if dependency.name == **name || !dependency.evaluate_markers(&markers, &[]) {
+                        continue;
+                    }
+                    if let Some(target) = requested.get(&dependency.name) {
+                        edges.insert(*target);
+                    }
+                }
+            }
+            for target in &edges {
+                *dependents.get_mut(target).expect("requested package") += 1;
+            }
+            dependencies.insert(*name, edges);
+        }
+
+        let mut order = Vec::with_capacity(dependents.len());
+        while let Some((&first, _)) = dependents.first_key_value() {
+            let next = dependents
+                .iter()
+                .find(|(_, count)| **count == 0)
+                .map_or_else(
+                    || {
+                        warn_user!(
+                            "The packages to uninstall form a dependency cycle; uninstalling `{first}` before its dependents"
+                        );
+                        first
+                    },
+                    |(name, _)| *name,
+                );
+            dependents.remove(next);
+            for dependency in &dependencies[next] {
+                if let Some(count) = dependents.get_mut(dependency) {
+                    *count -= 1;
                 }
-                [distribution] => {
-                    // Validate that the requirement is satisfied.
-                    if requirement.evaluate_markers(Some(markers), &[]) {
-                        match RequirementSatisfaction::check(
-                            name,
-                            distribution,
-                            &requirement.source,
-                            installation,
-                            tags,
-                            config_settings,
-                            config_settings_package,
-                            extra_build_requires,
-                            extra_build_variables,
-                        ) {
-                            RequirementSatisfaction::Mismatch
-                            | RequirementSatisfaction::OutOfDate
-                            | RequirementSatisfaction::CacheInvalid => {
-                                return Ok(SatisfiesResult::Unsatisfied(requirement.to_string()));
-                            }
-                            RequirementSatisfaction::Satisfied => {}
+            }
+            order.push(next.clone());
+        }
+        order
+    }
+
+    /// Build a [`DependencyGraph`] of the installed packages.
+    ///
+    /// Each installed distribution is a node. For each dependency that applies under the given
+    /// markers, an edge is added to every installed distribution that satisfies it. Distributions
+    /// whose metadata can't be read are included without any outgoing edges.
+    pub fn dependency_graph(&self, markers: &ResolverMarkerEnvironment) -> DependencyGraph<'_> {
+        let mut graph = DependencyGraph::default();
+
+        // Add a node for each installed distribution.
+        let mut nodes =
+            FxHashMap::with_capacity_and_hasher(self.distributions.len(), FxBuildHasher);
+        for (index, distribution) in self.distributions.iter().enumerate() {
+            if let Some(distribution) = distribution {
+                nodes.insert(index, graph.add_node(distribution));
+            }
+        }
+
+        // Add an edge for each satisfied dependency.
+        for (index, distribution) in self.distributions.iter().enumerate() {
+            let Some(distribution) = distribution else {
+                continue;
+            };
+            let Ok(metadata) = distribution.read_metadata() else {
+                continue;
+            };
+            let source = nodes[&index];
+            for dependency in &metadata.requires_dist {
+                if !dependency.evaluate_markers(markers, &[]) {
+                    continue;
+                }
+                let Some(targets) = self.by_name.get(&dependency.name) else {
+                    continue;
+                };
+                for target in targets {
+                    let Some(installed) = &self.distributions[*target] else {
+                        continue;
+                    };
+                    if let Some(VersionOrUrl::VersionSpecifier(version_specifier)) =
+                        &dependency.version_or_url
+                    {
+                        if !self
+                            .compatibility_policy
+                            .is_compatible(installed.version(), version_specifier)
+                        {
+                            continue;
                         }
                     }
+                    graph.add_edge(source, nodes[target], dependency);
+                }
+            }
+        }
 
-                    // Validate that the installed version satisfies the constraints.
-                    for constraint in constraints.get(name).into_iter().flatten() {
-                        if constraint.evaluate_markers(Some(markers), &[]) {
-                            match RequirementSatisfaction::check(
-                                name,
-                                distribution,
-                                &constraint.source,
-                                installation,
+        graph
+    }
+
+    /// Validate that the installed packages exactly match a resolution (e.g., as derived from a
+    /// lockfile), returning any missing, extraneous, or mismatched distributions.
+    ///
+    /// Unlike [`SitePackages::satisfies_requirements`], installed versions must match the
+    /// resolved versions exactly, and every installed package must be part of the resolution.
+    pub fn validate_against_lock<'a>(
+        &'a self,
+        resolution: &'a Resolution,
+        config_settings: &ConfigSettings,
+        config_settings_package: &PackageConfigSettings,
+        extra_build_requires: &ExtraBuildRequires,
+        extra_build_variables: &ExtraBuildVariables,
+    ) -> Result<LockValidation<'a>> {
+        let markers = self.interpreter.resolver_marker_environment();
+        let tags = self.interpreter.tags()?;
+
+        let mut validation = LockValidation::default();
+        let mut locked = FxHashSet::default();
+
+        // Verify that each locked distribution is installed at the locked version.
+        for dist in resolution.distributions() {
+            locked.insert(dist.name());
+            match self.get_packages(dist.name()).as_slice() {
+                [] => validation.missing.push(dist),
+                [installed] => {
+                    let source = RequirementSource::from(dist);
+                    let satisfied = dist
+                        .version()
+                        .is_none_or(|version| version == installed.version())
+                        && matches!(
+                            RequirementSatisfaction::check(
+                                dist.name(),
+                                installed,
+                                &source,
+                                InstallationStrategy::Strict,
+                                self.compatibility_policy(),
+                                &markers,
                                 tags,
                                 config_settings,
                                 config_settings_package,
                                 extra_build_requires,
                                 extra_build_variables,
-                            ) {
-                                RequirementSatisfaction::Mismatch
-                                | RequirementSatisfaction::OutOfDate
-                                | RequirementSatisfaction::CacheInvalid => {
-                                    return Ok(SatisfiesResult::Unsatisfied(
-                                        requirement.to_string(),
-                                    ));
-                                }
-                                RequirementSatisfaction::Satisfied => {}
-                            }
-                        }
-                    }
-
-                    // Recurse into the dependencies.
-                    let metadata = distribution
-                        .read_metadata()
-                        .with_context(|| format!("Failed to read metadata for: {distribution}"))?;
-
-                    // Add the dependencies to the queue.
-                    for dependency in &metadata.requires_dist {
-                        let dependency = Requirement::from(dependency.clone());
-                        if let Some(r#overrides) = overrides.get(&dependency.name) {
-                            for dependency in r#overrides {
-                                if dependency.evaluate_markers(Some(markers), &requirement.extras) {
-                                    if seen.insert((*dependency).clone()) {
-                                        stack.push(Cow::Borrowed(*dependency));
-                                    }
-                                }
-                            }
-                        } else {
-                            if dependency.evaluate_markers(Some(markers), &requirement.extras) {
-                                if seen.insert(dependency.clone()) {
-                                    stack.push(Cow::Owned(dependency));
-                                }
-                            }
-                        }
+                            ),
+                            RequirementSatisfaction::Satisfied
+                        );
+                    if !satisfied {
+                        validation.mismatched.push((dist, *installed));
                     }
                 }
-                _ => {
-                    // There are multiple installed distributions for the same package.
-                    return Ok(SatisfiesResult::Unsatisfied(requirement.to_string()));
+                installed => {
+                    // Multiple installed distributions can't exactly match a single locked
+                    // distribution.
+                    validation
+                        .mismatched
+                        .extend(installed.iter().map(|installed| (dist, *installed)));
                 }
             }
         }
 
-        Ok(SatisfiesResult::Fresh {
-            recursive_requirements: seen,
-        })
+        // Verify that no other packages are installed.
+        validation.extraneous = self
+            .iter()
+            .filter(|installed| !locked.contains(installed.name()))
+            .collect();
+
+        Ok(validation)
+    }
+
+    /// Compare this environment (e.g., before an install) to `other` (e.g., after the install),
+    /// returning the packages that were added, removed, or changed.
+    ///
+    /// Packages are compared by name. A package is considered changed if its version or (for
+    /// editable installs) its URL differs. If multiple distributions are installed for a package
+    /// in either environment, each distribution is instead reported as added or removed, unless
+    /// a distribution with the same version and URL is present in both.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> EnvironmentDiff<'a> {
+        let mut diff = EnvironmentDiff::default();
+        let names = self
+            .by_name
+            .keys()
+            .chain(other.by_name.keys())
+            .collect::<BTreeSet<_>>();
+        for name in names {
+            let before = self.get_packages(name);
+            let after = other.get_packages(name);
+            let is_same = |a: &InstalledDist, b: &InstalledDist| {
+                a.version() == b.version() && a.as_editable() == b.as_editable()
+            };
+            if let ([before], [after]) = (before.as_slice(), after.as_slice()) {
+                if !is_same(before, after) {
+                    diff.changed.push((*before, *after));
+                }
+                continue;
+            }
+            diff.removed.extend(
+                before
+                    .iter()
+                    .filter(|before| !after.iter().any(|after| is_same(before, after))),
+            );
+            diff.added.extend(
+                after
+                    .iter()
+                    .filter(|after| !before.iter().any(|before| is_same(before, after))),
+            );
+        }
+        diff
     }
 }
 
@@ -586,17 +1404,428 @@ pub enum InstallationStrategy {
     Strict,
 }
 
-/// We check if all requirements are already satisfied, recursing through the requirements tree.
-#[derive(Debug)]
-pub enum SatisfiesResult {
-    /// All requirements are recursively satisfied.
-    Fresh {
-        /// The flattened set (transitive closure) of all requirements checked.
-        recursive_requirements: FxHashSet<Requirement>,
-    },
-    /// We found an unsatisfied requirement. Since we exit early, we only know about the first
-    /// unsatisfied requirement.
-    Unsatisfied(String),
+/// The result of validating an environment against a resolution, via
+/// [`SitePackages::validate_against_lock`].
+#[derive(Debug, Default)]
+pub struct LockValidation<'a> {
+    /// Distributions in the resolution that aren't installed.
+    pub missing: Vec<&'a ResolvedDist>,
+    /// Installed distributions that aren't in the resolution.
+    pub extraneous: Vec<&'a InstalledDist>,
+    /// Distributions that are installed, but don't match the resolution (e.g., at a different
+    /// version, or from a different source).
+    pub mismatched: Vec<(&'a ResolvedDist, &'a InstalledDist)>,
+}
+
+impl LockValidation<'_> {
+    /// Returns `true` if the environment exactly matches the resolution.
+    pub fn is_exact(&self) -> bool {
+        self.missing.is_empty() && self.extraneous.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// The differences between two environments, as returned by [`SitePackages::diff`].
+#[derive(Debug, Default)]
+pub struct EnvironmentDiff<'a> {
+    /// Distributions that are only installed in the second environment.
+    pub added: Vec<&'a InstalledDist>,
+    /// Distributions that are only installed in the first environment.
+    pub removed: Vec<&'a InstalledDist>,
+    /// Distributions that are installed in both environments, but at a different version or
+    /// (for editable installs) from a different URL, as `(before, after)`.
+    pub changed: Vec<(&'a InstalledDist, &'a InstalledDist)>,
+}
+
+impl EnvironmentDiff<'_> {
+    /// Returns `true` if the environments contain the same packages.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Read the paths of the entries in a `site-packages` directory that may be installed
+/// distributions (i.e., directories, `.egg-link` files, and `.egg-info` files), in sorted order,
+/// or `None` if the directory doesn't exist.
+fn read_dist_likes(site_packages: &Path) -> Result<Option<BTreeSet<PathBuf>>> {
+    match fs::read_dir(site_packages) {
+        Ok(read_dir) => {
+            // Collect sorted directory paths; `read_dir` is not stable across platforms
+            let dist_likes: BTreeSet<_> = read_dir
+                .filter_map(|read_dir| match read_dir {
+                    Ok(entry) => match entry.file_type() {
+                        Ok(file_type) => (file_type.is_dir()
+                            || entry.path().extension().is_some_and(|ext| {
+                                ext.eq_ignore_ascii_case("egg-link")
+                                    || ext.eq_ignore_ascii_case("egg-info")
+                            }))
+                        .then_some(Ok(entry.path())),
+                        Err(err) => Some(Err(err)),
+                    },
+                    Err(err) => Some(Err(err)),
+                })
+                .collect::<Result<_, std::io::Error>>()
+                .with_context(|| {
+                    format!(
+                        "Failed to read site-packages directory contents: {}",
+                        site_packages.user_display()
+                    )
+                })?;
+            Ok(Some(dist_likes))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).context("Failed to read site-packages directory"),
+    }
+}
+
+/// Read the installed distribution at the given path, or `None` if the path isn't a
+/// distribution (or is a dangling temporary directory left behind by an interrupted install).
+fn read_distribution(path: &Path) -> Result<Option<InstalledDist>> {
+    match InstalledDist::try_from_path(path) {
+        Ok(dist_info) => Ok(dist_info),
+        Err(_)
+            if path
+                .file_name()
+                .is_some_and(|name| name.to_str().is_some_and(|name| name.starts_with('~'))) =>
+        {
+            warn_user!(
+                "Ignoring dangling temporary directory: `{}`",
+                path.simplified_display().cyan()
+            );
+            Ok(None)
+        }
+        Err(err) => Err(err).context(format!(
+            "Failed to read metadata from: `{}`",
+            path.simplified_display()
+        )),
+    }
+}
+
+/// Returns `true` if the path is a `.dist-info` directory whose version isn't a valid PEP 440
+/// version, warning the user that it's excluded from the index.
+fn is_invalid_version(path: &Path) -> bool {
+    let Some((package, raw)) = invalid_version(path) else {
+        return false;
+    };
+    warn_user!(
+        "Ignoring `{package}` with invalid version `{raw}`: `{}`",
+        path.simplified_display().cyan()
+    );
+    true
+}
+
+/// Returns the package name and raw version of a `.dist-info` directory whose version isn't a
+/// valid PEP 440 version (e.g., `foo-not_a_version.dist-info`), or `None` otherwise.
+pub(crate) fn invalid_version(path: &Path) -> Option<(PackageName, String)> {
+    if !path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("dist-info"))
+    {
+        return None;
+    }
+    let (name, version) = path.file_stem()?.to_str()?.split_once('-')?;
+    let name = PackageName::from_str(name).ok()?;
+    if Version::from_str(version).is_ok() {
+        return None;
+    }
+    Some((name, version.to_string()))
+}
+
+/// Returns the on-disk metadata path of an installed distribution, i.e., the entry in its
+/// `site-packages` directory: the `.dist-info` or `.egg-info` directory (or `.egg-info` file),
+/// or, for legacy editable installs, the `.egg-link` file.
+fn metadata_path(dist: &InstalledDist) -> &Path {
+    match &dist.kind {
+        InstalledDistKind::LegacyEditable(dist) => &dist.egg_link,
+        _ => dist.install_path(),
+    }
+}
+
+/// Resolve the URL of an editable distribution that was recorded with a relative `file:` URL
+/// (e.g., `file:../project`) against the root of the environment.
+///
+/// Relative URLs aren't valid in `direct_url.json`, but some tools produce them anyway; parsed
+/// as-is, they'd be resolved against the filesystem root.
+fn resolve_relative_editable(mut dist: InstalledDist, root: &Path) -> InstalledDist {
+    let InstalledDistKind::Url(InstalledDirectUrlDist {
+        direct_url,
+        url,
+        editable: true,
+        ..
+    }) = &mut dist.kind
+    else {
+        return dist;
+    };
+    let DirectUrl::LocalDirectory {
+        url: installed_url, ..
+    } = direct_url.as_mut()
+    else {
+        return dist;
+    };
+    let Some(relative) = relative_file_url(installed_url) else {
+        return dist;
+    };
+
+    // On Windows, a drive-letter path (e.g., `file:C:/project`) is absolute.
+    let resolved = if Path::new(relative).is_absolute() {
+        Url::from_file_path(relative).ok()
+    } else {
+        Url::from_directory_path(root)
+            .ok()
+            .and_then(|root| root.join(relative).ok())
+    };
+    let Some(resolved) = resolved else {
+        return dist;
+    };
+    let Some(resolved) = resolved
+        .to_file_path()
+        .ok()
+        .and_then(|path| Url::from_file_path(normalize_path(&path)).ok())
+    else {
+        return dist;
+    };
+
+    debug!("Resolved relative editable URL `{installed_url}` to: `{resolved}`");
+    *installed_url = resolved.to_string();
+    if let Ok(resolved) = Url::try_from(direct_url.as_ref()) {
+        *url = DisplaySafeUrl::from(resolved);
+    }
+    dist
+}
+
+/// Returns the path component of a relative `file:` URL (e.g., `../project` for
+/// `file:../project` or `file://../project`), or `None` if the URL is absolute.
+fn relative_file_url(url: &str) -> Option<&str> {
+    let rest = url.strip_prefix("file:")?;
+    let rest = match rest.strip_prefix("//") {
+        // A relative path in place of the host, as in `file://./project`.
+        Some(rest) if rest.starts_with('.') => rest,
+        Some(_) => return None,
+        None => rest,
+    };
+    (!rest.is_empty() && !rest.starts_with('/')).then_some(rest)
+}
+
+/// Normalize the path of a local file URL (e.g., dropping trailing slashes and resolving `.` and
+/// `..` segments), such that URLs for the same directory compare equal.
+///
+/// Non-file URLs are returned unchanged.
+fn canonicalize_file_url(url: &DisplaySafeUrl) -> DisplaySafeUrl {
+    if url.scheme() != "file" {
+        return url.clone();
+    }
+    let Ok(path) = url.to_file_path() else {
+        return url.clone();
+    };
+    let Ok(mut canonical) = Url::from_file_path(normalize_path(&path)) else {
+        return url.clone();
+    };
+    canonical.set_query(url.query());
+    canonical.set_fragment(url.fragment());
+    DisplaySafeUrl::from(canonical)
+}
+
+/// Remove the distribution at the given index from the name and URL indexes.
+fn unindex_distribution(
+    by_name: &mut FxHashMap<PackageName, Vec<usize>>,
+    by_url: &mut FxHashMap<DisplaySafeUrl, Vec<usize>>,
+    dist_info: &InstalledDist,
+    index: usize,
+) {
+    if let Some(indexes) = by_name.get_mut(dist_info.name()) {
+        indexes.retain(|&idx| idx != index);
+        if indexes.is_empty() {
+            by_name.remove(dist_info.name());
+        }
+    }
+    if let InstalledDistKind::Url(dist) = &dist_info.kind {
+        let url = canonicalize_file_url(&dist.url);
+        if let Some(indexes) = by_url.get_mut(&url) {
+            indexes.retain(|&idx| idx != index);
+            if indexes.is_empty() {
+                by_url.remove(&url);
+            }
+        }
+    }
+}
+
+/// Add a distribution to the index, keyed by both name and (for direct URL distributions) URL.
+fn index_distribution(
+    distributions: &mut Vec<Option<InstalledDist>>,
+    by_name: &mut FxHashMap<PackageName, Vec<usize>>,
+    by_url: &mut FxHashMap<DisplaySafeUrl, Vec<usize>>,
+    dist_info: InstalledDist,
+) {
+    let idx = distributions.len();
+
+    // Index the distribution by name.
+    by_name
+        .entry(dist_info.name().clone())
+        .or_default()
+        .push(idx);
+
+    // Index the distribution by URL.
+    if let InstalledDistKind::Url(dist) = &dist_info.kind {
+        by_url
+            .entry(canonicalize_file_url(&dist.url))
+            .or_default()
+            .push(idx);
+    }
+
+    // Add the distribution to the database.
+    distributions.push(Some(dist_info));
+}
+
+/// Convert an installed distribution into a requirement that pins it.
+fn to_requirement(distribution: &InstalledDist) -> Requirement {
+    let registry = || RequirementSource::Registry {
+        specifier: VersionSpecifiers::from(VersionSpecifier::equals_version(
+            distribution.version().clone(),
+        )),
+        index: None,
+        conflict: None,
+    };
+    let source = match &distribution.kind {
+        InstalledDistKind::Registry(_)
+        | InstalledDistKind::EggInfoFile(_)
+        | InstalledDistKind::EggInfoDirectory(_) => registry(),
+        InstalledDistKind::Url(dist) => match ParsedUrl::try_from(dist.url.clone()) {
+            Ok(mut parsed_url) => {
+                if let ParsedUrl::Directory(directory) = &mut parsed_url {
+                    directory.editable = Some(dist.editable);
+                }
+                RequirementSource::from_parsed_url(
+                    parsed_url,
+                    VerbatimUrl::from_url(dist.url.clone()),
+                )
+            }
+            Err(err) => {
+                warn!("Failed to parse direct URL for `{}`: {err}", dist.name);
+                registry()
+            }
+        },
+        InstalledDistKind::LegacyEditable(dist) => RequirementSource::Directory {
+            install_path: dist.target.clone(),
+            editable: Some(true),
+            r#virtual: None,
+            url: VerbatimUrl::from_url(dist.target_url.clone()),
+        },
+    };
+    Requirement {
+        name: distribution.name().clone(),
+        extras: Box::default(),
+        groups: Box::default(),
+        marker: MarkerTree::TRUE,
+        source,
+        origin: None,
+    }
+}
+
+/// Read the absolute paths of the files listed in a distribution's `RECORD`, or `None` if the
+/// distribution doesn't have a `RECORD`.
+pub(crate) fn record_files(distribution: &InstalledDist) -> Result<Option<Vec<PathBuf>>> {
+    match &distribution.kind {
+        InstalledDistKind::Registry(_) | InstalledDistKind::Url(_) => {}
+        InstalledDistKind::EggInfoFile(_)
+        | InstalledDistKind::EggInfoDirectory(_)
+        | InstalledDistKind::LegacyEditable(_) => return Ok(None),
+    }
+    let Some(site_packages) = distribution.install_path().parent() else {
+        return Ok(None);
+    };
+    let record = match fs::File::open(distribution.install_path().join("RECORD")) {
+        Ok(record) => record,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let record = read_record_file(&mut BufReader::new(record))?;
+    Ok(Some(
+        record
+            .into_iter()
+            .map(|entry| normalize_path(&site_packages.join(entry.path)).into_owned())
+            .collect(),
+    ))
+}
+
+/// Returns the importable modules provided by a distribution, as derived from its `RECORD`.
+///
+/// Each file is attributed to its top-level package or module, descending through any directories
+/// that lack an `__init__.py` (i.e., namespace packages). Non-Python files at the top level (e.g.,
+/// `.pth` files) and files outside of `site-packages` (e.g., scripts) are ignored.
+fn top_level_modules(distribution: &InstalledDist) -> BTreeSet<String> {
+    let Ok(Some(files)) = record_files(distribution) else {
+        return BTreeSet::new();
+    };
+    let Some(site_packages) = distribution.install_path().parent() else {
+        return BTreeSet::new();
+    };
+    let files = files
+        .iter()
+        .filter_map(|path| {
+            let components = path
+                .strip_prefix(site_packages)
+                .ok()?
+                .components()
+                .map(|component| component.as_os_str().to_str())
+                .collect::<Option<Vec<_>>>()?;
+            let first = components.first()?;
+            let is_metadata = Path::new(first)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| matches!(extension, "dist-info" | "data" | "egg-info"));
+            if is_metadata || components.contains(&"__pycache__") {
+                return None;
+            }
+            Some(components)
+        })
+        .collect::<Vec<_>>();
+
+    // Identify the regular packages, i.e., the directories that contain an `__init__.py`.
+    let packages = files
+        .iter()
+        .filter(|components| components.last() == Some(&"__init__.py"))
+        .map(|components| &components[..components.len() - 1])
+        .collect::<FxHashSet<_>>();
+
+    let mut modules = BTreeSet::new();
+    for components in &files {
+        for index in 0..components.len() {
+            if index == components.len() - 1 {
+                let file = components[index];
+                let Some((stem, extension)) = file.split_once('.') else {
+                    break;
+                };
+                let is_module = extension == "py"
+                    || Path::new(file).extension().is_some_and(|extension| {
+                        extension.eq_ignore_ascii_case("so")
+                            || extension.eq_ignore_ascii_case("pyd")
+                    });
+                if is_module {
+                    let mut module = components[..index].to_vec();
+                    module.push(stem);
+                    modules.insert(module.join("."));
+                }
+            } else if packages.contains(&components[..=index]) {
+                modules.insert(components[..=index].join("."));
+            } else {
+                continue;
+            }
+            break;
+        }
+    }
+    modules
+}
+
+/// Returns the path to the distribution's core metadata file: `METADATA` for a `.dist-info`
+/// directory, or `PKG-INFO` for an `.egg-info` directory.
+fn metadata_file(distribution: &InstalledDist) -> Cow<'_, Path> {
+    match &distribution.kind {
+        InstalledDistKind::Registry(_) | InstalledDistKind::Url(_) => {
+            Cow::Owned(distribution.install_path().join("METADATA"))
+        }
+        InstalledDistKind::EggInfoFile(dist) => Cow::Borrowed(&dist.path),
+        InstalledDistKind::EggInfoDirectory(dist) => Cow::Owned(dist.path.join("PKG-INFO")),
+        InstalledDistKind::LegacyEditable(dist) => Cow::Owned(dist.egg_info.join("PKG-INFO")),
+    }
 }
 
 impl IntoIterator for SitePackages {
@@ -608,125 +1837,1850 @@ impl IntoIterator for SitePackages {
     }
 }
 
-#[derive(Debug)]
-pub enum SitePackagesDiagnostic {
-    MetadataUnavailable {
-        /// The package that is missing metadata.
-        package: PackageName,
-        /// The path to the package.
-        path: PathBuf,
-    },
-    TagsUnavailable {
-        /// The package that is missing tags.
-        package: PackageName,
-        /// The path to the package.
-        path: PathBuf,
-    },
-    IncompatiblePythonVersion {
-        /// The package that requires a different version of Python.
-        package: PackageName,
-        /// The version of Python that is installed.
-        version: Version,
-        /// The version of Python that is required.
-        requires_python: VersionSpecifiers,
-    },
-    IncompatiblePlatform {
-        /// The package that was built for a different platform.
-        package: PackageName,
-    },
-    MissingDependency {
-        /// The package that is missing a dependency.
-        package: PackageName,
-        /// The dependency that is missing.
-        requirement: uv_pep508::Requirement<VerbatimParsedUrl>,
-    },
-    IncompatibleDependency {
-        /// The package that has an incompatible dependency.
-        package: PackageName,
-        /// The version of the package that is installed.
-        version: Version,
-        /// The dependency that is incompatible.
-        requirement: uv_pep508::Requirement<VerbatimParsedUrl>,
-    },
-    DuplicatePackage {
-        /// The package that has multiple installed distributions.
-        package: PackageName,
-        /// The installed versions of the package.
-        paths: Vec<PathBuf>,
-    },
+impl InstalledPackagesProvider for SitePackages {
+    fn iter(&self) -> impl Iterator<Item = &InstalledDist> {
+        self.iter()
+    }
+
+    fn get_packages(&self, name: &PackageName) -> Vec<&InstalledDist> {
+        self.get_packages(name)
+    }
 }
 
-impl Diagnostic for SitePackagesDiagnostic {
-    /// Convert the diagnostic into a user-facing message.
-    fn message(&self) -> String {
-        match self {
-            Self::MetadataUnavailable { package, path } => format!(
-                "The package `{package}` is broken or incomplete (unable to read `METADATA`). Consider recreating the virtualenv, or removing the package directory at: {}.",
-                path.display(),
-            ),
-            Self::TagsUnavailable { package, path } => format!(
-                "The package `{package}` is broken or incomplete (unable to read `WHEEL` file). Consider recreating the virtualenv, or removing the package directory at: {}.",
-                path.display(),
-            ),
-            Self::IncompatiblePythonVersion {
-                package,
-                version,
-                requires_python,
-            } => format!(
-                "The package `{package}` requires Python {requires_python}, but `{version}` is installed"
-            ),
-            Self::IncompatiblePlatform { package } => {
-                format!("The package `{package}` was built for a different platform")
-            }
-            Self::MissingDependency {
-                package,
-                requirement,
-            } => {
-                format!("The package `{package}` requires `{requirement}`, but it's not installed")
-            }
-            Self::IncompatibleDependency {
-                package,
-                version,
-                requirement,
-            } => format!(
-                "The package `{package}` requires `{requirement}`, but `{version}` is installed"
+#[cfg(all(test, unix))]
+pub(crate) mod tests {
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use anyhow::Result;
+    use fs_err as fs;
+    use indoc::formatdoc;
+    use tempfile::TempDir;
+
+    use uv_cache::Cache;
+    use uv_distribution_types::{
+        ConfigSettings, Diagnostic, ExtraBuildRequires, ExtraBuildVariables, InstalledDist,
+        InstalledDistKind, Name, Node, PackageConfigSettings, Requirement, RequirementSource,
+        Resolution, ResolvedDist,
+    };
+    use uv_fs::normalize_path;
+    use uv_normalize::{ExtraName, PackageName};
+    use uv_pep440::{Version, VersionSpecifiers};
+    use uv_platform_tags::Tags;
+    use uv_pypi_types::{DirectUrl, HashDigests, VerbatimParsedUrl};
+    use uv_python::Interpreter;
+    use uv_redacted::DisplaySafeUrl;
+    use uv_types::InstalledPackagesProvider;
+
+    use super::{Cancelled, CompatibilityPolicy, SitePackages, Timestamp};
+    use crate::SitePackagesDiagnostic;
+
+    /// Create a mocked interpreter with the given `purelib` and `platlib` directories.
+    pub(crate) fn mock_interpreter(
+        root: &Path,
+        purelib: &Path,
+        platlib: &Path,
+    ) -> Result<Interpreter> {
+        mock_interpreter_with_gil(root, purelib, platlib, false)
+    }
+
+    /// Create a mock interpreter, with the GIL disabled (i.e., a free-threaded build) if requested.
+    pub(crate) fn mock_interpreter_with_gil(
+        root: &Path,
+        purelib: &Path,
+        platlib: &Path,
+        gil_disabled: bool,
+    ) -> Result<Interpreter> {
+        let executable = root.join("python");
+        let site_packages = if purelib == platlib {
+            format!(r#""{}""#, purelib.display())
+        } else {
+            format!(r#""{}", "{}""#, purelib.display(), platlib.display())
+        };
+        let purelib = purelib.display();
+        let platlib = platlib.display();
+        let root = root.display();
+        let json = formatdoc! {r##"
+        {{
+            "result": "success",
+            "platform": {{
+                "os": {{
+                    "name": "manylinux",
+                    "major": 2,
+                    "minor": 38
+                }},
+                "arch": "x86_64"
+            }},
+            "manylinux_compatible": true,
+            "standalone": false,
+            "markers": {{
+                "implementation_name": "cpython",
+                "implementation_version": "3.12.0",
+                "os_name": "posix",
+                "platform_machine": "x86_64",
+                "platform_python_implementation": "CPython",
+                "platform_release": "6.5.0-13-generic",
+                "platform_system": "Linux",
+                "platform_version": "#13-Ubuntu SMP PREEMPT_DYNAMIC Fri Nov  3 12:16:05 UTC 2023",
+                "python_full_version": "3.12.0",
+                "python_version": "3.12",
+                "sys_platform": "linux"
+            }},
+            "sys_base_exec_prefix": "{root}",
+            "sys_base_prefix": "{root}",
+            "sys_prefix": "{root}",
+            "sys_executable": "{root}/python",
+            "sys_path": [
+                {site_packages}
+            ],
+            "site_packages": [
+                {site_packages}
+            ],
+            "stdlib": "{root}/lib/python3.12",
+            "scheme": {{
+                "data": "{root}",
+                "include": "{root}/include",
+                "platlib": "{platlib}",
+                "purelib": "{purelib}",
+                "scripts": "{root}/bin"
+            }},
+            "virtualenv": {{
+                "data": "",
+                "include": "include",
+                "platlib": "lib/python3.12/site-packages",
+                "purelib": "lib/python3.12/site-packages",
+                "scripts": "bin"
+            }},
+            "pointer_size": "64",
+            "gil_disabled": {gil_disabled},
+            "debug_enabled": false
+        }}
+        "##};
+        fs::write(
+            &executable,
+            formatdoc! {r"
+            #!/bin/sh
+            echo '{json}'
+            "},
+        )?;
+        fs::set_permissions(
+            &executable,
+            std::os::unix::fs::PermissionsExt::from_mode(0o770),
+        )?;
+        let cache = Cache::temp()?.init()?;
+        Ok(Interpreter::query(&executable, &cache)?)
+    }
+
+    /// Write a `.dist-info` directory for the given package to `site_packages`.
+    pub(crate) fn install(
+        site_packages: &Path,
+        name: &str,
+        version: &str,
+        requires_dist: &[&str],
+    ) -> Result<()> {
+        let dist_info = site_packages.join(format!("{name}-{version}.dist-info"));
+        fs::create_dir_all(&dist_info)?;
+        let mut metadata = format!("Metadata-Version: 2.1\nName: {name}\nVersion: {version}\n");
+        for requirement in requires_dist {
+            writeln!(metadata, "Requires-Dist: {requirement}")?;
+        }
+        fs::write(dist_info.join("METADATA"), metadata)?;
+        fs::write(
+            dist_info.join("WHEEL"),
+            "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n",
+        )?;
+        Ok(())
+    }
+
+    /// Write an editable `.dist-info` directory for the given package, with a `.pth` file that
+    /// adds `path_entry` to `sys.path`.
+    pub(crate) fn install_editable(
+        site_packages: &Path,
+        name: &str,
+        version: &str,
+        project: &Path,
+        path_entry: &Path,
+    ) -> Result<()> {
+        install(site_packages, name, version, &[])?;
+        let dist_info = site_packages.join(format!("{name}-{version}.dist-info"));
+        let url = url::Url::from_directory_path(project).unwrap();
+        fs::write(
+            dist_info.join("direct_url.json"),
+            format!(r#"{{"url": "{url}", "dir_info": {{"editable": true}}}}"#),
+        )?;
+        let pth = format!("__editable__.{name}-{version}.pth");
+        fs::write(
+            site_packages.join(&pth),
+            format!("{}\n", path_entry.display()),
+        )?;
+        fs::write(
+            dist_info.join("RECORD"),
+            format!("{pth},,\n{name}-{version}.dist-info/METADATA,,\n"),
+        )?;
+        Ok(())
+    }
+
+    /// An environment rooted in a temporary directory, with a single `site-packages` directory
+    /// (or, if split, separate `purelib` and `platlib` directories).
+    pub(crate) struct MockEnvironment {
+        pub(crate) root: TempDir,
+        split: bool,
+    }
+
+    impl MockEnvironment {
+        pub(crate) fn new() -> Result<Self> {
+            let root = TempDir::new()?;
+            fs::create_dir_all(root.path().join("site-packages"))?;
+            Ok(Self { root, split: false })
+        }
+
+        pub(crate) fn split() -> Result<Self> {
+            let root = TempDir::new()?;
+            fs::create_dir_all(root.path().join("site-packages"))?;
+            fs::create_dir_all(root.path().join("platlib"))?;
+            Ok(Self { root, split: true })
+        }
+
+        pub(crate) fn site_packages(&self) -> PathBuf {
+            self.root.path().join("site-packages")
+        }
+
+        pub(crate) fn platlib(&self) -> PathBuf {
+            if self.split {
+                self.root.path().join("platlib")
+            } else {
+                self.site_packages()
+            }
+        }
+
+        pub(crate) fn install(
+            &self,
+            name: &str,
+            version: &str,
+            requires_dist: &[&str],
+        ) -> Result<()> {
+            install(&self.site_packages(), name, version, requires_dist)
+        }
+
+        pub(crate) fn interpreter(&self) -> Result<Interpreter> {
+            mock_interpreter(self.root.path(), &self.site_packages(), &self.platlib())
+        }
+
+        pub(crate) fn index(&self) -> Result<SitePackages> {
+            SitePackages::from_interpreter(&self.interpreter()?)
+        }
+
+        pub(crate) fn diagnostics(&self) -> Result<Vec<SitePackagesDiagnostic>> {
+            let interpreter = self.interpreter()?;
+            let site_packages =
+                SitePackages::from_interpreter(&interpreter)?.with_file_checks(true);
+            site_packages.diagnostics(
+                &interpreter.resolver_marker_environment(),
+                &Tags::new(vec![]),
+            )
+        }
+    }
+
+    pub(crate) fn name(name: &str) -> PackageName {
+        PackageName::from_str(name).unwrap()
+    }
+
+    pub(crate) fn requirement(requirement: &str) -> Requirement {
+        Requirement::from(
+            uv_pep508::Requirement::<VerbatimParsedUrl>::from_str(requirement).unwrap(),
+        )
+    }
+
+    #[test]
+    fn overlay_shadows_base() -> Result<()> {
+        let base = MockEnvironment::new()?;
+        base.install("anyio", "4.0.0", &[])?;
+        base.install("idna", "3.5", &[])?;
+
+        let overlay = MockEnvironment::new()?;
+        overlay.install("anyio", "4.3.0", &[])?;
+
+        let site_packages = SitePackages::overlay(base.index()?, overlay.index()?);
+
+        let anyio = site_packages.get_packages(&name("anyio"));
+        assert_eq!(anyio.len(), 1);
+        assert_eq!(anyio[0].version().to_string(), "4.3.0");
+        assert!(anyio[0].install_path().starts_with(overlay.site_packages()));
+
+        let idna = site_packages.get_packages(&name("idna"));
+        assert_eq!(idna.len(), 1);
+        assert_eq!(idna[0].name(), &name("idna"));
+
+        assert_eq!(
+            site_packages.interpreter().sys_prefix(),
+            overlay.root.path()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn overlay_preserves_duplicates_within_layer() -> Result<()> {
+        let base = MockEnvironment::new()?;
+        base.install("idna", "3.5", &[])?;
+        base.install("idna", "3.6", &[])?;
+
+        let overlay = MockEnvironment::new()?;
+        overlay.install("anyio", "4.3.0", &[])?;
+
+        let site_packages = SitePackages::overlay(base.index()?, overlay.index()?);
+        assert_eq!(site_packages.get_packages(&name("idna")).len(), 2);
+        assert_eq!(site_packages.get_packages(&name("anyio")).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn installed_extras() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install(
+            "anyio",
+            "4.3.0",
+            &[
+                "idna>=2.8",
+                "trio>=0.23; extra == 'trio'",
+                "sniffio; extra == 'trio'",
+                "sphinx; extra == 'doc'",
+                "pytest>=8; extra == 'test'",
+                "pywin32; extra == 'windows' and sys_platform == 'win32'",
+            ],
+        )?;
+        env.install("idna", "3.6", &[])?;
+        env.install("trio", "0.25.0", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        env.install("pytest", "7.4.0", &[])?;
+        env.install("httpx", "0.27.0", &["anyio", "brotli; extra == 'brotli'"])?;
+
+        // Declare the extras, which the mock installation omits.
+        for (dist_info, extras) in [
+            (
+                "anyio-4.3.0.dist-info",
+                &["trio", "doc", "test", "windows"][..],
             ),
-            Self::DuplicatePackage { package, paths } => {
-                let mut paths = paths.clone();
-                paths.sort();
+            ("httpx-0.27.0.dist-info", &["brotli"][..]),
+        ] {
+            let path = env.site_packages().join(dist_info).join("METADATA");
+            let mut metadata = fs::read_to_string(&path)?;
+            for extra in extras {
+                writeln!(metadata, "Provides-Extra: {extra}")?;
+            }
+            fs::write(&path, metadata)?;
+        }
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let installed_extras =
+            site_packages.installed_extras(&interpreter.resolver_marker_environment());
+
+        // `doc` is missing `sphinx`, `test` has an incompatible `pytest`, and `windows` doesn't
+        // add any dependencies on Linux. `httpx` and `idna` weren't installed with any extras.
+        assert_eq!(
+            installed_extras.into_iter().collect::<BTreeMap<_, _>>(),
+            BTreeMap::from([(name("anyio"), vec![ExtraName::from_str("trio")?])])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dependency_graph() -> Result<()> {
+        /// A policy under which any installed version is compatible.
+        #[derive(Debug)]
+        struct AnyVersionPolicy;
+
+        impl CompatibilityPolicy for AnyVersionPolicy {
+            fn is_compatible(&self, _installed: &Version, _specifiers: &VersionSpecifiers) -> bool {
+                true
+            }
+        }
+
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        env.install("httpx", "0.27.0", &["anyio", "idna<3", "certifi"])?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let graph = site_packages.dependency_graph(&interpreter.resolver_marker_environment());
+
+        // `idna<3` isn't satisfied, and `certifi` isn't installed.
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 3);
+
+        let edge = graph
+            .edges()
+            .find(|edge| {
+                edge.source.name() == &name("anyio") && edge.target.name() == &name("idna")
+            })
+            .expect("`anyio` should depend on `idna`");
+        assert_eq!(
+            edge.specifier().map(ToString::to_string).as_deref(),
+            Some(">=2.8")
+        );
+
+        // Edges respect the compatibility policy, like `satisfies_requirements`.
+        let site_packages = site_packages.with_compatibility_policy(AnyVersionPolicy);
+        let graph = site_packages.dependency_graph(&interpreter.resolver_marker_environment());
+        assert_eq!(graph.edge_count(), 4);
+        assert!(graph.edges().any(|edge| {
+            edge.source.name() == &name("httpx") && edge.target.name() == &name("idna")
+        }));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_by_local_version() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("torch", "2.1.0+cu118", &[])?;
+        env.install("torchvision", "0.16.0+cu118", &[])?;
+        env.install("torchaudio", "2.1.0+cpu", &[])?;
+        env.install("numpy", "1.26.4", &[])?;
+
+        let site_packages = env.index()?;
+        let mut cuda = site_packages
+            .filter(|dist| dist.version().local().to_string() == "cu118")
+            .into_iter()
+            .map(|dist| dist.name().to_string())
+            .collect::<Vec<_>>();
+        cuda.sort();
+        assert_eq!(cuda, ["torch", "torchvision"]);
+
+        assert!(site_packages.filter(|_| false).is_empty());
+        assert_eq!(site_packages.filter(|_| true).len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn cancellation() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8"])?;
+        env.install("httpx", "0.27.0", &["certifi"])?;
+        env.install("requests", "2.31.0", &["urllib3"])?;
+        let interpreter = env.interpreter()?;
+
+        // A scan that's cancelled before it starts fails immediately.
+        let cancel = AtomicBool::new(true);
+        let err = SitePackages::from_interpreter_cancellable(&interpreter, &cancel).unwrap_err();
+        assert!(err.is::<Cancelled>());
+
+        // Otherwise, the index is identical to that of an uncancellable scan.
+        cancel.store(false, Ordering::Relaxed);
+        let site_packages = SitePackages::from_interpreter_cancellable(&interpreter, &cancel)?;
+        assert_eq!(
+            site_packages.dist_info_paths(),
+            env.index()?.dist_info_paths()
+        );
+
+        // Cancel the diagnostics after the first package that's missing a dependency.
+        let mut diagnostics = Vec::new();
+        let err = site_packages
+            .diagnostics_stream_cancellable(
+                &interpreter.resolver_marker_environment(),
+                &Tags::new(vec![]),
+                &cancel,
+                |diagnostic| {
+                    if matches!(diagnostic, SitePackagesDiagnostic::MissingDependency { .. }) {
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                    diagnostics.push(diagnostic);
+                },
+            )
+            .unwrap_err();
+        assert!(err.is::<Cancelled>());
+        assert!(
+            diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.includes(&name("anyio")))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn provider_excluding() -> Result<()> {
+        /// Return the installed versions of the given packages, as the resolver would.
+        fn installed<P: InstalledPackagesProvider>(provider: &P, names: &[&str]) -> Vec<String> {
+            names
+                .iter()
+                .flat_map(|package| provider.get_packages(&name(package)))
+                .map(|dist| format!("{}=={}", dist.name(), dist.version()))
+                .collect()
+        }
+
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        let site_packages = Arc::new(env.index()?);
+
+        let provider = site_packages.provider_excluding([name("idna")]);
+        assert_eq!(
+            installed(&provider, &["anyio", "idna", "sniffio"]),
+            vec!["anyio==4.3.0", "sniffio==1.3.1"]
+        );
+        assert_eq!(provider.iter().count(), 2);
+
+        // The underlying index is unaffected.
+        assert_eq!(
+            installed(&site_packages, &["anyio", "idna", "sniffio"]),
+            vec!["anyio==4.3.0", "idna==3.6", "sniffio==1.3.1"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn version_of() -> Result<()> {
+        let env = MockEnvironment::split()?;
+        env.install("numpy", "1.26.4", &[])?;
+        env.install("torch", "2.2.1", &[])?;
+        install(&env.platlib(), "torch", "2.0.0", &[])?;
+        let site_packages = env.index()?;
+
+        assert_eq!(
+            site_packages.version_of(&name("numpy")),
+            Some(&Version::from_str("1.26.4")?)
+        );
+        assert_eq!(site_packages.version_of(&name("tensorflow")), None);
+
+        // For duplicates, the first distribution on `sys.path` wins.
+        assert_eq!(
+            site_packages.version_of(&name("torch")),
+            Some(&Version::from_str("2.2.1")?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn any_installed() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("numpy", "1.26.4", &[])?;
+        env.install("torch", "2.2.1", &[])?;
+        let mut site_packages = env.index()?;
+
+        assert!(site_packages.any_installed(&[name("tensorflow"), name("torch")]));
+        assert!(!site_packages.any_installed(&[name("tensorflow"), name("jax")]));
+        assert!(!site_packages.any_installed(&[]));
+
+        // Removed packages are no longer installed.
+        site_packages.remove_packages(&name("torch"));
+        assert!(!site_packages.any_installed(&[name("tensorflow"), name("torch")]));
+        Ok(())
+    }
+
+    #[test]
+    fn preload_metadata() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        env.install("anyio", "4.3.0", &["idna>=2.8"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("broken", "1.0.0", &[])?;
+        fs::write(
+            site_packages.join("broken-1.0.0.dist-info/METADATA"),
+            "Metadata-Version: 2.1\n",
+        )?;
+
+        let index = env.index()?;
+        index.preload_metadata();
+
+        // Remove the metadata from disk, such that any subsequent read would fail.
+        for dist_info in ["anyio-4.3.0.dist-info", "idna-3.6.dist-info"] {
+            fs::remove_file(site_packages.join(dist_info).join("METADATA"))?;
+            fs::remove_file(site_packages.join(dist_info).join("WHEEL"))?;
+        }
+        let anyio = &index.get_packages(&name("anyio"))[0];
+        assert_eq!(anyio.read_metadata()?.requires_dist.len(), 1);
+        assert!(anyio.read_tags()?.is_some());
+        let graph = index.dependency_graph(&env.interpreter()?.resolver_marker_environment());
+        assert_eq!(graph.edge_count(), 1);
+
+        // Failures are cached, too, even once the metadata is fixed on disk.
+        env.install("broken", "1.0.0", &[])?;
+        let broken = &index.get_packages(&name("broken"))[0];
+        assert!(broken.read_metadata().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn file_counts() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        env.install("idna", "3.6", &[])?;
+        fs::write(
+            site_packages.join("idna-3.6.dist-info/RECORD"),
+            indoc::indoc! {"
+                idna/__init__.py,sha256=abc,849
+                idna/codec.py,sha256=abc,3422
+                idna/core.py,sha256=abc,12663
+                idna-3.6.dist-info/METADATA,sha256=abc,9911
+                idna-3.6.dist-info/RECORD,,
+            "},
+        )?;
+        env.install("anyio", "4.3.0", &[])?;
+        fs::write(
+            site_packages.join("anyio-4.3.0.dist-info/RECORD"),
+            "anyio/__init__.py,,\nanyio-4.3.0.dist-info/RECORD,,\n",
+        )?;
+
+        // A package without a `RECORD`.
+        env.install("sniffio", "1.3.1", &[])?;
+
+        let site_packages = env.index()?;
+        assert_eq!(
+            site_packages.file_counts(),
+            vec![
+                (name("anyio"), Some(2)),
+                (name("idna"), Some(5)),
+                (name("sniffio"), None),
+            ]
+        );
+        assert_eq!(site_packages.total_file_count(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn iter_sorted() -> Result<()> {
+        let env = MockEnvironment::split()?;
+        env.install("zipp", "3.18.0", &[])?;
+        install(&env.platlib(), "anyio", "4.3.0", &[])?;
+        env.install("idna", "3.6", &[])?;
+        install(&env.platlib(), "idna", "3.4", &[])?;
+        env.install("anyio", "4.0.0", &[])?;
+
+        let site_packages = env.index()?;
+        assert_eq!(
+            site_packages
+                .iter_sorted()
+                .map(|dist| format!("{}=={}", dist.name(), dist.version()))
+                .collect::<Vec<_>>(),
+            vec![
+                "anyio==4.0.0",
+                "anyio==4.3.0",
+                "idna==3.4",
+                "idna==3.6",
+                "zipp==3.18.0"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diff() -> Result<()> {
+        let before = MockEnvironment::new()?;
+        before.install("anyio", "4.0.0", &[])?;
+        before.install("idna", "3.6", &[])?;
+        before.install("sniffio", "1.3.1", &[])?;
+        let project = before.root.path().join("project");
+        fs::create_dir_all(&project)?;
+        install_editable(&before.site_packages(), "foo", "0.1.0", &project, &project)?;
+
+        let after = MockEnvironment::new()?;
+        after.install("anyio", "4.3.0", &[])?;
+        after.install("httpx", "0.27.0", &[])?;
+        after.install("idna", "3.6", &[])?;
+        let moved = after.root.path().join("project");
+        fs::create_dir_all(&moved)?;
+        install_editable(&after.site_packages(), "foo", "0.1.0", &moved, &moved)?;
+
+        let (before, after) = (before.index()?, after.index()?);
+        let diff = before.diff(&after);
+        let format = |dist: &InstalledDist| format!("{}=={}", dist.name(), dist.version());
+        assert_eq!(
+            diff.added
+                .iter()
+                .map(|dist| format(dist))
+                .collect::<Vec<_>>(),
+            vec!["httpx==0.27.0"]
+        );
+        assert_eq!(
+            diff.removed
+                .iter()
+                .map(|dist| format(dist))
+                .collect::<Vec<_>>(),
+            vec!["sniffio==1.3.1"]
+        );
+        assert_eq!(
+            diff.changed
+                .iter()
+                .map(|(before, after)| format!("{} -> {}", format(before), format(after)))
+                .collect::<Vec<_>>(),
+            vec!["anyio==4.0.0 -> anyio==4.3.0", "foo==0.1.0 -> foo==0.1.0"]
+        );
+
+        // An environment doesn't differ from itself.
+        assert!(before.diff(&before).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn requires() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install(
+            "anyio",
+            "4.3.0",
+            &[
+                "idna>=2.8",
+                "sniffio>=1.1 ; python_version >= '3'",
+                "colorama ; sys_platform == 'never'",
+                "trio>=0.23 ; extra == 'trio'",
+            ],
+        )?;
+
+        let interpreter = env.interpreter()?;
+        let site_packages = SitePackages::from_interpreter(&interpreter)?;
+        let markers = interpreter.resolver_marker_environment();
+        let requires = |package: &str| {
+            site_packages
+                .requires(&name(package), &markers)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            requires("anyio"),
+            vec!["idna>=2.8", "sniffio>=1.1 ; python_full_version >= '3'"]
+        );
+        assert!(requires("idna").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn uninstall_order() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install(
+            "app",
+            "1.0.0",
+            &["httpx", "pywin32; sys_platform == 'win32'"],
+        )?;
+        env.install("httpx", "0.27.0", &["anyio", "idna"])?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        env.install("pywin32", "306", &["app"])?;
+
+        let site_packages = env.index()?;
+        let order = |names: &[&str]| {
+            let names = names
+                .iter()
+                .map(|package| name(package))
+                .collect::<Vec<_>>();
+            site_packages
+                .uninstall_order(&names)
+                .into_iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Dependents are uninstalled before their dependencies.
+        assert_eq!(
+            order(&["idna", "anyio", "httpx", "app"]),
+            vec!["app", "httpx", "anyio", "idna"]
+        );
+
+        // Packages outside the requested set are ignored, and ties are broken by name.
+        assert_eq!(
+            order(&["sniffio", "idna", "httpx"]),
+            vec!["httpx", "idna", "sniffio"]
+        );
+
+        // Dependencies that don't apply to the environment are ignored.
+        assert_eq!(order(&["pywin32", "app"]), vec!["pywin32", "app"]);
+
+        // Packages that aren't installed are still included.
+        assert_eq!(order(&["missing", "idna"]), vec!["idna", "missing"]);
+        Ok(())
+    }
+
+    #[test]
+    fn uninstall_order_cycle() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("app", "1.0.0", &["sphinx"])?;
+        env.install("sphinx", "7.0.0", &["sphinxcontrib-serializinghtml"])?;
+        env.install("sphinxcontrib_serializinghtml", "1.1.9", &["sphinx"])?;
+
+        // The cycle is broken at the package with the lowest name, after any dependents.
+        let order = env
+            .index()?
+            .uninstall_order(&[
+                name("sphinxcontrib-serializinghtml"),
+                name("sphinx"),
+                name("app"),
+            ])
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec!["app", "sphinx", "sphinxcontrib-serializinghtml"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn closure() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install(
+            "anyio",
+            "4.3.0",
+            &["idna>=2.8", "sniffio>=1.1", "trio>=0.23; extra == 'trio'"],
+        )?;
+        env.install("idna", "2.0", &[])?;
+        env.install("sniffio", "1.3.1", &["missing"])?;
+        env.install("trio", "0.25.0", &["outcome", "sniffio"])?;
+        env.install("outcome", "1.3.0", &[])?;
+        env.install(
+            "app",
+            "1.0.0",
+            &["anyio[trio]", "pywin32; sys_platform == 'win32'"],
+        )?;
+        env.install("pywin32", "306", &[])?;
+
+        let site_packages = env.index()?;
+        let markers = env.interpreter()?.resolver_marker_environment();
+        let closure = |roots: &[&str]| {
+            let roots = roots.iter().map(|root| name(root)).collect::<Vec<_>>();
+            let mut closure = site_packages
+                .closure(&roots, &markers)
+                .into_iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>();
+            closure.sort();
+            closure
+        };
+
+        // Mismatched versions (`idna`) are followed, but missing packages and inapplicable
+        // markers are not.
+        assert_eq!(closure(&["anyio"]), vec!["anyio", "idna", "sniffio"]);
+
+        // Extras requested by a dependency are followed.
+        assert_eq!(
+            closure(&["app"]),
+            vec!["anyio", "app", "idna", "outcome", "sniffio", "trio"]
+        );
+
+        // Roots that aren't installed are omitted.
+        assert_eq!(closure(&["missing", "outcome"]), vec!["outcome"]);
+        Ok(())
+    }
+
+    #[test]
+    fn license() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        let install = |name: &str, headers: &str| -> Result<()> {
+            let dist_info = site_packages.join(format!("{name}-1.0.0.dist-info"));
+            fs::create_dir_all(&dist_info)?;
+            fs::write(
+                dist_info.join("METADATA"),
+                format!("Metadata-Version: 2.4\nName: {name}\nVersion: 1.0.0\n{headers}"),
+            )?;
+            Ok(())
+        };
+
+        // An expression takes precedence over the field and classifiers.
+        install(
+            "expression",
+            "License-Expression: MIT OR Apache-2.0\nLicense: BSD\nClassifier: License :: OSI Approved :: BSD License\n",
+        )?;
+        install(
+            "field",
+            "License: BSD-3-Clause\nClassifier: License :: OSI Approved :: MIT License\n",
+        )?;
+        install(
+            "classifiers",
+            "License: UNKNOWN\nClassifier: Programming Language :: Python\nClassifier: License :: OSI Approved :: MIT License\nClassifier: License :: OSI Approved :: Apache Software License\n",
+        )?;
+        install("missing", "Classifier: License :: OSI Approved\n")?;
+
+        let index = env.index()?;
+        assert_eq!(
+            index.license(&name("expression")).as_deref(),
+            Some("MIT OR Apache-2.0")
+        );
+        assert_eq!(
+            index.license(&name("field")).as_deref(),
+            Some("BSD-3-Clause")
+        );
+        assert_eq!(
+            index.license(&name("classifiers")).as_deref(),
+            Some("MIT License, Apache Software License")
+        );
+        assert_eq!(index.license(&name("missing")), None);
+        assert_eq!(index.license(&name("uninstalled")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn reverse_dependencies() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("idna", "3.6", &[])?;
+        env.install("anyio", "4.3.0", &["idna>=2.8", "sniffio>=1.1"])?;
+        env.install("requests", "2.31.0", &["idna<4,>=2.5"])?;
+        env.install("httpx", "0.27.0", &["idna; extra == 'idna'", "anyio"])?;
+        env.install("legacy", "1.0.0", &["idna; python_version < '3'"])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        let site_packages = env.index()?;
+        let markers = env.interpreter()?.resolver_marker_environment();
+
+        let mut dependents = site_packages
+            .reverse_dependencies(&name("idna"), &markers)
+            .into_iter()
+            .map(|(dist, requirement)| (dist.name().to_string(), requirement.to_string()))
+            .collect::<Vec<_>>();
+        dependents.sort();
+        assert_eq!(
+            dependents,
+            vec![
+                ("anyio".to_string(), "idna>=2.8".to_string()),
+                ("httpx".to_string(), "idna ; extra == 'idna'".to_string()),
+                ("requests".to_string(), "idna>=2.5,<4".to_string()),
+            ]
+        );
+        assert!(
+            site_packages
+                .reverse_dependencies(&name("httpx"), &markers)
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dist_info_paths() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        env.install("foo", "1.0.0", &[])?;
+
+        // An `.egg-info` directory.
+        let egg_info = site_packages.join("bar-2.0.0.egg-info");
+        fs::create_dir_all(&egg_info)?;
+        fs::write(
+            egg_info.join("PKG-INFO"),
+            "Metadata-Version: 1.0\nName: bar\nVersion: 2.0.0\n",
+        )?;
+
+        // A legacy editable, linked via an `.egg-link` file.
+        let project = env.root.path().join("project");
+        fs::create_dir_all(project.join("baz.egg-info"))?;
+        fs::write(
+            project.join("baz.egg-info").join("PKG-INFO"),
+            "Metadata-Version: 1.0\nName: baz\nVersion: 0.1.0\n",
+        )?;
+        let egg_link = site_packages.join("baz.egg-link");
+        fs::write(&egg_link, format!("{}\n.\n", project.display()))?;
+
+        let index = env.index()?;
+        let mut paths = index.dist_info_paths();
+        paths.sort();
+        let mut expected = vec![
+            site_packages.join("foo-1.0.0.dist-info"),
+            egg_info,
+            egg_link,
+        ];
+        expected.sort();
+        assert_eq!(paths, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn uppercase_extensions() -> Result<()> {
+        let env = MockEnvironment::new()?;
+
+        // An `.egg-info` directory, as written by legacy tooling on a case-preserving filesystem.
+        let egg_info = env.site_packages().join("foo-1.0.0.EGG-INFO");
+        fs::create_dir_all(&egg_info)?;
+        fs::write(
+            egg_info.join("PKG-INFO"),
+            "Metadata-Version: 1.0\nName: foo\nVersion: 1.0.0\n",
+        )?;
+
+        // A `.dist-info` directory with a nonstandard casing.
+        let dist_info = env.site_packages().join("bar-2.0.0.Dist-Info");
+        fs::create_dir_all(&dist_info)?;
+        fs::write(
+            dist_info.join("METADATA"),
+            "Metadata-Version: 2.1\nName: bar\nVersion: 2.0.0\n",
+        )?;
+
+        let site_packages = env.index()?;
+        let foo = site_packages.get_packages(&name("foo"));
+        assert_eq!(foo.len(), 1);
+        assert_eq!(foo[0].version().to_string(), "1.0.0");
+        let bar = site_packages.get_packages(&name("bar"));
+        assert_eq!(bar.len(), 1);
+        assert_eq!(bar[0].version().to_string(), "2.0.0");
+        Ok(())
+    }
+
+    #[test]
+    fn to_requirements() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &["idna>=2.8"])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("idna", "3.7", &[])?;
+
+        let project = env.root.path().join("project");
+        fs::create_dir_all(project.join("src").join("foo"))?;
+        install_editable(
+            &env.site_packages(),
+            "foo",
+            "0.1.0",
+            &project,
+            &project.join("src"),
+        )?;
+
+        let site_packages = env.index()?;
+
+        // Duplicate packages resolve to the first distribution on `sys.path`.
+        let requirements = site_packages.to_requirements(true);
+        let url = url::Url::from_directory_path(&project).unwrap();
+        assert_eq!(
+            requirements
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "anyio==4.3.0".to_string(),
+                format!("foo @ {url}"),
+                "idna==3.6".to_string(),
+            ]
+        );
+        assert!(matches!(
+            requirements[1].source,
+            RequirementSource::Directory {
+                editable: Some(true),
+                ..
+            }
+        ));
+
+        // Editable installs can be excluded.
+        let requirements = site_packages.to_requirements(false);
+        assert_eq!(
+            requirements
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["anyio==4.3.0".to_string(), "idna==3.6".to_string()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_interpreter_missing_site_packages() -> Result<()> {
+        let env = MockEnvironment::split()?;
+        install(&env.platlib(), "idna", "3.6", &[])?;
+
+        // A missing `purelib` directory is skipped, without skipping the `platlib` directory.
+        fs::remove_dir_all(env.site_packages())?;
+        let site_packages = env.index()?;
+        assert_eq!(
+            site_packages
+                .iter()
+                .map(|dist| dist.name().to_string())
+                .collect::<Vec<_>>(),
+            vec!["idna"]
+        );
+
+        // A `purelib` that can't be read as a directory is an error.
+        fs::write(env.site_packages(), "")?;
+        assert!(env.index().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn from_interpreter_filtered() -> Result<()> {
+        let env = MockEnvironment::split()?;
+        env.install("anyio", "4.3.0", &[])?;
+        install(&env.platlib(), "idna", "3.6", &[])?;
+
+        let interpreter = env.interpreter()?;
+        let names = |site_packages: &SitePackages| {
+            let mut names = site_packages
+                .iter()
+                .map(|dist| dist.name().to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            names(&SitePackages::from_interpreter(&interpreter)?),
+            vec!["anyio", "idna"]
+        );
+
+        // Skip the second directory, as if it were a shared system `site-packages`.
+        let site_packages =
+            SitePackages::from_interpreter_filtered(&interpreter, &[env.platlib().join(".")])?;
+        assert_eq!(names(&site_packages), vec!["anyio"]);
+
+        // The skipped directory is reported as such, but an unfiltered index skips nothing.
+        assert_eq!(site_packages.skipped_sites(), [env.platlib()]);
+        assert!(
+            SitePackages::from_interpreter(&interpreter)?
+                .skipped_sites()
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn iter_by_site() -> Result<()> {
+        let system = MockEnvironment::new()?;
+        system.install("anyio", "4.0.0", &[])?;
+        system.install("sniffio", "1.3.1", &[])?;
+
+        let venv = MockEnvironment::split()?;
+        venv.install("anyio", "4.3.0", &[])?;
+        install(&venv.platlib(), "idna", "3.6", &[])?;
+
+        let site_packages = SitePackages::overlay(system.index()?, venv.index()?);
+        let sites = site_packages
+            .iter_by_site()
+            .map(|(site, dists)| {
+                let mut names = dists
+                    .iter()
+                    .map(|dist| format!("{}=={}", dist.name(), dist.version()))
+                    .collect::<Vec<_>>();
+                names.sort();
+                (site.to_path_buf(), names)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sites,
+            vec![
+                (venv.site_packages(), vec!["anyio==4.3.0".to_string()]),
+                (venv.platlib(), vec!["idna==3.6".to_string()]),
+                (system.site_packages(), vec!["sniffio==1.3.1".to_string()]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reconcile() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+        env.install("idna", "3.6", &[])?;
+        let mut site_packages = env.index()?;
+
+        let names =
+            |dists: &[InstalledDist]| dists.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        // Without any changes, the index is left as-is.
+        assert!(site_packages.reconcile()?.is_empty());
+
+        // A package is installed, and another uninstalled, by another tool.
+        env.install("sniffio", "1.3.1", &[])?;
+        fs::remove_dir_all(env.site_packages().join("idna-3.6.dist-info"))?;
+        let report = site_packages.reconcile()?;
+        assert_eq!(names(&report.added), vec!["sniffio==1.3.1"]);
+        assert_eq!(names(&report.removed), vec!["idna==3.6"]);
+        assert!(site_packages.get_packages(&name("idna")).is_empty());
+        assert_eq!(site_packages.get_packages(&name("sniffio")).len(), 1);
+        assert!(site_packages.reconcile()?.is_empty());
+
+        // A package is upgraded.
+        fs::remove_dir_all(env.site_packages().join("anyio-4.3.0.dist-info"))?;
+        env.install("anyio", "4.4.0", &[])?;
+        let report = site_packages.reconcile()?;
+        assert_eq!(names(&report.added), vec!["anyio==4.4.0"]);
+        assert_eq!(names(&report.removed), vec!["anyio==4.3.0"]);
+        assert_eq!(
+            site_packages.version_of(&name("anyio")),
+            Some(&Version::new([4, 4, 0]))
+        );
+
+        // The result matches a fresh scan.
+        let mut reconciled = site_packages.dist_info_paths();
+        reconciled.sort();
+        assert_eq!(reconciled, env.index()?.dist_info_paths());
+        Ok(())
+    }
+
+    #[test]
+    fn reconcile_preserves_precedence() -> Result<()> {
+        let env = MockEnvironment::split()?;
+        install(&env.platlib(), "anyio", "4.3.0", &[])?;
+        let mut site_packages = env.index()?;
+
+        // A distribution that appears in an earlier `site-packages` directory takes precedence.
+        env.install("anyio", "4.4.0", &[])?;
+        let report = site_packages.reconcile()?;
+        assert_eq!(report.added.len(), 1);
+        assert!(report.removed.is_empty());
+        assert_eq!(
+            site_packages.version_of(&name("anyio")),
+            Some(&Version::new([4, 4, 0]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_pythonpath() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+        env.install("idna", "3.4", &[])?;
+
+        let vendored = env.root.path().join("vendored");
+        let extra = env.root.path().join("extra");
+        install(&vendored, "idna", "3.6", &[])?;
+        install(&extra, "idna", "3.7", &[])?;
+        install(&extra, "sniffio", "1.3.1", &[])?;
+
+        let pythonpath = std::env::join_paths([
+            vendored.clone(),
+            PathBuf::new(),
+            env.root.path().join("missing"),
+            extra.clone(),
+            vendored.clone(),
+        ])?;
+        let site_packages =
+            SitePackages::from_pythonpath(&env.interpreter()?, pythonpath.to_str().unwrap())?;
+
+        // The `PYTHONPATH` entries take precedence over `site-packages`, in order.
+        assert_eq!(
+            site_packages
+                .get_packages(&name("idna"))
+                .iter()
+                .map(|dist| dist.version().to_string())
+                .collect::<Vec<_>>(),
+            vec!["3.6", "3.7", "3.4"]
+        );
+        assert_eq!(
+            site_packages.version_of(&name("sniffio")),
+            Some(&Version::from_str("1.3.1")?)
+        );
+        assert_eq!(
+            site_packages.version_of(&name("anyio")),
+            Some(&Version::from_str("4.3.0")?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_paths() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+
+        // Install packages into directories outside the interpreter's `site-packages`.
+        let vendored = env.root.path().join("vendored");
+        let extra = env.root.path().join("extra");
+        install(&vendored, "idna", "3.6", &[])?;
+        install(&extra, "idna", "3.7", &[])?;
+        install(&extra, "sniffio", "1.3.1", &[])?;
+
+        let interpreter = env.interpreter()?;
+        let versions = |site_packages: &SitePackages| {
+            let mut versions = site_packages
+                .iter()
+                .map(|dist| format!("{}=={}", dist.name(), dist.version()))
+                .collect::<Vec<_>>();
+            versions.sort();
+            versions
+        };
+
+        // Only the given directories are scanned, in order.
+        let site_packages = SitePackages::from_paths(
+            &interpreter,
+            &[
+                vendored.clone(),
+                env.root.path().join("missing"),
+                extra.clone(),
+            ],
+        )?;
+        assert_eq!(
+            versions(&site_packages),
+            vec!["idna==3.6", "idna==3.7", "sniffio==1.3.1"]
+        );
+        assert_eq!(
+            site_packages.get_packages(&name("idna"))[0]
+                .version()
+                .to_string(),
+            "3.6"
+        );
+
+        let site_packages = SitePackages::from_paths(&interpreter, &[vendored])?;
+        assert_eq!(versions(&site_packages), vec!["idna==3.6"]);
+
+        // The interpreter's own `site-packages` is unaffected.
+        assert_eq!(versions(&env.index()?), vec!["anyio==4.3.0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn exclusive_files() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+
+        // Two distributions that share a `pkgutil`-style namespace package.
+        for (package, module) in [("ns_a", "a"), ("ns_b", "b")] {
+            env.install(package, "1.0.0", &[])?;
+            fs::create_dir_all(site_packages.join("ns").join(module))?;
+            fs::write(site_packages.join("ns").join("__init__.py"), "")?;
+            fs::write(
+                site_packages.join("ns").join(module).join("__init__.py"),
+                "",
+            )?;
+            let dist_info = format!("{package}-1.0.0.dist-info");
+            fs::write(
+                site_packages.join(&dist_info).join("RECORD"),
                 format!(
-                    "The package `{package}` has multiple installed distributions: {}",
-                    paths.iter().fold(String::new(), |acc, path| acc
-                        + &format!("\n  - {}", path.display()))
+                    "ns/__init__.py,,\nns/{module}/__init__.py,,\n{dist_info}/METADATA,,\n{dist_info}/RECORD,,\n"
+                ),
+            )?;
+        }
+
+        let index = env.index()?;
+        assert_eq!(
+            index.exclusive_files(&name("ns-a"))?,
+            vec![
+                site_packages.join("ns").join("a").join("__init__.py"),
+                site_packages.join("ns_a-1.0.0.dist-info").join("METADATA"),
+                site_packages.join("ns_a-1.0.0.dist-info").join("RECORD"),
+            ]
+        );
+
+        // Once the other distribution is removed, the shared file is owned exclusively.
+        let mut index = index;
+        index.remove_packages(&name("ns-b"));
+        assert!(
+            index
+                .exclusive_files(&name("ns-a"))?
+                .contains(&site_packages.join("ns").join("__init__.py"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn has_stdlib_shadows() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        let stdlib = env.root.path().join("lib").join("python3.12");
+        fs::create_dir_all(stdlib.join("email"))?;
+        fs::write(stdlib.join("typing.py"), "")?;
+        fs::write(stdlib.join("email").join("__init__.py"), "")?;
+
+        // Write the given files to `site-packages`, and list them in the package's `RECORD`.
+        let install = |package: &str, files: &[&str]| -> Result<()> {
+            env.install(package, "1.0.0", &[])?;
+            let dist_info = format!("{package}-1.0.0.dist-info");
+            let mut record = format!("{dist_info}/METADATA,,\n{dist_info}/RECORD,,\n");
+            for file in files {
+                writeln!(record, "{file},,")?;
+            }
+            fs::write(site_packages.join(&dist_info).join("RECORD"), record)?;
+            Ok(())
+        };
+
+        // A module nested within a package doesn't shadow the standard library.
+        install("anyio", &["anyio/__init__.py", "anyio/typing.py"])?;
+        assert!(!env.index()?.has_stdlib_shadows());
+
+        // A top-level module does, as does a top-level package.
+        install("typing", &["typing.py"])?;
+        assert!(env.index()?.has_stdlib_shadows());
+        fs::remove_dir_all(site_packages.join("typing-1.0.0.dist-info"))?;
+        install("backport", &["email/__init__.py", "email/parser.py"])?;
+        assert!(env.index()?.has_stdlib_shadows());
+        Ok(())
+    }
+
+    #[test]
+    fn owners_of_module() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+
+        // Write the given files to `site-packages`, and list them in the package's `RECORD`.
+        let install = |package: &str, files: &[&str]| -> Result<()> {
+            env.install(package, "1.0.0", &[])?;
+            let dist_info = format!("{package}-1.0.0.dist-info");
+            let mut record = format!("{dist_info}/METADATA,,\n{dist_info}/RECORD,,\n");
+            for file in files {
+                writeln!(record, "{file},,")?;
+            }
+            fs::write(site_packages.join(&dist_info).join("RECORD"), record)?;
+            Ok(())
+        };
+
+        install(
+            "anyio",
+            &[
+                "anyio/__init__.py",
+                "anyio/abc/__init__.py",
+                "anyio/abc/tasks.py",
+                "anyio/_core/_speedups.cpython-312-x86_64-linux-gnu.so",
+            ],
+        )?;
+        // A package that installs a submodule into another package.
+        install("plugin", &["anyio/abc/plugin.py"])?;
+        // Two distributions that share a namespace package.
+        install("protobuf", &["google/protobuf/__init__.py"])?;
+        install("api", &["google/api/__init__.py", "google/api/http.py"])?;
+        let site_packages = env.index()?;
+
+        let owners = |module: &str| {
+            site_packages
+                .owners_of_module(module)
+                .into_iter()
+                .map(|dist| dist.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Regular packages, modules, and extension modules are resolved to their owner.
+        assert_eq!(owners("anyio"), vec!["anyio"]);
+        assert_eq!(owners("anyio.abc"), vec!["anyio"]);
+        assert_eq!(owners("anyio.abc.tasks"), vec!["anyio"]);
+        assert_eq!(owners("anyio._core._speedups"), vec!["anyio"]);
+        assert_eq!(owners("anyio.abc.plugin"), vec!["plugin"]);
+        assert_eq!(owners("google.api.http"), vec!["api"]);
+
+        // A namespace package is owned by every distribution that contributes to it.
+        let mut google = owners("google");
+        google.sort();
+        assert_eq!(google, vec!["api", "protobuf"]);
+
+        // Unknown and malformed modules have no owner.
+        assert!(owners("anyio.missing").is_empty());
+        assert!(owners("anyio..abc").is_empty());
+        assert!(owners("").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn group_by_top_level_conflict() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+
+        // Write the given files to `site-packages`, and list them in the package's `RECORD`.
+        let install = |package: &str, files: &[&str]| -> Result<()> {
+            env.install(package, "1.0.0", &[])?;
+            let dist_info = format!("{package}-1.0.0.dist-info");
+            let mut record = format!("{dist_info}/METADATA,,\n{dist_info}/RECORD,,\n");
+            for file in files {
+                let path = site_packages.join(file);
+                fs::create_dir_all(path.parent().unwrap())?;
+                fs::write(path, "")?;
+                writeln!(record, "{file},,")?;
+            }
+            fs::write(site_packages.join(&dist_info).join("RECORD"), record)?;
+            Ok(())
+        };
+
+        // Two distinct packages that both ship a `tests` package.
+        install("foo", &["foo/__init__.py", "tests/__init__.py", "foo.pth"])?;
+        install(
+            "bar",
+            &[
+                "bar.py",
+                "tests/__init__.py",
+                "tests/test_bar.py",
+                "bar.pth",
+            ],
+        )?;
+        // Two packages that share a namespace package, but not any modules within it.
+        install("ns_a", &["ns/a/__init__.py"])?;
+        install(
+            "ns_b",
+            &["ns/b.py", "ns/_native.cpython-312-x86_64-linux-gnu.so"],
+        )?;
+        // A package that ships a module into the same namespace as another.
+        install("ns_c", &["ns/b.py"])?;
+
+        let index = env.index()?;
+        let conflicts = index
+            .group_by_top_level_conflict()
+            .into_iter()
+            .map(|(module, distributions)| {
+                let mut names = distributions
+                    .iter()
+                    .map(|distribution| distribution.name().to_string())
+                    .collect::<Vec<_>>();
+                names.sort();
+                format!("{module}: {}", names.join(", "))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(conflicts, vec!["ns.b: ns-b, ns-c", "tests: bar, foo"]);
+        Ok(())
+    }
+
+    #[test]
+    fn installed_at() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("foo", "1.0.0", &[])?;
+        env.install("bar", "1.0.0", &[])?;
+        env.install("baz", "1.0.0", &[])?;
+        env.install("baz", "2.0.0", &[])?;
+
+        let site_packages = env.index()?;
+        assert!(site_packages.installed_at(&name("foo")).is_some());
+        assert!(site_packages.installed_at(&name("qux")).is_none());
+
+        // Inject timestamps rather than relying on the filesystem, whose resolution may be too
+        // coarse to distinguish consecutive installs.
+        let timestamps = [
+            ("foo-1.0.0.dist-info", 30),
+            ("bar-1.0.0.dist-info", 10),
+            ("baz-1.0.0.dist-info", 40),
+            ("baz-2.0.0.dist-info", 20),
+        ]
+        .into_iter()
+        .map(|(dist_info, seconds)| {
+            (
+                env.site_packages().join(dist_info),
+                Timestamp::from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds)),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+        let installed_at = |package: &PackageName| {
+            site_packages.installed_at_with(package, |path| timestamps.get(path).copied())
+        };
+
+        let mut packages = vec![name("foo"), name("bar"), name("baz")];
+        packages.sort_by_key(installed_at);
+        assert_eq!(packages, vec![name("bar"), name("foo"), name("baz")]);
+
+        // With multiple distributions installed, the most recent wins.
+        assert_eq!(
+            installed_at(&name("baz")),
+            Some(Timestamp::from(
+                std::time::UNIX_EPOCH + std::time::Duration::from_secs(40)
+            ))
+        );
+        assert!(installed_at(&name("qux")).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn scripts() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let site_packages = env.site_packages();
+        let bin = env.root.path().join("bin");
+        env.install("black", "24.2.0", &[])?;
+        env.install("idna", "3.6", &[])?;
+        fs::write(
+            site_packages
+                .join("black-24.2.0.dist-info")
+                .join("entry_points.txt"),
+            indoc::indoc! {"
+                [console_scripts]
+                black = black:patched_main
+                blackd = blackd:patched_main [d]
+
+                [gui_scripts]
+                black-gui = black.gui:main
+            "},
+        )?;
+        fs::write(
+            site_packages.join("black-24.2.0.dist-info").join("RECORD"),
+            indoc::indoc! {"
+                ../bin/black,,
+                ../bin/blackd,,
+                black-24.2.0.dist-info/METADATA,,
+                black-24.2.0.dist-info/RECORD,,
+            "},
+        )?;
+        fs::create_dir_all(&bin)?;
+        fs::write(bin.join("black"), "")?;
+
+        assert_eq!(
+            env.index()?.scripts(),
+            vec![
+                (name("black"), "black".to_string(), bin.join("black")),
+                (
+                    name("black"),
+                    "black-gui".to_string(),
+                    bin.join("black-gui")
+                ),
+                (name("black"), "blackd".to_string(), bin.join("blackd")),
+            ]
+        );
+
+        // Missing scripts are reported once, whether or not they're listed in the `RECORD`.
+        let missing = env
+            .diagnostics()?
+            .into_iter()
+            .filter(|diagnostic| {
+                matches!(
+                    diagnostic,
+                    SitePackagesDiagnostic::MissingInstalledFile { .. }
                 )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            missing,
+            vec![
+                SitePackagesDiagnostic::MissingInstalledFile {
+                    package: name("black"),
+                    path: bin.join("blackd"),
+                },
+                SitePackagesDiagnostic::MissingInstalledFile {
+                    package: name("black"),
+                    path: bin.join("black-gui"),
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn scripts_in_other_sites() -> Result<()> {
+        let system = MockEnvironment::new()?;
+        let system_bin = system.root.path().join("bin");
+        for package in ["black", "ruff"] {
+            system.install(package, "1.0.0", &[])?;
+            fs::write(
+                system
+                    .site_packages()
+                    .join(format!("{package}-1.0.0.dist-info"))
+                    .join("entry_points.txt"),
+                format!("[console_scripts]\n{package} = {package}:main\n"),
+            )?;
+        }
+        fs::write(
+            system
+                .site_packages()
+                .join("black-1.0.0.dist-info")
+                .join("RECORD"),
+            indoc::indoc! {"
+                ../bin/black,,
+                black-1.0.0.dist-info/METADATA,,
+                black-1.0.0.dist-info/RECORD,,
+            "},
+        )?;
+        fs::create_dir_all(&system_bin)?;
+        fs::write(system_bin.join("black"), "")?;
+
+        let venv = MockEnvironment::new()?;
+        venv.install("idna", "3.6", &[])?;
+
+        // The script is located in the base environment's scripts directory, via the `RECORD`,
+        // while scripts that can't be located outside the environment's own site are skipped.
+        let site_packages = SitePackages::overlay(system.index()?, venv.index()?);
+        assert_eq!(
+            site_packages.scripts(),
+            vec![(name("black"), "black".to_string(), system_bin.join("black"))]
+        );
+
+        let interpreter = venv.interpreter()?;
+        let diagnostics = site_packages.with_file_checks(true).diagnostics(
+            &interpreter.resolver_marker_environment(),
+            interpreter.tags()?,
+        )?;
+        assert!(
+            !diagnostics.iter().any(|diagnostic| matches!(
+                diagnostic,
+                SitePackagesDiagnostic::MissingInstalledFile { .. }
+            )),
+            "{diagnostics:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn get_normalized() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        env.install("foo_bar", "1.0.0", &[])?;
+        let site_packages = env.index()?;
+
+        for raw in [
+            "foo-bar",
+            "foo_bar",
+            "Foo.Bar",
+            "FOO_BAR",
+            "foo--bar",
+            "foo._-bar",
+        ] {
+            let packages = site_packages.get_normalized(raw);
+            assert_eq!(packages.len(), 1, "`{raw}` should match `foo-bar`");
+            assert_eq!(packages[0].name(), &name("foo-bar"));
+        }
+        assert!(site_packages.get_normalized("foobar").is_empty());
+        assert!(site_packages.get_normalized("-foo-bar").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn get_urls_relative_editable() -> Result<()> {
+        for label in [
+            "file:project",
+            "file:./project/",
+            "file://./project",
+            "absolute",
+            "absolute-no-slash",
+        ] {
+            let env = MockEnvironment::new()?;
+            let project = env.root.path().join("project");
+            fs::create_dir_all(&project)?;
+            install_editable(&env.site_packages(), "foo", "0.1.0", &project, &project)?;
+            let recorded = match label {
+                "absolute" => url::Url::from_directory_path(&project).unwrap().to_string(),
+                "absolute-no-slash" => url::Url::from_file_path(&project).unwrap().to_string(),
+                relative => relative.to_string(),
+            };
+            fs::write(
+                env.site_packages()
+                    .join("foo-0.1.0.dist-info")
+                    .join("direct_url.json"),
+                format!(r#"{{"url": "{recorded}", "dir_info": {{"editable": true}}}}"#),
+            )?;
+
+            let site_packages = env.index()?;
+            for requested in [
+                url::Url::from_directory_path(&project).unwrap(),
+                url::Url::from_file_path(&project).unwrap(),
+            ] {
+                assert_eq!(
+                    site_packages
+                        .get_urls(&DisplaySafeUrl::from(requested))
+                        .len(),
+                    1,
+                    "{label}"
+                );
             }
+
+            // The recorded URL is resolved against the environment root.
+            let [dist] = site_packages.get_packages(&name("foo"))[..] else {
+                panic!("{label}");
+            };
+            let InstalledDistKind::Url(dist) = &dist.kind else {
+                panic!("{label}");
+            };
+            let DirectUrl::LocalDirectory { url, .. } = dist.direct_url.as_ref() else {
+                panic!("{label}");
+            };
+            assert_eq!(
+                normalize_path(&url::Url::parse(url)?.to_file_path().unwrap()),
+                project,
+                "{label}"
+            );
         }
+        Ok(())
     }
 
-    /// Returns `true` if the [`PackageName`] is involved in this diagnostic.
-    fn includes(&self, name: &PackageName) -> bool {
-        match self {
-            Self::MetadataUnavailable { package, .. } => name == package,
-            Self::TagsUnavailable { package, .. } => name == package,
-            Self::IncompatiblePythonVersion { package, .. } => name == package,
-            Self::IncompatiblePlatform { package } => name == package,
-            Self::MissingDependency { package, .. } => name == package,
-            Self::IncompatibleDependency {
-                package,
-                requirement,
-                ..
-            } => name == package || &requirement.name == name,
-            Self::DuplicatePackage { package, .. } => name == package,
+    #[test]
+    fn remove_editable_package() -> Result<()> {
+        let env = MockEnvironment::new()?;
+        let project = env.root.path().join("project");
+        fs::create_dir_all(project.join("foo"))?;
+        install_editable(&env.site_packages(), "foo", "0.1.0", &project, &project)?;
+        env.install("idna", "3.6", &[])?;
+
+        let mut site_packages = env.index()?;
+        let url = DisplaySafeUrl::from(url::Url::from_directory_path(&project).unwrap());
+        assert_eq!(site_packages.get_urls(&url).len(), 1);
+
+        let removed = site_packages.remove_packages(&name("foo"));
+        assert_eq!(removed.len(), 1);
+        assert!(site_packages.get_urls(&url).is_empty());
+        assert!(site_packages.get_packages(&name("foo")).is_empty());
+        assert!(site_packages.by_url.is_empty());
+        assert!(!site_packages.by_name.contains_key(&name("foo")));
+
+        // Removing the package again is a no-op.
+        assert!(site_packages.remove_packages(&name("foo")).is_empty());
+        assert_eq!(site_packages.get_packages(&name("idna")).len(), 1);
+        Ok(())
+    }
+
+    /// Create a [`Resolution`] that pins each of the given installed distributions.
+    fn resolution(site_packages: &SitePackages) -> Resolution {
+        let mut graph = petgraph::graph::DiGraph::new();
+        graph.add_node(Node::Root);
+        for dist in site_packages.iter() {
+            graph.add_node(Node::Dist {
+                dist: ResolvedDist::Installed {
+                    dist: Arc::new(dist.clone()),
+                },
+                hashes: HashDigests::empty(),
+                install: true,
+            });
         }
+        Resolution::new(graph)
     }
-}
 
-impl InstalledPackagesProvider for SitePackages {
-    fn iter(&self) -> impl Iterator<Item = &InstalledDist> {
-        self.iter()
+    fn validate(env: &MockEnvironment, lock: &MockEnvironment) -> Result<Vec<Vec<String>>> {
+        let site_packages = env.index()?;
+        let lock = resolution(&lock.index()?);
+        let validation = site_packages.validate_against_lock(
+            &lock,
+            &ConfigSettings::default(),
+            &PackageConfigSettings::default(),
+            &ExtraBuildRequires::default(),
+            &ExtraBuildVariables::default(),
+        )?;
+        let mut missing = validation
+            .missing
+            .iter()
+            .map(|dist| dist.name().to_string())
+            .collect::<Vec<_>>();
+        let mut extraneous = validation
+            .extraneous
+            .iter()
+            .map(|dist| dist.name().to_string())
+            .collect::<Vec<_>>();
+        let mut mismatched = validation
+            .mismatched
+            .iter()
+            .map(|(_, installed)| installed.to_string())
+            .collect::<Vec<_>>();
+        missing.sort();
+        extraneous.sort();
+        mismatched.sort();
+        Ok(vec![missing, extraneous, mismatched])
     }
 
-    fn get_packages(&self, name: &PackageName) -> Vec<&InstalledDist> {
-        self.get_packages(name)
+    #[test]
+    fn validate_against_lock() -> Result<()> {
+        let lock = MockEnvironment::new()?;
+        lock.install("anyio", "4.3.0", &[])?;
+        lock.install("idna", "3.6", &[])?;
+        lock.install("sniffio", "1.3.1", &[])?;
+
+        // An exact match.
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        assert!(validate(&env, &lock)?.iter().all(Vec::is_empty));
+
+        // A missing package.
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+        env.install("idna", "3.6", &[])?;
+        assert_eq!(
+            validate(&env, &lock)?,
+            vec![vec!["sniffio".to_string()], vec![], vec![]]
+        );
+
+        // An extraneous package.
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.3.0", &[])?;
+        env.install("idna", "3.6", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        env.install("httpx", "0.27.0", &[])?;
+        assert_eq!(
+            validate(&env, &lock)?,
+            vec![vec![], vec!["httpx".to_string()], vec![]]
+        );
+
+        // A mismatched version, including a local version that would satisfy `==3.6`.
+        let env = MockEnvironment::new()?;
+        env.install("anyio", "4.2.0", &[])?;
+        env.install("idna", "3.6+local", &[])?;
+        env.install("sniffio", "1.3.1", &[])?;
+        assert_eq!(
+            validate(&env, &lock)?,
+            vec![
+                vec![],
+                vec![],
+                vec!["anyio==4.2.0".to_string(), "idna==3.6+local".to_string()]
+            ]
+        );
+
+        Ok(())
     }
 }
@@ -81,25 +81,91 @@ pub struct Options {
     /// to ensure that the project is rebuilt whenever the `requirements.txt` file is modified (in
     /// addition to watching the `pyproject.toml`).
     ///
+    /// Specifying `cache-keys` replaces the defaults entirely, rather than extending them. For example,
+    /// to rebuild only when the `pyproject.toml` changes (ignoring `setup.py`, `setup.cfg`, and `src`),
+    /// you can specify `cache-keys = [{ file = "pyproject.toml" }]`.
+    ///
     /// Globs are supported, following the syntax of the [`glob`](https://docs.rs/glob/0.3.1/glob/struct.Pattern.html)
     /// crate. For example, to invalidate the cache whenever a `.toml` file in the project directory
     /// or any of its subdirectories is modified, you can specify `cache-keys = [{ file = "**/*.toml" }]`.
+    /// A `**` matches across any number of nested directories, as in `src/**/*.py`. As in a `.gitignore`
+    /// file, a glob with wildcards only in its last component (like `*.toml` or `src/*.py`) matches that
+    /// component at any depth; to only match within a single directory, add `anchored = true` to the
+    /// glob, as in `cache-keys = [{ file = "src/*.py", anchored = true }]`, which matches `src/main.py`
+    /// but not `src/pkg/module.py`.
+    /// To exclude generated files (like build outputs) that are ignored by Git, add
+    /// `respect-gitignore = true` to a glob, as in `cache-keys = [{ file = "**/*.py", respect-gitignore = true }]`;
+    /// any `.gitignore` files in the project (including nested `.gitignore` files) are then respected when
+    /// expanding the glob.
+    /// Python bytecode (`*.pyc` and `*.pyo` files, and the contents of `__pycache__` directories) is
+    /// excluded from globs by default, since it's rewritten on import; to include it, add
+    /// `include-bytecode = true` to the glob, as in `cache-keys = [{ file = "**/*", include-bytecode = true }]`.
     /// Note that the use of globs can be expensive, as uv may need to walk the filesystem to
     /// determine whether any files have changed.
     ///
+    /// By default, files are tracked by their modification times. To instead track their contents
+    /// (e.g., if a file is often rewritten without changes, as by a code generator), add `hash = true`
+    /// to the key, as in `cache-keys = [{ file = "requirements.txt", hash = true }]`, which uses a fast,
+    /// non-cryptographic hash. To use a cryptographic hash, specify the algorithm instead, as in
+    /// `{ file = "requirements.txt", hash = "sha256" }` (`"blake3"` is also supported).
+    ///
+    /// To track only a single value within a file (e.g., a version string in an `__init__.py`), specify
+    /// a regular expression via `pattern`, as in
+    /// `cache-keys = [{ file = "src/foo/__init__.py", pattern = '__version__ = "(.+)"' }]`. Only the first
+    /// capture group of the first match (or the entire match, if the pattern has no capture groups) is
+    /// included in the cache key, such that unrelated edits to the file don't invalidate the cache. If the
+    /// pattern doesn't match, the key is ignored.
+    ///
+    /// Directory keys (like `{ dir = "src" }`) invalidate the cache whenever the directory is created or
+    /// removed, or whenever an entry is added to, removed from, or renamed within it (as reflected in the
+    /// directory's own timestamp). To only track the presence of the directory, as in the default
+    /// `src` key, specify `{ dir = "src", entries = false }`.
+    ///
     /// Cache keys can also include version control information. For example, if a project uses
     /// `setuptools_scm` to read its version from a Git commit, you can specify `cache-keys = [{ git = { commit = true }, { file = "pyproject.toml" }]`
     /// to include the current Git commit hash in the cache key (in addition to the
     /// `pyproject.toml`). Git tags are also supported via `cache-keys = [{ git = { commit = true, tags = true } }]`.
+    /// In a monorepo, you can specify `cache-keys = [{ git = true, path = "." }]` to instead include the
+    /// Git tree of the project directory at the current commit, which only changes when a commit modifies
+    /// the directory, such that commits elsewhere in the repository don't invalidate the cache. If the path
+    /// is the root of a separate repository (e.g., a vendored tree with its own history), its current
+    /// commit is included instead; multiple such keys may be specified.
     ///
     /// Cache keys can also include environment variables. For example, if a project relies on
     /// `MACOSX_DEPLOYMENT_TARGET` or other environment variables to determine its behavior, you can
     /// specify `cache-keys = [{ env = "MACOSX_DEPLOYMENT_TARGET" }]` to invalidate the cache
     /// whenever the environment variable changes.
     ///
+    /// Cache keys can also include the marker environment of the target interpreter. For example,
+    /// if a project's build backend produces different wheels depending on the platform or Python
+    /// version, you can specify `cache-keys = [{ markers = true }]` to include the `sys_platform`,
+    /// `platform_machine`, and `python_full_version` markers in the cache key.
+    ///
+    /// Cache keys can also include only the build-relevant tables of the `pyproject.toml` (like
+    /// `[build-system]`, `[project]`, and `[tool.setuptools]`), rather than its timestamp. For example,
+    /// you can specify `cache-keys = [{ pyproject = true }, { file = "setup.py" }]` to avoid rebuilding
+    /// the project when unrelated configuration (e.g., `[tool.ruff]`) is edited. If the `pyproject.toml`
+    /// can't be parsed, its timestamp is used instead.
+    ///
+    /// Cache keys can also include the `[build-system].requires` list of the `pyproject.toml`. For
+    /// example, you can specify `cache-keys = [{ build-requires = true }, { file = "setup.py" }]` to
+    /// rebuild the project whenever a build requirement (e.g., a pinned build backend) changes.
+    ///
+    /// Cache keys can also include the identity of the machine on which the project is built. For
+    /// example, if the cache is shared between machines whose clocks may be skewed (e.g., on a network
+    /// drive), you can specify `cache-keys = [{ file = "pyproject.toml" }, { host = true }]` to rebuild
+    /// the project whenever it's built on a different machine, rather than trusting timestamps recorded
+    /// elsewhere. Only a digest of the hostname is recorded.
+    ///
     /// Cache keys only affect the project defined by the `pyproject.toml` in which they're
     /// specified (as opposed to, e.g., affecting all members in a workspace), and all paths and
-    /// globs are interpreted as relative to the project directory.
+    /// globs are interpreted as relative to the directory containing the `pyproject.toml` (or
+    /// `uv.toml`) that defines them. If a source tree doesn't contain either file (as in some
+    /// nonstandard layouts), the nearest one in a parent directory is used instead; the search stops at
+    /// a directory containing a `setup.py` or `setup.cfg`, or at the root of the Git repository.
+    ///
+    /// Cache keys may also be specified at the top level of a `uv.toml` file in the project
+    /// directory, in which case they take precedence over any `cache-keys` in the `pyproject.toml`.
     #[option(
         default = r#"[{ file = "pyproject.toml" }, { file = "setup.py" }, { file = "setup.cfg" }]"#,
         value_type = "list[dict]",
@@ -728,6 +728,13 @@ impl EnvVars {
     #[attr_added_in("0.1.16")]
     pub const SHELL: &'static str = "SHELL";
 
+    /// The standard `SOURCE_DATE_EPOCH` variable for reproducible builds, as a Unix timestamp.
+    ///
+    /// When set, file timestamps older than `SOURCE_DATE_EPOCH` are clamped to it when
+    /// determining whether a local package needs to be rebuilt.
+    #[attr_added_in("0.9.8")]
+    pub const SOURCE_DATE_EPOCH: &'static str = "SOURCE_DATE_EPOCH";
+
     /// The standard `PWD` posix env var.
     #[attr_added_in("0.0.5")]
     pub const PWD: &'static str = "PWD";
@@ -2,6 +2,7 @@ use std::fmt::{Debug, Display, Formatter};
 use std::future::Future;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::Result;
 use rustc_hash::FxHashSet;
@@ -12,7 +13,7 @@ use uv_distribution_filename::DistFilename;
 use uv_distribution_types::{
     CachedDist, ConfigSettings, DependencyMetadata, DistributionId, ExtraBuildRequires,
     ExtraBuildVariables, IndexCapabilities, IndexLocations, InstalledDist, IsBuildBackendError,
-    PackageConfigSettings, Requirement, Resolution, SourceDist,
+    Name, PackageConfigSettings, Requirement, Resolution, SourceDist,
 };
 use uv_git::GitResolver;
 use uv_normalize::PackageName;
@@ -209,6 +210,50 @@ impl InstalledPackagesProvider for EmptyInstalledPackages {
     }
 }
 
+impl<P: InstalledPackagesProvider> InstalledPackagesProvider for Arc<P> {
+    fn iter(&self) -> impl Iterator<Item = &InstalledDist> {
+        P::iter(self)
+    }
+
+    fn get_packages(&self, name: &PackageName) -> Vec<&InstalledDist> {
+        P::get_packages(self, name)
+    }
+}
+
+/// An [`InstalledPackagesProvider`] that hides the given packages from another provider, e.g., to
+/// resolve as if they weren't installed.
+#[derive(Clone)]
+pub struct FilteredProvider<P> {
+    inner: P,
+    excluded: Arc<FxHashSet<PackageName>>,
+}
+
+impl<P: InstalledPackagesProvider> FilteredProvider<P> {
+    /// Wrap the given provider, excluding the given packages.
+    pub fn new(inner: P, excluded: impl IntoIterator<Item = PackageName>) -> Self {
+        Self {
+            inner,
+            excluded: Arc::new(excluded.into_iter().collect()),
+        }
+    }
+}
+
+impl<P: InstalledPackagesProvider> InstalledPackagesProvider for FilteredProvider<P> {
+    fn iter(&self) -> impl Iterator<Item = &InstalledDist> {
+        self.inner
+            .iter()
+            .filter(|dist| !self.excluded.contains(dist.name()))
+    }
+
+    fn get_packages(&self, name: &PackageName) -> Vec<&InstalledDist> {
+        if self.excluded.contains(name) {
+            Vec::new()
+        } else {
+            self.inner.get_packages(name)
+        }
+    }
+}
+
 /// [`anyhow::Error`]-like wrapper type for [`BuildDispatch`] method return values, that also makes
 /// [`IsBuildBackendError`] work as [`thiserror`] `#[source]`.
 ///
@@ -41,8 +41,9 @@ pub(crate) fn pip_check(
 
     report_target_environment(&environment, cache, printer)?;
 
-    // Build the installed index.
-    let site_packages = SitePackages::from_environment(&environment)?;
+    // Build the installed index. Unlike the post-install audit, `uv pip check` also verifies the
+    // installed files against each package's `RECORD`.
+    let site_packages = SitePackages::from_environment(&environment)?.with_file_checks(true);
     let packages: Vec<&InstalledDist> = site_packages.iter().collect();
 
     let s = if packages.len() == 1 { "" } else { "s" };
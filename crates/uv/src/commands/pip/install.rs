@@ -301,7 +301,8 @@ pub(crate) async fn pip_install(
     )?;
 
     // Determine the set of installed packages.
-    let site_packages = SitePackages::from_environment(&environment)?;
+    let site_packages =
+        SitePackages::from_environment(&environment)?.with_offline(client_builder.is_offline());
 
     // Check if the current environment satisfies the requirements.
     // Ideally, the resolver would be fast enough to let us remove this check. But right now, for large environments,
@@ -348,6 +349,9 @@ pub(crate) async fn pip_install(
             SatisfiesResult::Unsatisfied(requirement) => {
                 debug!("At least one requirement is not satisfied: {requirement}");
             }
+            SatisfiesResult::Indeterminate(requirement) => {
+                debug!("Unable to determine whether the requirements are satisfied: {requirement}");
+            }
         }
     }
 
@@ -477,6 +477,7 @@ pub(crate) async fn install(
             build_dispatch.config_settings_package(),
             build_dispatch.extra_build_requires(),
             build_dispatch.extra_build_variables(),
+            build_dispatch.build_isolation(),
             cache,
             venv,
             tags,
@@ -2249,7 +2249,8 @@ pub(crate) async fn update_environment(
     let tags = pip::resolution_tags(None, python_platform, interpreter)?;
 
     // Check if the current environment satisfies the requirements
-    let site_packages = SitePackages::from_environment(&venv)?;
+    let site_packages =
+        SitePackages::from_environment(&venv)?.with_offline(client_builder.is_offline());
     if reinstall.is_none()
         && upgrade.is_none()
         && source_trees.is_empty()
@@ -2291,6 +2292,9 @@ pub(crate) async fn update_environment(
             SatisfiesResult::Unsatisfied(requirement) => {
                 debug!("At least one requirement is not satisfied: {requirement}");
             }
+            SatisfiesResult::Indeterminate(requirement) => {
+                debug!("Unable to determine whether the requirements are satisfied: {requirement}");
+            }
         }
     }
 
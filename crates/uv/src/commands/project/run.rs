@@ -951,7 +951,8 @@ hint: If you are running a script with `{}` in the shebang, you may need to incl
     };
 
     // If necessary, create an environment for the ephemeral requirements or command.
-    let base_site_packages = SitePackages::from_interpreter(&base_interpreter)?;
+    let base_site_packages = SitePackages::from_interpreter(&base_interpreter)?
+        .with_offline(client_builder.is_offline());
     let requirements_env = match spec {
         None => None,
         Some(spec)
@@ -1383,6 +1384,12 @@ fn can_skip_ephemeral(
             );
             false
         }
+        Ok(SatisfiesResult::Indeterminate(requirement)) => {
+            debug!(
+                "Unable to determine whether the requirements are satisfied in the base environment: {requirement}"
+            );
+            false
+        }
         Err(err) => {
             debug!("Failed to check requirements against base environment: {err}");
             false
@@ -401,7 +401,8 @@ pub(crate) async fn install(
                 let tags = resolution_tags(None, python_platform.as_ref(), &interpreter)?;
 
                 // Check if the installed packages meet the requirements.
-                let site_packages = SitePackages::from_environment(environment.environment())?;
+                let site_packages = SitePackages::from_environment(environment.environment())?
+                    .with_offline(client_builder.is_offline());
                 if matches!(
                     site_packages.satisfies_requirements(
                         requirements.iter(),
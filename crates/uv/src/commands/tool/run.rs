@@ -975,7 +975,8 @@ async fn get_or_create_environment(
                     let tags = pip::resolution_tags(None, python_platform.as_ref(), &interpreter)?;
 
                     // Check if the installed packages meet the requirements.
-                    let site_packages = SitePackages::from_environment(environment.environment())?;
+                    let site_packages = SitePackages::from_environment(environment.environment())?
+                        .with_offline(client_builder.is_offline());
                     if matches!(
                         site_packages.satisfies_requirements(
                             requirements.iter(),
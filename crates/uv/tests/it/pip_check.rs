@@ -47,6 +47,58 @@ fn check_compatible_packages() -> Result<()> {
     Ok(())
 }
 
+/// Files that are listed in a package's `RECORD` but missing from disk are only reported by
+/// `uv pip check`, not by the audit that runs after `uv pip install`.
+#[test]
+fn check_missing_installed_file() -> Result<()> {
+    let context = TestContext::new("3.12");
+
+    uv_snapshot!(context
+        .pip_install()
+        .arg("iniconfig==2.0.0")
+        .arg("--strict"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    Resolved 1 package in [TIME]
+    Prepared 1 package in [TIME]
+    Installed 1 package in [TIME]
+     + iniconfig==2.0.0
+    "###
+    );
+
+    fs_err::remove_file(context.site_packages().join("iniconfig").join("py.typed"))?;
+
+    uv_snapshot!(context
+        .pip_install()
+        .arg("iniconfig==2.0.0")
+        .arg("--strict"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    Audited 1 package in [TIME]
+    "###
+    );
+
+    uv_snapshot!(context.filters(), context.pip_check(), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Checked 1 package in [TIME]
+    Found 1 incompatibility
+    The package `iniconfig` is broken or incomplete (`[SITE_PACKAGES]/iniconfig/py.typed` is listed in its `RECORD`, but doesn't exist). Consider reinstalling it with `--reinstall`.
+    "###
+    );
+
+    Ok(())
+}
+
 // requests 2.31.0 requires idna (<4,>=2.5)
 // this test force-installs idna 2.4 to trigger a failure.
 #[test]